@@ -26,16 +26,12 @@ impl FileResolver {
             .map(|file| file.expect("Unable to get path").path())
             .collect();
 
-        dbg!(&paths);
-
         // Read the contents of those files
         let contents: Vec<String> = paths
             .iter()
             .map(|file| fs::read_to_string(file).expect("Unable to read `{file}`"))
             .collect();
 
-        dbg!(&contents);
-
         Ok(Self { paths, contents })
     }
 }
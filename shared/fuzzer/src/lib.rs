@@ -53,6 +53,18 @@ fn rand_between(min: usize, max: usize, state: &mut XORShiftState) -> usize {
     result
 }
 
+/// How `Fuzzer` mutates its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MutationStrategy {
+    /// Overwrite a random byte with a random value. Can split a multi-byte char, so the result
+    /// may not be valid UTF-8, which is useful for stressing the compiler's input handling.
+    #[default]
+    ByteLevel,
+
+    /// Replace a whole char with a random valid char, so the result is always valid UTF-8
+    CharBoundaryAware,
+}
+
 #[derive(Debug)]
 pub struct Fuzzer {
     // Raw bytes into a file.
@@ -60,6 +72,9 @@ pub struct Fuzzer {
 
     // Random number seed
     rand_state: XORShiftState,
+
+    // How each mutation is made
+    strategy: MutationStrategy,
 }
 
 impl Fuzzer {
@@ -69,14 +84,32 @@ impl Fuzzer {
         Self {
             file_raw: file.into_bytes(),
             rand_state,
+            strategy: MutationStrategy::default(),
         }
     }
 
+    /// Mutate with `strategy` instead of `MutationStrategy::ByteLevel`
+    pub fn with_strategy(mut self, strategy: MutationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
     pub fn fuzz(&mut self) -> String {
+        String::from_utf8_lossy(&self.fuzz_to_bytes()).to_string()
+    }
+
+    // Same as `fuzz`, but keeps the mutated bytes as they are, even if they are not valid UTF-8
+    pub fn fuzz_to_bytes(&mut self) -> Vec<u8> {
         const MUT_COUNT: usize = 1;
 
         for _ in 0..MUT_COUNT
         {
+            if self.strategy == MutationStrategy::CharBoundaryAware
+            {
+                self.mutate_char();
+                continue;
+            }
+
             // Get position in file that we will mutate
             let file_begin = 0;
             let file_end = self.file_raw.len() - 1;
@@ -87,7 +120,71 @@ impl Fuzzer {
             self.file_raw[mut_pos] = rand_val;
         }
 
-        dbg!(String::from_utf8_lossy(&self.file_raw).to_string())
+        self.file_raw.clone()
+    }
+
+    // Replace a random char with a random valid char, leaving every other char as it was
+    fn mutate_char(&mut self) {
+        // Earlier byte-level mutations may have left invalid UTF-8 behind
+        let source = String::from_utf8_lossy(&self.file_raw).to_string();
+        let Some(last_char) = source.chars().count().checked_sub(1)
+        else
+        {
+            return;
+        };
+
+        let char_pos = rand_between(0, last_char, &mut self.rand_state);
+        let (mut_pos, old_char) = source.char_indices().nth(char_pos).unwrap();
+
+        // Surrogates are not valid chars, so draw again until we land outside of them
+        let rand_char = loop
+        {
+            let rand_val = rand_between(0, char::MAX as usize, &mut self.rand_state) as u32;
+            if let Some(rand_char) = char::from_u32(rand_val)
+            {
+                break rand_char;
+            }
+        };
+
+        let mut mutated = source[..mut_pos].to_string();
+        mutated.push(rand_char);
+        mutated.push_str(&source[mut_pos + old_char.len_utf8()..]);
+        self.file_raw = mutated.into_bytes();
+    }
+
+    // Shrink an input that makes the compiler fail into a smaller one that still does, using
+    // delta debugging. Chunks of `input` are removed for as long as `still_fails` holds, and the
+    // chunks get smaller once no chunk can be removed.
+    pub fn minimize(mut input: Vec<u8>, still_fails: impl Fn(&[u8]) -> bool) -> Vec<u8> {
+        // Number of chunks `input` is split into
+        let mut granularity = 2;
+
+        while input.len() >= 2
+        {
+            let chunk_len = input.len().div_ceil(granularity);
+
+            // Try removing each chunk, keeping the first removal that still fails
+            let reduced = (0..input.len()).step_by(chunk_len).find_map(|chunk_start| {
+                let chunk_end = (chunk_start + chunk_len).min(input.len());
+                let candidate = [&input[..chunk_start], &input[chunk_end..]].concat();
+
+                still_fails(&candidate).then_some(candidate)
+            });
+
+            match reduced
+            {
+                Some(candidate) =>
+                {
+                    input = candidate;
+                    granularity = (granularity - 1).max(2);
+                }
+                // Every chunk is already a single byte, so we can not do any better
+                None if granularity >= input.len() => break,
+                None => granularity = (granularity * 2).min(input.len()),
+            }
+        }
+
+        input
     }
 }
 
@@ -110,4 +207,73 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn fuzz_to_bytes_keeps_invalid_utf8() {
+        let source = "quiet quiet quiet";
+
+        // This seed mutates a byte into one that is not valid UTF-8 on its own
+        let seed       = 34;
+        let mut fuzzer = Fuzzer::new(source.to_string(), XORShiftState::new(seed));
+        let bytes      = fuzzer.fuzz_to_bytes();
+
+        assert!(String::from_utf8(bytes.clone()).is_err());
+        assert_eq!(bytes.len(), source.len());
+
+        let changed = bytes.iter().zip(source.bytes()).filter(|(new, old)| *new != old).count();
+        assert_eq!(changed, 1);
+    }
+
+    #[test]
+    fn char_boundary_mutation_keeps_valid_utf8() {
+        let source = "naïve ünïcode → λ x ✓";
+
+        for seed in 1..500
+        {
+            let mut fuzzer = Fuzzer::new(source.to_string(), XORShiftState::new(seed))
+                .with_strategy(MutationStrategy::CharBoundaryAware);
+            let mutated = String::from_utf8(fuzzer.fuzz_to_bytes()).unwrap();
+
+            // One whole char was replaced, which may have been drawn as itself
+            assert_eq!(mutated.chars().count(), source.chars().count());
+            let changed = mutated.chars().zip(source.chars()).filter(|(new, old)| new != old);
+            assert!(changed.count() <= 1);
+        }
+    }
+
+    #[test]
+    fn minimize_to_single_byte() {
+        let input = b"main :: () -> int { let x <- 5!; 0 }\n".to_vec();
+
+        let minimized = Fuzzer::minimize(input, |bytes| bytes.contains(&b'!'));
+
+        assert_eq!(minimized, b"!");
+    }
+
+    #[test]
+    fn minimize_keeps_every_needed_byte() {
+        let input = b"let x <- (1 + 2;\n".to_vec();
+
+        // Fails while there is an unclosed `(` before a `;`
+        let minimized = Fuzzer::minimize(input, |bytes| {
+            let paren = bytes.iter().position(|b| *b == b'(');
+            let semicolon = bytes.iter().position(|b| *b == b';');
+
+            matches!((paren, semicolon), (Some(paren), Some(semicolon)) if paren < semicolon)
+        });
+
+        assert_eq!(minimized, b"(;");
+    }
+
+    #[test]
+    fn fuzz_returns_mutated_source() {
+        let source = "quiet quiet quiet";
+
+        // The mutated source is handed back instead of being printed
+        let fuzzed = Fuzzer::new(source.to_string(), XORShiftState::new(1)).fuzz();
+        let bytes = Fuzzer::new(source.to_string(), XORShiftState::new(1)).fuzz_to_bytes();
+
+        assert_eq!(fuzzed, String::from_utf8_lossy(&bytes));
+        assert_ne!(fuzzed, source);
+    }
 }
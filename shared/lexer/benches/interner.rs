@@ -0,0 +1,21 @@
+//! Lexes an identifier heavy file, where most of the work is interning the same few names
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use lexer::lexer::Lexer;
+
+fn ident_heavy_source() -> String {
+    let line = "let total <- add(total, count) + add(index, count) * index;\n";
+
+    format!("main = {{\n{}}}\n", line.repeat(200))
+}
+
+fn lex_idents(c: &mut Criterion) {
+    let source = ident_heavy_source();
+
+    c.bench_function("lex identifier heavy file", |b| {
+        b.iter(|| Lexer::new("bench.az", &source).lex(false).unwrap())
+    });
+}
+
+criterion_group!(benches, lex_idents);
+criterion_main!(benches);
@@ -0,0 +1,71 @@
+//! Lexes a keyword heavy file, where most of the work is telling keywords apart from idents
+
+use std::collections::BTreeMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lexer::lexer::Lexer;
+use lexer::token::TokenKind;
+
+// The map based lookup the lexer used to build for every ident, kept to check the `match` against
+fn map_refined_or_ident(raw_token_content: &str) -> (TokenKind, bool) {
+    let reserved = BTreeMap::from_iter(TokenKind::keywords());
+
+    match reserved.get(raw_token_content)
+    {
+        Some(kind) => (*kind, true),
+        None => (TokenKind::Ident, false),
+    }
+}
+
+fn keyword_heavy_source() -> String {
+    let lines = "\
+        while true and done or false { if ready { break } elif waiting { continue } else { } }\n\
+        for index in 0..count { let flag :: bool <- true; let total :: int <- index as int; }\n\
+        match shape { circle => { let area :: float <- 1.0; } }\n\
+        match shape { square => { let label :: text <- name; } }\n";
+
+    format!("main :: ()\nmain = {{\n{}}}\n", lines.repeat(200))
+}
+
+// Panics unless every word in `source` lexes to the kind the old map gave it
+fn assert_matches_map_lookup(source: &str) {
+    let tokens = Lexer::new("bench.az", source).lex(false).unwrap();
+    let words = tokens.iter().filter(|tok| {
+        tok.get_raw_content().starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+    });
+
+    for tok in words
+    {
+        let expected = map_refined_or_ident(tok.get_raw_content());
+
+        assert_eq!(TokenKind::refined_or_ident(tok.get_raw_content()), expected);
+        assert_eq!(tok.get_token_kind(), expected.0, "for {:?}", tok.get_raw_content());
+    }
+}
+
+fn lex_keywords(c: &mut Criterion) {
+    let source = keyword_heavy_source();
+    assert_matches_map_lookup(&source);
+
+    c.bench_function("lex keyword heavy file", |b| {
+        b.iter(|| Lexer::new("bench.az", &source).lex(false).unwrap())
+    });
+
+    let words: Vec<&str> = source.split(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_')
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    c.bench_function("keyword lookup by match", |b| {
+        b.iter(|| {
+            words.iter().filter(|word| TokenKind::refined_or_ident(black_box(word)).1).count()
+        })
+    });
+    c.bench_function("keyword lookup by map", |b| {
+        b.iter(|| {
+            words.iter().filter(|word| map_refined_or_ident(black_box(word)).1).count()
+        })
+    });
+}
+
+criterion_group!(benches, lex_keywords);
+criterion_main!(benches);
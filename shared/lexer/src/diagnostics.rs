@@ -0,0 +1,522 @@
+//! Helpful API for reporting diagnostics in the Morehead Azalea Compiler.
+//!
+//! Every stage reports its errors through `report_error`, which either prints a pretty `ariadne`
+//! report or, for editors and other tools, collects a machine-readable `Diagnostic`.
+//!
+//! A single `ErrorCounter` is shared between the compiler stages so a garbage source file does
+//! not bury the user under hundreds of reports.
+
+use std::cell::{Cell, RefCell};
+use std::io::{IsTerminal, Write};
+use std::ops::Range;
+
+use ariadne::{Config, Label, Report, ReportKind, Source};
+use serde::Serialize;
+
+use crate::source_map::LineDirective;
+use crate::span::BOM;
+
+/// How diagnostics are printed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Pretty `ariadne` reports
+    #[default]
+    Human,
+
+    /// One JSON `Diagnostic` per line
+    Json,
+}
+
+/// When pretty reports are printed with colors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only use colors if stdout is a terminal
+    #[default]
+    Auto,
+
+    Always,
+
+    Never,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Machine-readable version of a compiler error report
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+    pub code: u32,
+    pub severity: Severity,
+    pub message: String,
+
+    /// Char offset of the error in the reported source. Tools get it from `line` and `col`, so
+    /// it is not printed.
+    #[serde(skip)]
+    pub offset: usize,
+}
+
+thread_local! {
+    static MESSAGE_FORMAT: Cell<MessageFormat> = const { Cell::new(MessageFormat::Human) };
+
+    static COLOR_CHOICE: Cell<ColorChoice> = const { Cell::new(ColorChoice::Auto) };
+
+    /// `Diagnostic`s collected while in `MessageFormat::Json` mode
+    static COLLECTED: RefCell<Vec<Diagnostic>> = const { RefCell::new(Vec::new()) };
+
+    /// Number of errors reported so far, in either `MessageFormat`
+    static ERRORS_REPORTED: Cell<usize> = const { Cell::new(0) };
+
+    /// Path and content of every file given to `register_source`, indexed by source id - 1
+    static SOURCES: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+
+    /// `#line` directives of every file given to `register_line_directives`, by path
+    static LINE_DIRECTIVES: RefCell<Vec<(String, Vec<LineDirective>)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Set how every following diagnostic is printed
+pub fn set_message_format(format: MessageFormat) {
+    MESSAGE_FORMAT.with(|cell| cell.set(format));
+}
+
+pub fn message_format() -> MessageFormat {
+    MESSAGE_FORMAT.with(|cell| cell.get())
+}
+
+/// Set when every following pretty report uses colors
+pub fn set_color_choice(choice: ColorChoice) {
+    COLOR_CHOICE.with(|cell| cell.set(choice));
+}
+
+pub fn color_choice() -> ColorChoice {
+    COLOR_CHOICE.with(|cell| cell.get())
+}
+
+/// `ariadne` settings for pretty reports, following `color_choice`
+fn report_config() -> Config {
+    let color = match color_choice()
+    {
+        ColorChoice::Auto => std::io::stdout().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+
+    Config::default().with_color(color)
+}
+
+/// Number of errors reported so far by this thread. Warnings are not counted.
+pub fn errors_reported() -> usize {
+    ERRORS_REPORTED.with(|count| count.get())
+}
+
+/// Move the collected `Diagnostic`s out, leaving none behind
+pub fn take_diagnostics() -> Vec<Diagnostic> {
+    COLLECTED.with(|collected| collected.take())
+}
+
+/// Print the collected `Diagnostic`s as JSON, one per line
+pub fn print_diagnostics() {
+    for diagnostic in take_diagnostics()
+    {
+        println!("{}", serde_json::to_string(&diagnostic).unwrap());
+    }
+}
+
+/// Run `f` without printing or keeping the diagnostics it reports, and without counting its
+/// errors. Useful to ask whether something compiles without telling the user about it.
+pub fn silenced<T>(f: impl FnOnce() -> T) -> T {
+    // Put everything back even if `f` panics
+    struct Restore {
+        format: MessageFormat,
+        errors_reported: usize,
+        collected: usize,
+    }
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            COLLECTED.with(|collected| collected.borrow_mut().truncate(self.collected));
+            ERRORS_REPORTED.with(|count| count.set(self.errors_reported));
+            set_message_format(self.format);
+        }
+    }
+
+    let _restore = Restore {
+        format: message_format(),
+        errors_reported: errors_reported(),
+        collected: COLLECTED.with(|collected| collected.borrow().len()),
+    };
+    set_message_format(MessageFormat::Json);
+
+    f()
+}
+
+/// Run `f`, handing back the diagnostics it reports instead of printing them. Like `silenced`,
+/// its errors are not counted.
+pub fn collected<T>(f: impl FnOnce() -> T) -> (T, Vec<Diagnostic>) {
+    let collected_before = COLLECTED.with(|collected| collected.borrow().len());
+
+    silenced(|| {
+        let value = f();
+        let reported =
+            COLLECTED.with(|collected| collected.borrow_mut().split_off(collected_before));
+
+        (value, reported)
+    })
+}
+
+/// Remember a source file so diagnostics about its `Token`s can be reported against it when
+/// several files are compiled together
+///
+/// # Returns
+///
+/// Returns the source id to give `Lexer::with_source_id`. Ids start at `1`, since `0` means the
+/// file currently being compiled.
+pub fn register_source(path: &str, source: &str) -> usize {
+    SOURCES.with(|sources| {
+        let mut sources = sources.borrow_mut();
+        sources.push((path.to_string(), source.to_string()));
+
+        sources.len()
+    })
+}
+
+/// Path and content of the file registered as `source_id`, or `path` and `source` if it was
+/// never registered (e.g a single file compile)
+pub fn source_of(source_id: usize, path: &str, source: &str) -> (String, String) {
+    let registered = source_id
+        .checked_sub(1)
+        .and_then(|idx| SOURCES.with(|sources| sources.borrow().get(idx).cloned()));
+
+    registered.unwrap_or_else(|| (path.to_string(), source.to_string()))
+}
+
+/// Remember the `#line` directives found in the file at `path`, so diagnostics in it are
+/// reported at the file and line the directives name instead. Replaces any directives that were
+/// registered for `path` before.
+pub fn register_line_directives(path: &str, directives: Vec<LineDirective>) {
+    LINE_DIRECTIVES.with(|registered| {
+        let mut registered = registered.borrow_mut();
+        registered.retain(|(registered_path, _)| registered_path != path);
+
+        if !directives.is_empty()
+        {
+            registered.push((path.to_string(), directives));
+        }
+    });
+}
+
+/// File and line that `line` of the file at `path` is reported at, following the last `#line`
+/// directive before it (if any)
+pub fn remap_line(path: &str, line: usize) -> Option<(String, usize)> {
+    LINE_DIRECTIVES.with(|registered| {
+        let registered = registered.borrow();
+        let (_, directives) = registered
+            .iter()
+            .find(|(registered_path, _)| registered_path == path)?;
+        let directive = directives.iter().rev().find(|directive| directive.line < line)?;
+
+        let (file, line) = directive.remap(line, path);
+        Some((file.to_string(), line))
+    })
+}
+
+/// Line and column (both starting at 1) of the char at `offset` in `source`
+fn line_and_col(source: &str, offset: usize) -> (usize, usize) {
+    let (mut line, mut col) = (1, 1);
+
+    let mut chars = source.chars().peekable();
+    for _ in 0..offset
+    {
+        let Some(ch) = chars.next()
+        else
+        {
+            break;
+        };
+
+        // A lone `\r` ends a line, but in a `\r\n` only the `\n` does
+        if ch == '\n' || (ch == '\r' && chars.peek() != Some(&'\n'))
+        {
+            line += 1;
+            col = 1;
+        }
+        else if ch != BOM
+        {
+            col += 1;
+        }
+    }
+
+    (line, col)
+}
+
+/// Report an error pointing at `span` in `source`
+///
+/// # Parameters
+///
+/// - `code`: Error code shown to the user (e.g `[00] Error`)
+/// - `message`: Short description of the error
+/// - `note`: Longer explanation of what went wrong
+/// - `path`: Path to the source file
+/// - `source`: Content of the source file
+/// - `span`: Char offsets of the offending text
+pub fn report_error(
+    code: u32,
+    message: &str,
+    note: &str,
+    path: &str,
+    source: &str,
+    span: Range<usize>,
+) {
+    report(Severity::Error, code, message, note, path, source, span);
+}
+
+/// Same as `report_error`, but for code that compiles and is probably still a mistake
+pub fn report_warning(
+    code: u32,
+    message: &str,
+    note: &str,
+    path: &str,
+    source: &str,
+    span: Range<usize>,
+) {
+    report(Severity::Warning, code, message, note, path, source, span);
+}
+
+fn report(
+    severity: Severity,
+    code: u32,
+    message: &str,
+    note: &str,
+    path: &str,
+    source: &str,
+    span: Range<usize>,
+) {
+    if severity == Severity::Error
+    {
+        ERRORS_REPORTED.with(|count| count.set(count.get() + 1));
+    }
+
+    match message_format()
+    {
+        MessageFormat::Human =>
+        {
+            let stdout = std::io::stdout();
+            write_report(severity, code, message, note, path, source, span, stdout).unwrap();
+        }
+        MessageFormat::Json =>
+        {
+            // A `#line` directive may say the offending text came from somewhere else
+            let (line, col) = line_and_col(source, span.start);
+            let (file, line) = remap_line(path, line).unwrap_or_else(|| (path.to_string(), line));
+            let diagnostic = Diagnostic {
+                file,
+                line,
+                col,
+                code,
+                severity,
+                message: format!("{message}: {note}"),
+                offset: span.start,
+            };
+
+            COLLECTED.with(|collected| collected.borrow_mut().push(diagnostic));
+        }
+    }
+}
+
+// `source` with lines added to or dropped from its start, so the char at `span.start` moves from
+// `line` to `to_line`. The returned span is moved along with it.
+fn move_to_line(
+    source: &str,
+    span: Range<usize>,
+    line: usize,
+    to_line: usize,
+) -> (String, Range<usize>) {
+    if to_line >= line
+    {
+        let padding = "\n".repeat(to_line - line);
+        let shift = padding.len();
+
+        return (padding + source, span.start + shift..span.end + shift);
+    }
+
+    // Every dropped line comes before the one `span` starts on
+    let mut lines_left = line - to_line;
+    let mut skipped = 0;
+    let mut chars = source.chars().peekable();
+    while lines_left > 0
+    {
+        let Some(ch) = chars.next()
+        else
+        {
+            break;
+        };
+        skipped += 1;
+
+        // Line endings are counted the same way as in `line_and_col`
+        if ch == '\n' || (ch == '\r' && chars.peek() != Some(&'\n'))
+        {
+            lines_left -= 1;
+        }
+    }
+
+    (chars.collect(), span.start - skipped..span.end - skipped)
+}
+
+// Write the pretty `ariadne` report of a diagnostic to `w`, which ends up on stdout. If a `#line`
+// directive remaps the offending line, the report shows it at the file and line the directive
+// names instead.
+#[allow(clippy::too_many_arguments)]
+fn write_report<W: Write>(
+    severity: Severity,
+    code: u32,
+    message: &str,
+    note: &str,
+    path: &str,
+    source: &str,
+    span: Range<usize>,
+    w: W,
+) -> std::io::Result<()> {
+    let (kind, color) = match severity
+    {
+        Severity::Error => (ReportKind::Error, ariadne::Color::Red),
+        Severity::Warning => (ReportKind::Warning, ariadne::Color::Yellow),
+    };
+
+    let (line, _) = line_and_col(source, span.start);
+    let (path, source, span) = match remap_line(path, line)
+    {
+        Some((file, to_line)) =>
+        {
+            let (source, span) = move_to_line(source, span, line, to_line);
+            (file, source, span)
+        }
+        None => (path.to_string(), source.to_string(), span),
+    };
+    let path = path.as_str();
+
+    Report::build(kind, path, span.start)
+        .with_config(report_config())
+        .with_code(code)
+        .with_message(message)
+        .with_label(Label::new((path, span)).with_message("Here").with_color(color))
+        .with_note(note)
+        .finish()
+        .write_for_stdout((path, Source::from(&source)), w)
+}
+
+#[derive(Debug)]
+pub struct ErrorCounter {
+    /// Most diagnostics to report before suppressing the rest
+    max_errors: usize,
+
+    /// Every diagnostic seen so far, reported or not
+    count: Cell<usize>,
+}
+
+impl Default for ErrorCounter {
+    fn default() -> Self {
+        // Never suppress anything by default
+        ErrorCounter::new(usize::MAX)
+    }
+}
+
+impl ErrorCounter {
+    pub fn new(max_errors: usize) -> Self {
+        Self {
+            max_errors,
+            count: Cell::new(0),
+        }
+    }
+
+    /// Count a diagnostic
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the diagnostic should be reported, `false` if we hit `max_errors`
+    pub fn report(&self) -> bool {
+        let count = self.count.get().saturating_add(1);
+        self.count.set(count);
+
+        count <= self.max_errors
+    }
+
+    /// Number of diagnostics that were actually reported
+    pub fn reported(&self) -> usize {
+        self.count.get().min(self.max_errors)
+    }
+
+    /// Number of diagnostics that were counted after hitting `max_errors`
+    pub fn suppressed(&self) -> usize {
+        self.count.get().saturating_sub(self.max_errors)
+    }
+
+    /// Print how many diagnostics were suppressed, if any
+    pub fn print_suppressed(&self) {
+        let suppressed = self.suppressed();
+
+        if suppressed > 0
+        {
+            eprintln!("{suppressed} more errors suppressed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Render a report for `let x <- 5!` with the current `ColorChoice`
+    fn render() -> String {
+        let mut out = Vec::new();
+        let (path, source) = ("test.az", "let x <- 5!");
+        write_report(Severity::Error, 0, "Unexpected Token", "note", path, source, 10..11, &mut out)
+            .unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn color_choice_controls_escape_codes() {
+        set_color_choice(ColorChoice::Never);
+        assert!(!render().contains('\x1b'));
+
+        set_color_choice(ColorChoice::Always);
+        assert!(render().contains('\x1b'));
+
+        set_color_choice(ColorChoice::Auto);
+    }
+
+    #[test]
+    fn remapped_reports_name_the_generated_from_location() {
+        let source = "let a <- 1;\nlet b <- 2;\nlet c <- 3!\n";
+        let render = |to_line| {
+            let directive = LineDirective { line: 1, target_line: to_line, target_file: None };
+            register_line_directives("test.az", vec![directive]);
+
+            let mut out = Vec::new();
+            write_report(Severity::Error, 0, "Bad", "note", "test.az", source, 34..35, &mut out)
+                .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        set_color_choice(ColorChoice::Never);
+
+        // Line 3 follows the directive, so it is line `to_line + 1` of the file it names
+        for to_line in [1, 40]
+        {
+            let report = render(to_line);
+            let remapped_line = to_line + 1;
+
+            assert!(report.contains(&format!("[test.az:{remapped_line}:11]")), "{report}");
+            assert!(report.contains(&format!("{remapped_line} │ let c <- 3!")), "{report}");
+        }
+
+        register_line_directives("test.az", Vec::new());
+        set_color_choice(ColorChoice::Auto);
+    }
+}
@@ -5,13 +5,66 @@
 use std::path::Path;
 
 use crate::span::SpanPoint;
-use ariadne::{Label, Report, ReportKind, Source};
+use crate::diagnostics::report_error;
 use thiserror::Error;
 
-#[derive(Clone, Debug, Error)]
+/// What went wrong while tokenizing. Each variant matches one of the `LexerErrorReporter`
+/// reports, and `offset` is the char offset in the original source file that the report points
+/// at.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
 pub enum LexError {
-    #[error("Failed to tokenize {0}")]
-    Failed(String)
+    #[error("Unsupported character `{ch}`")]
+    UnsupportedChar { ch: char, offset: usize },
+
+    #[error("Type qualifier is incomplete, expected `::`")]
+    IncompleteTypeQualifier { offset: usize },
+
+    #[error("Invalid identifier")]
+    InvalidIdent { offset: usize },
+
+    #[error("Misplaced underscore")]
+    MisplacedUnderscore { offset: usize },
+
+    #[error("Invalid float")]
+    InvalidFloat { offset: usize },
+
+    #[error("Invalid character literal")]
+    InvalidCharLit { offset: usize },
+
+    #[error("Unterminated character literal")]
+    UnterminatedCharLit { offset: usize },
+
+    #[error("Unterminated string literal")]
+    UnterminatedStrLit { offset: usize },
+
+    #[error("Integer literal out of range")]
+    IntOverflow { offset: usize },
+
+    #[error("Float literal out of range")]
+    FloatOverflow { offset: usize },
+
+    #[error("Invalid literal suffix")]
+    IntSuffixOnFloat { offset: usize },
+}
+
+impl LexError {
+    /// Char offset of the error in the original source file
+    pub fn offset(&self) -> usize {
+        match self
+        {
+            LexError::UnsupportedChar { offset, .. }
+            | LexError::IncompleteTypeQualifier { offset }
+            | LexError::InvalidIdent { offset }
+            | LexError::MisplacedUnderscore { offset }
+            | LexError::InvalidFloat { offset }
+            | LexError::InvalidCharLit { offset }
+            | LexError::UnterminatedCharLit { offset }
+            | LexError::UnterminatedStrLit { offset }
+            | LexError::IntOverflow { offset }
+            | LexError::FloatOverflow { offset }
+            | LexError::IntSuffixOnFloat { offset } => *offset,
+        }
+    }
 }
 
 
@@ -20,35 +73,21 @@ pub struct LexerErrorReporter;
 // Specific error report handlers
 impl LexerErrorReporter {
     pub fn unsupported_char<'a>(ch: char, path: &str, source: &str, offset: usize) {
-        let note = format!("`{0}` is an unsupported character", ch);
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Unsupported Character")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        // Stands in for a non-ASCII byte (see `Lexer::from_bytes`)
+        let note = if ch == char::REPLACEMENT_CHARACTER
+        {
+            "Found a byte that is not ASCII. Only ASCII characters are supported".to_string()
+        }
+        else
+        {
+            format!("`{0}` is an unsupported character", ch)
+        };
+        report_error(0, "Unsupported Character", &note, path, source, offset..offset);
     }
 
     pub fn incomplete_tqal<'a>(ch: char, path: &str, source: &str, offset: usize) {
         let note = format!("`{0}` should be `::`", ch);
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(1)
-            .with_message("Type Qualifier is incomplete")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(1, "Type Qualifier is incomplete", &note, path, source, offset..offset);
     }
 
     pub fn invalid_ident<'a>(ch: char, path: &str, source: &str, offset: usize) {
@@ -56,49 +95,75 @@ impl LexerErrorReporter {
             "`{0}` should not be attached to the start of a identifier",
             ch
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(2)
-            .with_message("Invalid identifier")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(2, "Invalid identifier", &note, path, source, offset..offset);
     }
 
     pub fn misplaced_underscore<'a>(ch: char, path: &str, source: &str, offset: usize) {
         let note = "Underscores must come directly before or after letters or other underscores";
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(3)
-            .with_message("Misplaced underscore")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(3, "Misplaced underscore", &note, path, source, offset..offset);
+    }
+
+    pub fn invalid_float<'a>(lit: &str, path: &str, source: &str, offset: usize) {
+        let rule = "Floats must contain strictly numbers before and after the `.`";
+
+        // Suggest the missing `0` (e.g `0.5` for `.5`, or `5.0` for `5.`)
+        let note = match (lit.starts_with('.'), lit.ends_with('.'))
+        {
+            (true, false) => format!("{rule}. Write `0{lit}` instead"),
+            (false, true) => format!("{rule}. Write `{lit}0` instead"),
+            _ => rule.to_string(),
+        };
+        report_error(4, "Invalid float", &note, path, source, offset..offset);
     }
 
-    pub fn invalid_float<'a>(ch: char, path: &str, source: &str, offset: usize) {
-        let note = "Floats must contain strictly numbers before and after the `.`";
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(4)
-            .with_message("Invalid float")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
+    pub fn invalid_char_lit(lit: &str, path: &str, source: &str, offset: usize) {
+        let note = if lit == "''"
+        {
+            "Character literals must contain exactly one character".to_string()
+        }
+        else
+        {
+            format!(
+                "`{0}` should contain exactly one character. Supported escapes are `\\n`, `\\t`, `\\\\`, and `\\'`",
+                lit
             )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        };
+        report_error(5, "Invalid character literal", &note, path, source, offset..offset);
+    }
+
+    pub fn unterminated_char_lit(path: &str, source: &str, offset: usize) {
+        let note = "Character literals must be closed with a `'` on the same line";
+        report_error(6, "Unterminated character literal", &note, path, source, offset..offset);
+    }
+
+    pub fn unterminated_str_lit(path: &str, source: &str, offset: usize) {
+        let note = "String literals must be closed with a `\"\"\"`";
+        report_error(10, "Unterminated string literal", &note, path, source, offset..offset);
+    }
+
+    pub fn integer_literal_overflow(lit: &str, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` does not fit in an `int`. The largest `int` is {1}",
+            lit,
+            i64::MAX
+        );
+        report_error(7, "Integer literal out of range", &note, path, source, offset..offset);
+    }
+
+    pub fn float_literal_overflow(lit: &str, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` is too large to be a `float`. The largest `float` is about {1:e}",
+            lit,
+            f64::MAX
+        );
+        report_error(8, "Float literal out of range", &note, path, source, offset..offset);
+    }
+
+    pub fn int_suffix_on_float(lit: &str, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}i` has an `int` suffix, but `{0}` is a float. Use the `f` suffix instead",
+            lit
+        );
+        report_error(9, "Invalid literal suffix", &note, path, source, offset..offset);
     }
 }
@@ -0,0 +1,126 @@
+//! String interning for the Morehead Azalea Compiler.
+//!
+//! Every `Token` keeps its text for display, but an identifier also holds a `Symbol`: a small id
+//! that is the same for every identifier with the same text. Comparing names (e.g in the symbol
+//! table) can then compare ids instead of strings.
+//!
+//! NOTE: Ids come from a per-thread interner, so a `Symbol` only means something on the thread
+//! that made it, and only until `clear_interner` is called.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// Id of an interned string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Intern `string` in this thread's interner
+    pub fn intern(string: &str) -> Self {
+        INTERNER.with(|interner| interner.borrow_mut().intern(string))
+    }
+
+    /// Raw id of this `Symbol`
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// The string this `Symbol` was interned from
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `Symbol` was not made by this thread's interner.
+    pub fn resolve(self) -> String {
+        INTERNER.with(|interner| {
+            interner
+                .borrow()
+                .resolve(self)
+                .expect("symbol was interned on another thread")
+                .to_string()
+        })
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.resolve())
+    }
+}
+
+/// Maps strings to `Symbol`s and back. Each distinct string is stored once.
+#[derive(Debug, Default)]
+pub struct StringInterner {
+    /// `Symbol` of each interned string
+    ids: HashMap<Rc<str>, Symbol>,
+
+    /// Interned strings, indexed by `Symbol`. Each one is shared with its key in `ids`.
+    strings: Vec<Rc<str>>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `Symbol` of `string`, interning it if it has not been seen before
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.ids.get(string)
+        {
+            return *symbol;
+        }
+
+        let symbol = Symbol(u32::try_from(self.strings.len()).expect("too many interned strings"));
+        let string = Rc::<str>::from(string);
+        self.strings.push(Rc::clone(&string));
+        self.ids.insert(string, symbol);
+
+        symbol
+    }
+
+    /// `Symbol` of `string`, without interning it
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.ids.get(string).copied()
+    }
+
+    /// The string `symbol` was interned from, or `None` if it came from another interner
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.strings.get(symbol.0 as usize).map(|string| &**string)
+    }
+
+    /// Number of distinct strings interned
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Forget every interned string
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.strings.clear();
+    }
+}
+
+/// Forget every string in this thread's interner, so it does not keep growing across unrelated
+/// runs of the compiler (e.g fuzzing iterations). Returns how many strings were dropped.
+///
+/// NOTE: `Symbol`s made before this no longer mean anything, so only call it once every `Token`
+/// from earlier runs is gone.
+pub fn clear_interner() -> usize {
+    INTERNER.with(|interner| {
+        let mut interner = interner.borrow_mut();
+        let dropped = interner.len();
+        interner.clear();
+
+        dropped
+    })
+}
+
+thread_local! {
+    // Interner behind `Symbol::intern`, used by every `Token`
+    static INTERNER: RefCell<StringInterner> = RefCell::new(StringInterner::new());
+}
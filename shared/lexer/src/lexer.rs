@@ -3,14 +3,18 @@
 //! The lexer, using a DFA, converts raw characters into meaningful
 //! words and punctuation (tokens).
 
+use crate::diagnostics::ErrorCounter;
 use crate::errors::{LexError, LexerErrorReporter};
+use crate::source_map::SourceMap;
 use crate::span::{Span, SpanPoint};
-use crate::token::{Token, TokenHint, TokenKind};
+use crate::token::{NumSuffix, Token, TokenHint, TokenKind};
 use ariadne::Report;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 #[derive(Default, Debug)]
 pub struct Lexer {
@@ -26,7 +30,10 @@ pub struct Lexer {
     /// Content of the source file
     source_content: String,
 
-    /// Lexer index into the file
+    /// Number of chars in `source_content`
+    source_len: usize,
+
+    /// Lexer index into the file, in chars
     index: usize,
 
     /// Flag to say we are at the end of the source file
@@ -35,28 +42,143 @@ pub struct Lexer {
     /// Token we suspect will be built
     hint_tok: TokenHint,
 
+    /// Flag to say the ident being built had a `r#` prefix, so it is never a keyword
+    raw_ident: bool,
+
+    /// Type suffix that follows the number being built (e.g the `i` in `5i`)
+    num_suffix: Option<NumSuffix>,
+
+    /// Extra keywords that take priority over the default ones (see `Lexer::with_keywords`)
+    keywords: Option<HashMap<String, TokenKind>>,
+
+    /// Flag to say we should emit `Whitespace` and `Newline` tokens instead of skipping them
+    preserve_trivia: bool,
+
     /// Flag to keep track of if we hit an error
     found_error: bool,
+
+    /// Flag to say we should emit `Error` tokens instead of failing on errors
+    recovering: bool,
+
+    /// `Error` tokens built while recovering from errors
+    error_tokens: Vec<Token>,
+
+    /// Errors found so far, in the order they were found
+    errors: Vec<LexError>,
+
+    /// Maps offsets in `source_content` back to the original source file for error reporting
+    source_map: Option<SourceMap>,
+
+    /// Counts reported errors so we stop reporting after the limit
+    error_counter: Rc<ErrorCounter>,
+
+    /// Id of the source file every built `Token` is marked with
+    source_id: usize,
 }
 
 /// Constructor for the `Lexer`
 impl Lexer {
     pub fn new<S, P>(file_path: P, file_content: S) -> Self
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        // A tab is treated as a single column by default
+        let tab_width = 1;
+
+        Lexer::with_tab_width(file_path, file_content, tab_width)
+    }
+
+    /// Same as `Lexer::new`, but a `\t` advances the column of `Token` spans by `tab_width`
+    /// columns. Useful to match the tab width of the user's editor.
+    pub fn with_tab_width<S, P>(file_path: P, file_content: S, tab_width: usize) -> Self
     where
         S: AsRef<str>,
         P: AsRef<Path>,
     {
         Lexer {
             current_tok: String::new(),
-            current_pos: Span::new(&file_content),
+            current_pos: Span::with_tab_width(&file_content, tab_width),
             source_path: file_path.as_ref().to_path_buf(),
             source_content: file_content.as_ref().to_string(),
+            source_len: file_content.as_ref().chars().count(),
             index: 0,
             eof: false,
             hint_tok: TokenHint::Undetermined,
+            raw_ident: false,
+            num_suffix: None,
+            keywords: None,
+            preserve_trivia: false,
             found_error: false,
+            recovering: false,
+            error_tokens: Vec::new(),
+            errors: Vec::new(),
+            source_map: None,
+            error_counter: Rc::default(),
+            source_id: 0,
         }
     }
+
+    /// Same as `Lexer::new`, but the content is raw bytes that may not be valid UTF-8 (e.g
+    /// fuzzed input). Every non-ASCII byte becomes a `char::REPLACEMENT_CHARACTER`, which is
+    /// reported as an unsupported character, so char offsets into the content are also byte
+    /// offsets into `file_content`.
+    pub fn from_bytes<P: AsRef<Path>>(file_path: P, file_content: &[u8]) -> Self {
+        let file_content = file_content
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii()
+                {
+                    char::from(byte)
+                }
+                else
+                {
+                    char::REPLACEMENT_CHARACTER
+                }
+            })
+            .collect::<String>();
+
+        Lexer::new(file_path, file_content)
+    }
+
+    /// Report errors against the original source file described by `source_map` instead of the
+    /// (preprocessed) content the `Lexer` was built with
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    /// Share `error_counter` with the other compiler stages so reporting stops once it hits its
+    /// limit
+    pub fn with_error_counter(mut self, error_counter: Rc<ErrorCounter>) -> Self {
+        self.error_counter = error_counter;
+        self
+    }
+
+    /// Lex the words in `keywords` as the given `TokenKind`s instead of their default kinds (e.g
+    /// `"fn"` as `TokenKind::FnDef`), to try out other syntaxes. Words missing from `keywords`
+    /// keep their default kind, and mapping a word to `TokenKind::Ident` makes it a plain ident.
+    pub fn with_keywords(mut self, keywords: HashMap<String, TokenKind>) -> Self {
+        self.keywords = Some(keywords);
+        self
+    }
+
+    /// Emit `TokenKind::Whitespace` and `TokenKind::Newline` tokens instead of skipping
+    /// whitespace, so the source can be rebuilt from the token stream (e.g by a formatter)
+    ///
+    /// NOTE: The parser does not expect these tokens, so only turn this on for other tools.
+    pub fn with_preserve_trivia(mut self, preserve_trivia: bool) -> Self {
+        self.preserve_trivia = preserve_trivia;
+        self
+    }
+
+    /// Mark every built `Token` with `source_id` so diagnostics about it are reported against
+    /// this file, even when several files are compiled together (see
+    /// `diagnostics::register_source`)
+    pub fn with_source_id(mut self, source_id: usize) -> Self {
+        self.source_id = source_id;
+        self
+    }
 }
 
 /// General getters/setters/incrementers
@@ -65,10 +187,47 @@ impl Lexer {
     ///
     /// # Returns
     ///
-    /// Returns the backing file index as a `usize`
+    /// Returns the backing file index as a `usize`, which is never passed the end of the file
+    ///
+    pub(crate) fn get_file_index(&self) -> usize {
+        self.index.min(self.source_len)
+    }
+
+    /// Whether the file index has reached the end of the file
+    pub(crate) fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    /// Source that error reports are printed against
+    ///
+    /// # Returns
+    ///
+    /// Returns the original source if a `SourceMap` was given, else the lexed content
     ///
-    fn get_file_index(&self) -> usize {
-        self.index
+    fn report_source(&self) -> &str {
+        match &self.source_map
+        {
+            Some(source_map) => source_map.original_source(),
+            None => &self.source_content,
+        }
+    }
+
+    /// Maps a file index into an offset within `report_source`
+    ///
+    /// # Parameters
+    ///
+    /// - `file_index`: Index into the lexed content
+    ///
+    /// # Returns
+    ///
+    /// Returns the offset to hand to the error reporters
+    ///
+    fn report_offset(&self, file_index: usize) -> usize {
+        match &self.source_map
+        {
+            Some(source_map) => source_map.original_offset(file_index),
+            None => file_index,
+        }
     }
 
     /// Increments the file index by one
@@ -101,28 +260,83 @@ impl Lexer {
     fn get_span_start_and_end_with_offset(&self, offset: usize) -> (SpanPoint, SpanPoint) {
         let file_index = self.get_file_index();
 
+        // Callers move passed the whole `Token` first, so it can never start before the file.
+        // Clamping would silently point the span at the start of the file instead.
+        debug_assert!(
+            offset <= file_index,
+            "`Token` of length {offset} cannot end at file index {file_index}"
+        );
+
         let start = self.current_pos[file_index.saturating_sub(offset)];
         let end = self.current_pos[file_index.saturating_sub(1)];
 
         (start, end)
     }
 
+    /// Flag that we hit an error and keep it so `lex` and `recover` can return it. When
+    /// recovering from errors, we also record an `Error` token that covers the offending text so
+    /// the remaining tokens are still usable.
+    ///
+    /// # Parameters
+    ///
+    /// * `error`: what went wrong.
+    /// * `start`: file index of the first offending character.
+    /// * `len`: amount of offending characters.
+    ///
+    fn record_error(&mut self, error: LexError, start: usize, len: usize) {
+        // Set flag but continue attempting to lex to find more errors
+        self.found_error = true;
+        self.errors.push(error);
+
+        if !self.recovering
+        {
+            return;
+        }
+
+        // Build `Error` token out of the offending text
+        let raw_content = self
+            .source_content
+            .chars()
+            .skip(start)
+            .take(len.max(1))
+            .collect::<String>();
+        let span_start  = self.current_pos.get(start).copied().unwrap_or_default();
+        let span_end    = self
+            .current_pos
+            .get(start + len.max(1) - 1)
+            .copied()
+            .unwrap_or(span_start);
+        let is_reserved = false;
+
+        self.error_tokens.push(Token::new(
+            raw_content,
+            TokenKind::Error,
+            span_start,
+            span_end,
+            start,
+            is_reserved,
+        ));
+    }
+
     /// Increment the current file index by some offset, but clamp in the event of overflowing file
     /// length.
     ///
+    /// Reaching or going passed the end of the file, however large `incre` is, sets the index to
+    /// the file length and marks the `Lexer` as at EOF.
+    ///
     /// # Parameters
     ///
     /// * `incre`: amount to increment by.
     ///
-    fn incre_file_index_by(&mut self, incre: usize) {
-        // Advance to next index in file
-        self.index += incre;
+    pub(crate) fn incre_file_index_by(&mut self, incre: usize) {
+        // Advance to next index in file. Saturate so a huge `incre` still lands passed EOF.
+        self.index = self.index.saturating_add(incre);
 
         // Clamp to the file if we go passed EOF to prevent out-of-bounds access
-        if self.index >= self.source_content.len()
+        if self.index >= self.source_len
         {
             self.eof = true;
-            self.index = self.source_content.len();
+            self.index = self.source_len;
         }
     }
 }
@@ -152,19 +366,28 @@ impl Lexer {
         let file_index   = self.get_file_index();
         let current_char = self.source_content.chars().nth(file_index);
 
-        // Make sure we throw an error if we detect invalid chars
-        if current_char.is_some_and(|ch| !ch.is_ascii())
+        // Make sure we throw an error if we detect invalid chars (e.g non-ASCII or control chars)
+        if current_char.is_some_and(|ch| !ch.is_ascii() || (ch.is_control() && !ch.is_whitespace()))
         {
+            let offset = self.report_offset(self.get_file_index());
+
             // Generate error report and print
-            LexerErrorReporter::unsupported_char(
-                current_char.unwrap(),
-                self.source_path.to_str().unwrap(),
-                &self.source_content,
-                self.get_file_index(),
-            );
+            if self.error_counter.report()
+            {
+                LexerErrorReporter::unsupported_char(
+                    current_char.unwrap(),
+                    self.source_path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
             // Set flag but continue attempting to lex to find more errors
-            self.found_error = true;
+            self.record_error(
+                LexError::UnsupportedChar { ch: current_char.unwrap(), offset },
+                file_index,
+                1,
+            );
 
             return None;
         }
@@ -188,6 +411,39 @@ impl Lexer {
 
 /// Consume implementations for punctuation
 impl Lexer {
+    /// Create a `Newline` `Token` if `ch` is a `\n`, else a `Whitespace` `Token` covering `ch`
+    /// and the whitespace after it up to the next newline.
+    ///
+    /// # Parameters
+    ///
+    /// * `ch`: whitespace character that we are trying to consume to `Token`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Newline` or `Whitespace` `Token`.
+    fn consume_trivia(&mut self, ch: char) -> Token {
+        if ch == '\n'
+        {
+            return self.consume_one_chars(ch, TokenKind::Newline).unwrap();
+        }
+
+        let mut trivia = ch.to_string();
+        while let Some(next) = self.peek().filter(|next| next.is_whitespace() && *next != '\n')
+        {
+            trivia.push(next);
+            self.incre_file_index_by(1);
+        }
+
+        // Move passed the last char of the run
+        self.incre_file_index_by(1);
+        let trivia_len = trivia.chars().count();
+        let (span_start, span_end) = self.get_span_start_and_end_with_offset(trivia_len);
+        let file_index = self.get_file_index().saturating_sub(trivia_len);
+        let is_reserved = false;
+
+        Token::new(trivia, TokenKind::Whitespace, span_start, span_end, file_index, is_reserved)
+    }
+
     /// Create a `Token` that is one char in length.
     ///
     /// # Parameters
@@ -234,9 +490,8 @@ impl Lexer {
         kind1: TokenKind,
         kind2: TokenKind,
     ) -> Option<Token> {
-        let next_char = self.peek().expect("Tried to peek pass EOF.");
-
-        if next_char == ch2
+        // `ch1` may be the last char of the file
+        if self.peek() == Some(ch2)
         {
             // Move to next position in file and get start and end span of current token
             self.incre_file_index_by(2);
@@ -272,6 +527,85 @@ impl Lexer {
 
 // Consume implementations for complex tokens
 impl Lexer {
+    /// Kind of the ident or keyword in `current_tok`, using the custom keywords if there are any.
+    /// A raw ident (e.g `r#for`) is always an `Ident`, even if it is spelled like a keyword.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `TokenKind` and whether it is reserved, like `TokenKind::refined_or_ident`.
+    fn refined_or_raw_ident(&mut self) -> (TokenKind, bool) {
+        if std::mem::take(&mut self.raw_ident)
+        {
+            let is_reserved = false;
+            return (TokenKind::Ident, is_reserved);
+        }
+
+        // Custom keywords take priority over the default ones
+        let custom_kind = self
+            .keywords
+            .as_ref()
+            .and_then(|keywords| keywords.get(&self.current_tok));
+        if let Some(&token_kind) = custom_kind
+        {
+            let is_reserved = token_kind != TokenKind::Ident;
+            return (token_kind, is_reserved);
+        }
+
+        TokenKind::refined_or_ident(&self.current_tok)
+    }
+
+    /// Make sure the number literal in `current_tok` fits in an `int` (`i64`) or a `float` (`f64`)
+    ///
+    /// # Parameters
+    ///
+    /// * `token_kind`: the kind of `Token` being built. Only `NumLit` and `FloatLit` are checked.
+    /// * `file_index`: where the literal starts in the file
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the literal fits, `false` otherwise (the error is reported).
+    ///
+    /// NOTE: A `-` is lexed as its own `Token`, so the literal never has a sign. That means
+    /// `i64::MIN` can not be written directly, since `9223372036854775808` is too large on its
+    /// own. Write `-9223372036854775807 - 1` instead.
+    fn check_num_lit_range(&mut self, token_kind: TokenKind, file_index: usize) -> bool {
+        let in_range = match token_kind
+        {
+            TokenKind::NumLit => self.current_tok.parse::<i64>().is_ok(),
+            TokenKind::FloatLit => self.current_tok.parse::<f64>().is_ok_and(f64::is_finite),
+            _ => true,
+        };
+
+        if !in_range
+        {
+            let offset = self.report_offset(file_index);
+            let (report, error): (fn(&str, &str, &str, usize), _) =
+                if token_kind == TokenKind::NumLit
+                {
+                    (LexerErrorReporter::integer_literal_overflow, LexError::IntOverflow { offset })
+                }
+                else
+                {
+                    (LexerErrorReporter::float_literal_overflow, LexError::FloatOverflow { offset })
+                };
+
+            // error report
+            if self.error_counter.report()
+            {
+                report(
+                    &self.current_tok,
+                    self.source_path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            self.record_error(error, file_index, self.current_tok.len());
+        }
+
+        in_range
+    }
+
     /// Create a `Token` that is a int or a float
     ///
     /// # Parameters
@@ -279,8 +613,11 @@ impl Lexer {
     /// * `token_buffer`: the accumulated `Token` buffer that the `Lexer` uses
     ///
     fn consume_num_or_float_lit(&mut self, token_buffer: &mut Vec<Token>) {
-        // Determine if we have a int literal of float
-        let token_kind = if self.current_tok.contains('.')
+        let suffix = self.num_suffix.take();
+        let is_float = self.current_tok.contains('.');
+
+        // Determine if we have a int literal of float. A suffix decides for us.
+        let token_kind = if is_float || suffix == Some(NumSuffix::Float)
         {
             TokenKind::FloatLit
         }
@@ -296,6 +633,38 @@ impl Lexer {
 
         let file_index = self.get_file_index().saturating_sub(token_len);
 
+        // A float cannot be made into an `int` with a suffix
+        if is_float && suffix == Some(NumSuffix::Int)
+        {
+            let offset = self.report_offset(file_index);
+
+            // error report
+            if self.error_counter.report()
+            {
+                LexerErrorReporter::int_suffix_on_float(
+                    &self.current_tok,
+                    self.source_path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            self.record_error(LexError::IntSuffixOnFloat { offset }, file_index, token_len + 1);
+            self.hint_tok = TokenHint::Undetermined;
+            self.current_tok.clear();
+            return;
+        }
+
+        // Drop literals that the backend could not represent. An integer written as a float
+        // (e.g `5f`) must still be a valid `int`.
+        let range_kind = if is_float { TokenKind::FloatLit } else { TokenKind::NumLit };
+        if !self.check_num_lit_range(range_kind, file_index)
+        {
+            self.hint_tok = TokenHint::Undetermined;
+            self.current_tok.clear();
+            return;
+        }
+
         // Create the `Token`
         let raw_token_content = self.current_tok.clone();
         let is_reserved = false;
@@ -307,6 +676,11 @@ impl Lexer {
             file_index,
             is_reserved,
         );
+        let tok = match suffix
+        {
+            Some(suffix) => tok.with_num_suffix(suffix),
+            None => tok,
+        };
 
         // Push to the internal `Token` buffer
         token_buffer.push(tok);
@@ -326,7 +700,7 @@ impl Lexer {
     ///
     fn consume_ident_or_reserved(&mut self, token_buffer: &mut Vec<Token>) {
         // See if we can refine to a more specific token other then `Ident`
-        let (token_kind, is_reserved) = TokenKind::refined_or_ident(&self.current_tok);
+        let (token_kind, is_reserved) = self.refined_or_raw_ident();
 
         // Get start and end of the current token's span
         let token_len = self.current_tok.len();
@@ -358,6 +732,206 @@ impl Lexer {
 }
 
 impl Lexer {
+    /// Create a `Token` for a `'`-delimited character literal (e.g `'a'` or `'\n'`). The raw
+    /// content of the `Token` keeps the quotes and escapes as written in the source file.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some` if we make a `CharLit` `Token`, `None` otherwise.
+    fn consume_char_lit(&mut self) -> Option<Token> {
+        let start_index = self.get_file_index();
+
+        // Walk passed the opening `'` until we find the closing `'`, counting how many
+        // characters (with escapes counting as one) are inside of the literal
+        let mut end_index  = start_index + 1;
+        let mut char_count = 0;
+        let mut bad_escape = false;
+        loop
+        {
+            match self.source_content.chars().nth(end_index)
+            {
+                // Hit the closing `'`
+                Some('\'') => break,
+
+                // Escapes are two characters in the source, but one character in the literal
+                Some('\\') =>
+                {
+                    let escaped = self.source_content.chars().nth(end_index + 1);
+                    if !escaped.is_some_and(|ch| matches!(ch, 'n' | 't' | '\\' | '\''))
+                    {
+                        bad_escape = true;
+                    }
+
+                    // Do not walk passed the end of the line with a bad escape
+                    if escaped.is_none() || escaped == Some('\n')
+                    {
+                        end_index += 1;
+                        continue;
+                    }
+
+                    end_index += 2;
+                }
+
+                // Literal was never closed on this line
+                None | Some('\n') =>
+                {
+                    let offset = self.report_offset(start_index);
+
+                    // error report
+                    if self.error_counter.report()
+                    {
+                        LexerErrorReporter::unterminated_char_lit(
+                            self.source_path.to_str().unwrap(),
+                            self.report_source(),
+                            offset,
+                        );
+                    }
+
+                    // Proceed to the end of the line and attempt to find other errors. The
+                    // lexing loop moves passed the last character for us.
+                    self.record_error(
+                        LexError::UnterminatedCharLit { offset },
+                        start_index,
+                        end_index - start_index,
+                    );
+                    self.incre_file_index_by(end_index - start_index - 1);
+                    return None;
+                }
+
+                Some(_) => end_index += 1,
+            }
+
+            char_count += 1;
+        }
+
+        // Include the closing `'` in the `Token`
+        let token_len   = end_index + 1 - start_index;
+        let raw_content = self
+            .source_content
+            .chars()
+            .skip(start_index)
+            .take(token_len)
+            .collect::<String>();
+
+        // Character literals hold exactly one (possibly escaped) character
+        if char_count != 1 || bad_escape
+        {
+            let offset = self.report_offset(start_index);
+
+            // error report
+            if self.error_counter.report()
+            {
+                LexerErrorReporter::invalid_char_lit(
+                    &raw_content,
+                    self.source_path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            // Proceed passed the literal and attempt to find other errors. The lexing loop
+            // moves passed the closing `'` for us.
+            self.record_error(LexError::InvalidCharLit { offset }, start_index, token_len);
+            self.incre_file_index_by(token_len - 1);
+            return None;
+        }
+
+        // Move to next position in file and get start and end span of current token
+        self.incre_file_index_by(token_len);
+        let (span_start, span_end) = self.get_span_start_and_end_with_offset(token_len);
+        let is_reserved = false;
+
+        Some(Token::new(
+            raw_content,
+            TokenKind::CharLit,
+            span_start,
+            span_end,
+            start_index,
+            is_reserved,
+        ))
+    }
+
+    /// Create a `Token` for a `"""`-delimited string literal, which may span several lines. The
+    /// raw content of the `Token` keeps the quotes and any newlines as written in the source file.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some` if we make a `StrLit` `Token`, `None` otherwise.
+    fn consume_triple_quoted_lit(&mut self) -> Option<Token> {
+        const QUOTES: &str = "\"\"\"";
+
+        let start_index = self.get_file_index();
+
+        // Everything after the opening quotes, as chars since file indices count chars
+        let rest = self.source_content.chars().skip(start_index + 3).collect::<String>();
+
+        // Literal was never closed before the end of the file
+        let Some(content_len) = rest.find(QUOTES).map(|end| rest[..end].chars().count())
+        else
+        {
+            let offset = self.report_offset(start_index);
+
+            // error report
+            if self.error_counter.report()
+            {
+                LexerErrorReporter::unterminated_str_lit(
+                    self.source_path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            // Nothing after the opening quotes can be lexed, so proceed to the end of the file.
+            // The lexing loop moves passed the last character for us.
+            let token_len = 3 + rest.chars().count();
+            self.record_error(LexError::UnterminatedStrLit { offset }, start_index, token_len);
+            self.incre_file_index_by(token_len - 1);
+            return None;
+        };
+
+        // Include both sets of quotes in the `Token`
+        let token_len   = content_len + 6;
+        let raw_content = self
+            .source_content
+            .chars()
+            .skip(start_index)
+            .take(token_len)
+            .collect::<String>();
+
+        // Move to next position in file and get start and end span of current token
+        self.incre_file_index_by(token_len);
+        let (span_start, span_end) = self.get_span_start_and_end_with_offset(token_len);
+        let is_reserved = false;
+
+        Some(Token::new(
+            raw_content,
+            TokenKind::StrLit,
+            span_start,
+            span_end,
+            start_index,
+            is_reserved,
+        ))
+    }
+
+    /// The digits around a `.` that is not part of a float, `RecordDot` or `ExRange`
+    ///
+    /// # Returns
+    ///
+    /// Returns the text as written (e.g `.5` or `5.`) for the `invalid_float` report
+    fn malformed_float(&self) -> String {
+        let index  = self.get_file_index();
+        let before = self.source_content.chars().take(index).collect::<String>();
+        let before = &before[before.trim_end_matches(|ch: char| ch.is_ascii_digit()).len()..];
+        let after  = self
+            .source_content
+            .chars()
+            .skip(index + 1)
+            .take_while(char::is_ascii_digit)
+            .collect::<String>();
+
+        format!("{before}.{after}")
+    }
+
     fn lex_punctuation(&mut self) -> Option<Token> {
         // Get the current character and move to next character if we find an issue
         let Some(character) = self.peek_current()
@@ -375,6 +949,10 @@ impl Lexer {
             '/' => self.consume_one_chars('/', TokenKind::Div),
             '-' => self.consume_one_or_two_chars('-', '>', TokenKind::Minus, TokenKind::RetArrow),
             '>' => self.consume_one_or_two_chars('>', '=', TokenKind::Gt, TokenKind::Gte),
+            '=' if self.peek() == Some('>') =>
+            {
+                self.consume_one_or_two_chars('=', '>', TokenKind::FnDef, TokenKind::FatArrow)
+            }
             '=' => self.consume_one_or_two_chars('=', '=', TokenKind::FnDef, TokenKind::Eq),
             ';' => self.consume_one_chars(';', TokenKind::Semicolon),
             '(' => self.consume_one_chars('(', TokenKind::LParn),
@@ -384,12 +962,18 @@ impl Lexer {
             '[' => self.consume_one_chars('[', TokenKind::LSBracket),
             ']' => self.consume_one_chars(']', TokenKind::RSBracket),
             ',' => self.consume_one_chars(',', TokenKind::Sep),
+            '\'' => self.consume_char_lit(),
+            '"' if self.peek() == Some('"')
+                && self.source_content.chars().nth(self.get_file_index() + 2) == Some('"') =>
+            {
+                self.consume_triple_quoted_lit()
+            }
             // Special case
 	    '!' =>
 	    {
-		let next_char = self.peek().expect("Tried to peek pass EOF.");
+		let next_char = self.peek();
 
-                if next_char == '='
+                if next_char == Some('=')
                 {
                     // Move to next position in file and get start and end span of current token
                     self.incre_file_index_by(2);
@@ -427,9 +1011,9 @@ impl Lexer {
             // Special case
             '<' =>
             {
-                let next_char = self.peek().expect("Tried to peek pass EOF.");
+                let next_char = self.peek();
 
-                if next_char == '-'
+                if next_char == Some('-')
                 {
                     // Move to next position in file and get start and end span of current token
                     self.incre_file_index_by(2);
@@ -446,7 +1030,7 @@ impl Lexer {
                         is_reserved,
                     ));
                 }
-                else if next_char == '='
+                else if next_char == Some('=')
                 {
                     // Move to next position in file and get start and end span of current token
                     self.incre_file_index_by(2);
@@ -484,9 +1068,9 @@ impl Lexer {
             // Special case
             ':' =>
             {
-                let next_char = self.peek().expect("Tried to peek pass EOF.");
+                let next_char = self.peek();
 
-                if next_char == ':'
+                if next_char == Some(':')
                 {
                     // Move to next position in file and get start and end span of current token
                     self.incre_file_index_by(2);
@@ -505,16 +1089,25 @@ impl Lexer {
                 }
                 else
                 {
+                    let offset = self.report_offset(self.get_file_index());
+
                     // error report
-                    LexerErrorReporter::incomplete_tqal(
-                        ':',
-                        self.source_path.to_str().unwrap(),
-                        &self.source_content,
-                        self.get_file_index(),
-                    );
+                    if self.error_counter.report()
+                    {
+                        LexerErrorReporter::incomplete_tqal(
+                            ':',
+                            self.source_path.to_str().unwrap(),
+                            self.report_source(),
+                            offset,
+                        );
+                    }
 
                     // Proceed to next character and attempt to find other errors
-                    self.found_error = true;
+                    self.record_error(
+                        LexError::IncompleteTypeQualifier { offset },
+                        self.get_file_index(),
+                        1,
+                    );
                     None
                 }
             }
@@ -562,42 +1155,146 @@ impl Lexer {
                         is_reserved,
                     ))
                 }
+                // A lone `.` is an error, even next to a number (e.g `.5` or `5.`). Supporting
+                // those would make `5..` and `x.5` harder to read, so the error suggests writing
+                // the missing `0` instead.
                 else
                 {
+                    let offset = self.report_offset(self.get_file_index());
+
                     // error report
-                    LexerErrorReporter::invalid_float(
-                        '.',
-                        self.source_path.to_str().unwrap(),
-                        &self.source_content,
-                        self.get_file_index(),
-                    );
+                    if self.error_counter.report()
+                    {
+                        LexerErrorReporter::invalid_float(
+                            &self.malformed_float(),
+                            self.source_path.to_str().unwrap(),
+                            self.report_source(),
+                            offset,
+                        );
+                    }
 
                     // Proceed to next character and attempt to find other errors
-                    self.found_error = true;
+                    self.record_error(LexError::InvalidFloat { offset }, self.get_file_index(), 1);
                     None
                 }
             }
             // Unknown character found
             ch =>
             {
+                let offset = self.report_offset(self.get_file_index());
+
                 // error report
-                LexerErrorReporter::unsupported_char(
-                    ch,
-                    self.source_path.to_str().unwrap(),
-                    &self.source_content,
+                if self.error_counter.report()
+                {
+                    LexerErrorReporter::unsupported_char(
+                        ch,
+                        self.source_path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
+
+                self.record_error(
+                    LexError::UnsupportedChar { ch, offset },
                     self.get_file_index(),
+                    1,
                 );
-
-                self.found_error = true;
                 None
             }
         }
     }
 
     pub fn lex(&mut self, verbose_mode: bool) -> Result<Vec<Token>, LexError> {
-        // Get raw characters from the loaded source file
-        let mut file_chars = &self.source_content;
+        let mut tokens = self.lex_tokens();
+
+        // If we generated zero errors, return the tokens.
+        // Otherwise, return Err to avoid giving user incorrect output.
+        if !self.found_error
+        {
+            // Append EOF token at the end of the token stream to know when to end the later
+            // parsing stage.
+            Lexer::push_eof_token(&mut tokens);
+            let tokens = self.mark_source_id(tokens);
 
+            if verbose_mode 
+            {
+                println!("[Generated Token Stream]:");
+                dbg!(&tokens);
+            }
+
+            Ok(tokens)
+        }
+        else
+        {
+            // Consumers of this API will probably want to just fail fast, so give them the first
+            // error
+            Err(self.errors.swap_remove(0))
+        }
+    }
+
+    /// Tokenize the source file without failing on errors. Each error is turned into an `Error`
+    /// token carrying the offending text, so tools like highlighters can still use the valid
+    /// parts of the file.
+    ///
+    /// # Returns
+    ///
+    /// Returns the token stream (with `Error` tokens embedded) and the errors that were found.
+    pub fn recover(&mut self, verbose_mode: bool) -> (Vec<Token>, Vec<LexError>) {
+        self.recovering = true;
+
+        let mut tokens = self.lex_tokens();
+
+        // Put the `Error` tokens back into the stream where they were found
+        tokens.append(&mut self.error_tokens);
+        tokens.sort_by_key(|tok| tok.get_file_index());
+
+        Lexer::push_eof_token(&mut tokens);
+        let tokens = self.mark_source_id(tokens);
+
+        if verbose_mode
+        {
+            println!("[Generated Token Stream]:");
+            dbg!(&tokens);
+        }
+
+        (tokens, std::mem::take(&mut self.errors))
+    }
+
+    /// Mark every `Token` in `tokens` as coming from this file
+    fn mark_source_id(&self, tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .map(|tok| tok.with_source_id(self.source_id))
+            .collect()
+    }
+
+    /// Append an `EOF` token to the end of `tokens`
+    fn push_eof_token(tokens: &mut Vec<Token>) {
+        // If we have at least one token in the token stream, then just use the span location
+        // of that for the EOF token. Otherwise, just set span location of EOF token to start
+        // of file. 
+        let last_token = tokens.last();
+        let (span_start, span_end, file_index) = if let Some(last_token) = last_token
+        {
+            (
+                last_token.span_start_ref().to_owned(),
+                last_token.span_end_ref().to_owned(),
+                last_token.get_file_index(),
+            )
+        }
+        else
+        {
+            (SpanPoint::default(), SpanPoint::default(), 0)
+        };
+        let is_reserved = false;
+
+        let eof_tok = Token::new("$$".to_owned(), TokenKind::EOF, span_start, span_end, file_index, is_reserved);
+
+        tokens.push(eof_tok);
+    }
+
+    /// Token building loop shared by `lex` and `recover`. Quits when we reach EOF.
+    fn lex_tokens(&mut self) -> Vec<Token> {
         // Check to see if the file is empty. Just skip while loop if source file is empty.
         let is_empty_file = self.source_content.trim().is_empty();
 
@@ -605,10 +1302,13 @@ impl Lexer {
         let mut tokens: Vec<Token> = Vec::new();
         while !self.eof && !is_empty_file
         {
-            // Get the current character
-            let ch = self
-                .peek_current()
-                .expect("Peeked passed EOF. (Should be impossible here!)");
+            // Get the current character. Skip it if it is not supported.
+            let Some(ch) = self.peek_current()
+            else
+            {
+                self.incre_file_index_by(1);
+                continue;
+            };
 
             // Where we determine what token we think we are building
             if self.current_tok.trim().is_empty()
@@ -625,19 +1325,43 @@ impl Lexer {
                 }
             }
 
+            // A number may end in a type suffix (e.g `5i` or `3.0f`), as long as the suffix is
+            // not the start of a longer word
+            let suffix = self.peek().and_then(NumSuffix::from_char);
+            let after_suffix = self.source_content.chars().nth(self.get_file_index() + 2);
+            if ch.is_numeric()
+                && self.hint_tok == TokenHint::Number
+                && suffix.is_some()
+                && !after_suffix.is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+            {
+                // Consume the number, then move passed the suffix
+                self.current_tok.push(ch);
+                self.incre_file_index_by(1);
+                self.num_suffix = suffix;
+                self.consume_num_or_float_lit(&mut tokens);
+                self.incre_file_index_by(1);
+
+                continue;
+            }
+
             // Make sure numbers do not come directly before letters
             if ch.is_numeric() && self.peek().expect("peeked passed EOF.").is_alphabetic()
             {
+                let offset = self.report_offset(self.get_file_index());
+
                 // error report
-                LexerErrorReporter::invalid_ident(
-                    ch,
-                    self.source_path.to_str().unwrap(),
-                    &self.source_content,
-                    self.get_file_index(),
-                );
+                if self.error_counter.report()
+                {
+                    LexerErrorReporter::invalid_ident(
+                        ch,
+                        self.source_path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
 
                 // Proceed to next character and attempt to find other errors
-                self.found_error = true;
+                self.record_error(LexError::InvalidIdent { offset }, self.get_file_index(), 1);
                 self.incre_file_index_by(1);
                 continue;
             }
@@ -663,16 +1387,25 @@ impl Lexer {
                     .peek()
                     .is_some_and(|ch| !ch.is_alphabetic() && ch != '_')
                 {
+                    let offset = self.report_offset(self.get_file_index());
+
                     // error report
-                    LexerErrorReporter::misplaced_underscore(
-                        ch,
-                        self.source_path.to_str().unwrap(),
-                        &self.source_content,
-                        self.get_file_index(),
-                    );
+                    if self.error_counter.report()
+                    {
+                        LexerErrorReporter::misplaced_underscore(
+                            ch,
+                            self.source_path.to_str().unwrap(),
+                            self.report_source(),
+                            offset,
+                        );
+                    }
 
                     // Proceed to next character and attempt to find other errors
-                    self.found_error = true;
+                    self.record_error(
+                        LexError::MisplacedUnderscore { offset },
+                        self.get_file_index(),
+                        1,
+                    );
                     self.incre_file_index_by(1);
                     continue;
                 }
@@ -712,6 +1445,12 @@ impl Lexer {
                     self.consume_num_or_float_lit(&mut tokens);
                 }
 
+                if self.preserve_trivia
+                {
+                    tokens.push(self.consume_trivia(ch));
+                    continue;
+                }
+
                 // Start at the top of loop to begin processing next character
                 self.incre_file_index_by(1);
                 continue;
@@ -731,6 +1470,19 @@ impl Lexer {
                         "Token hint must be set at this point."
                     );
 
+                    // A `r#` prefix makes the ident that follows it raw. The prefix is not part of
+                    // the ident, so its `Token` only covers the name (e.g `for` in `r#for`).
+                    if ch == '#'
+                        && self.current_tok == "r"
+                        && self.peek().is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+                    {
+                        self.raw_ident = true;
+                        self.hint_tok = TokenHint::Undetermined;
+                        self.current_tok.clear();
+                        self.incre_file_index_by(1);
+                        continue;
+                    }
+
                     // See if `token_kind` is a ident or keyword
                     let (token_kind, is_reserved) = if self.hint_tok == TokenHint::IdentOrKeyword
                     {
@@ -744,8 +1496,7 @@ impl Lexer {
                         }
 
                         // See if we can refine to a more specific token other then `Ident`
-                         TokenKind::refined_or_ident(&self.current_tok)
-                        
+                        self.refined_or_raw_ident()
                     }
                     // See if `token_kind` is number-like
                     else
@@ -781,18 +1532,22 @@ impl Lexer {
 
                     let file_index = self.get_file_index().saturating_sub(token_len);
 
-                    // Make the `Token`
-                    let tok = Token::new(
-                        self.current_tok.clone(),
-                        token_kind,
-                        span_start,
-                        span_end,
-                        file_index,
-                        is_reserved
-                    );
-
-                    // Push `Token` to internal token buffer
-                    tokens.push(tok);
+                    // Push `Token` to internal token buffer, unless it is a literal that the
+                    // backend could not represent
+                    if self.check_num_lit_range(token_kind, file_index)
+                    {
+                        // Make the `Token`
+                        let tok = Token::new(
+                            self.current_tok.clone(),
+                            token_kind,
+                            span_start,
+                            span_end,
+                            file_index,
+                            is_reserved
+                        );
+
+                        tokens.push(tok);
+                    }
 
                     // Reset hint
                     self.hint_tok = TokenHint::Undetermined;
@@ -829,57 +1584,6 @@ impl Lexer {
             self.current_tok.push(ch);
         }
 
-        // If we generated zero errors, return the tokens.
-        // Otherwise, return Err to avoid giving user incorrect output.
-        if !self.found_error
-        {
-            // Append EOF token at the end of the token stream to know when to end the later
-            // parsing stage.
-            let last_token = tokens.last();
-
-            // If we have at least one token in the token stream, then just use the span location
-            // of that for the EOF token. Otherwise, just set span location of EOF token to start
-            // of file. 
-            if last_token.is_some()
-            {
-                let last_token = last_token.unwrap();
-
-                let span_start = last_token.span_start_ref().to_owned();
-                let span_end   = last_token.span_end_ref().to_owned();
-                let file_index = last_token.get_file_index();
-                let is_reserved = false;
-
-               let mut eof_tok = Token::new("$$".to_owned(), TokenKind::EOF, span_start, span_end, file_index, is_reserved);
-
-               tokens.push(eof_tok);
-            }
-            else 
-            {
-                let span_start = SpanPoint::default(); 
-                let span_end   = SpanPoint::default();
-                let file_index = 0;
-                let is_reserved = false;
-
-               let mut eof_tok = Token::new("$$".to_owned(), TokenKind::EOF, span_start, span_end, file_index, is_reserved);
-
-               tokens.push(eof_tok);
-            }
-            
-            if verbose_mode 
-            {
-                println!("[Generated Token Stream]:");
-                dbg!(&tokens);
-            }
-
-            Ok(tokens)
-        }
-        else
-        {
-            // Consumers of this API will probably want to just fail fast
-            Err(LexError::Failed(format!(
-                "`{path}`",
-                path = self.source_path.display()
-            )))
-        }
+        tokens
     }
 }
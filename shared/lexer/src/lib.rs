@@ -1,7 +1,16 @@
+//! Lexer and diagnostics for the Morehead Azalea Compiler.
+//!
+//! Every offset into a source file, from the lexer's index to `Token::file_range`, `SourceMap`
+//! and the offsets given to reporters, counts chars rather than bytes. `ariadne` takes char
+//! offsets too, so a report after a multi-byte char still points at the right spot.
+
 #![allow(unused)]
 
+pub mod diagnostics;
 pub mod errors;
+pub mod interner;
 pub mod lexer;
+pub mod source_map;
 pub mod span;
 pub mod token;
 
@@ -15,8 +24,629 @@ mod tests {
 
         let mut lexer = lexer::Lexer::new("source_test.txt", content);
 
-        let tokens = lexer.lex().unwrap();
+        let tokens = lexer.lex(false).unwrap();
 
         println!("{tokens:#?}");
     }
+
+    #[test]
+    fn token_kind_display() {
+        assert_eq!(format!("{}", token::TokenKind::Assign), "<-");
+        assert_eq!(format!("{}", token::TokenKind::TQualifer), "::");
+    }
+
+    #[test]
+    fn tab_width_moves_columns() {
+        let content = "\tlet x <- 1;";
+
+        let default_tokens = lexer::Lexer::new("test.az", content).lex(false).unwrap();
+        let wide_tokens = lexer::Lexer::with_tab_width("test.az", content, 4)
+            .lex(false)
+            .unwrap();
+
+        // `let` follows the tab
+        assert_eq!(default_tokens[0].span_start_ref().get_col_num(), 2);
+        assert_eq!(wide_tokens[0].span_start_ref().get_col_num(), 5);
+
+        // Offsets into the file are unchanged
+        assert_eq!(default_tokens[0].get_file_index(), wide_tokens[0].get_file_index());
+    }
+
+    #[test]
+    fn recover_keeps_valid_tokens() {
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 1 $ 2;");
+
+        let (tokens, errors) = lexer.recover(false);
+
+        let kinds: Vec<_> = tokens.iter().map(|tok| tok.get_token_kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                token::TokenKind::LetKw,
+                token::TokenKind::Ident,
+                token::TokenKind::Assign,
+                token::TokenKind::NumLit,
+                token::TokenKind::Error,
+                token::TokenKind::NumLit,
+                token::TokenKind::Semicolon,
+                token::TokenKind::EOF,
+            ]
+        );
+        assert_eq!(tokens[4].get_raw_content(), "$");
+        assert_eq!(errors.len(), 1);
+
+        // Fail-fast lexing is still the default
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 1 $ 2;");
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn char_lit() {
+        let mut lexer = lexer::Lexer::new("test.az", "let c <- 'a';");
+
+        let tokens = lexer.lex(false).unwrap();
+
+        assert!(tokens[3].is_a(token::TokenKind::CharLit));
+        assert_eq!(tokens[3].get_raw_content(), "'a'");
+        assert!(tokens[4].is_a(token::TokenKind::Semicolon));
+    }
+
+    #[test]
+    fn escaped_char_lit() {
+        let mut lexer = lexer::Lexer::new("test.az", r"let c <- '\n';");
+
+        let tokens = lexer.lex(false).unwrap();
+
+        assert!(tokens[3].is_a(token::TokenKind::CharLit));
+        assert_eq!(tokens[3].get_raw_content(), r"'\n'");
+        assert!(tokens[4].is_a(token::TokenKind::Semicolon));
+    }
+
+    #[test]
+    fn trivia_tokens_reproduce_source() {
+        let source = "main :: () -> int\nmain = {\n\tlet x  <- 1;\n\n    x\n}\n";
+
+        // Whitespace is skipped by default
+        let tokens = lexer::Lexer::new("test.az", source).lex(false).unwrap();
+        assert!(!tokens.iter().any(|tok| tok.is_a(token::TokenKind::Whitespace)));
+
+        let tokens = lexer::Lexer::new("test.az", source)
+            .with_preserve_trivia(true)
+            .lex(false)
+            .unwrap();
+
+        // Skip the `EOF` token
+        let reconstructed = tokens[..tokens.len() - 1]
+            .iter()
+            .map(|tok| tok.get_raw_content())
+            .collect::<String>();
+        assert_eq!(reconstructed, source);
+
+        // A run of spaces and tabs is one token, but each newline is its own
+        assert!(tokens.iter().any(|tok| tok.get_raw_content() == "  "));
+        let newlines = tokens.iter().filter(|tok| tok.is_a(token::TokenKind::Newline)).count();
+        assert_eq!(newlines, 6);
+    }
+
+    #[test]
+    fn triple_quoted_str_lit() {
+        let source = "let s <- \"\"\"first \"line\"\nsecond\"\"\";\nlet y <- 1;";
+        let mut lexer = lexer::Lexer::new("test.az", source);
+
+        let tokens = lexer.lex(false).unwrap();
+
+        assert!(tokens[3].is_a(token::TokenKind::StrLit));
+        assert_eq!(tokens[3].get_raw_content(), "\"\"\"first \"line\"\nsecond\"\"\"");
+        assert_eq!(tokens[3].span_end_ref().get_line_num(), 2);
+
+        // Tokens after the literal are on the lines they were written on
+        assert!(tokens[4].is_a(token::TokenKind::Semicolon));
+        assert_eq!(tokens[4].span_start_ref().get_line_num(), 2);
+        assert_eq!(tokens[5].span_start_ref().get_line_num(), 3);
+    }
+
+    #[test]
+    fn unterminated_str_lit_is_rejected() {
+        let mut lexer = lexer::Lexer::new("test.az", "let s <- \"\"\"never\nclosed\";\n");
+
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn multi_char_lit_is_rejected() {
+        let mut lexer = lexer::Lexer::new("test.az", "let c <- 'ab';");
+
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn empty_char_lit_is_rejected() {
+        let mut lexer = lexer::Lexer::new("test.az", "let c <- '';");
+
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn reporting_stops_at_max_errors() {
+        use std::rc::Rc;
+
+        let source = "let x <- 1 ".to_string() + &"$ ".repeat(30) + ";";
+        let error_counter = Rc::new(diagnostics::ErrorCounter::new(5));
+        let mut lexer = lexer::Lexer::new("test.az", &source)
+            .with_error_counter(Rc::clone(&error_counter));
+
+        assert!(lexer.lex(false).is_err());
+        assert_eq!(error_counter.reported(), 5);
+        assert_eq!(error_counter.suppressed(), 25);
+    }
+
+    #[test]
+    fn lex_error_as_json() {
+        use diagnostics::MessageFormat;
+
+        diagnostics::set_message_format(MessageFormat::Json);
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 1;\nlet y <- $;");
+        assert!(lexer.lex(false).is_err());
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        let collected = diagnostics::take_diagnostics();
+        assert_eq!(collected.len(), 1);
+        assert_eq!(
+            serde_json::to_string(&collected[0]).unwrap(),
+            r#"{"file":"test.az","line":2,"col":10,"code":0,"severity":"error","message":"Unsupported Character: `$` is an unsupported character"}"#
+        );
+    }
+
+    #[test]
+    fn int_lit_in_range() {
+        let source = format!("let x <- {};", i64::MAX);
+        let tokens = lexer::Lexer::new("test.az", &source).lex(false).unwrap();
+
+        assert!(tokens[3].is_a(token::TokenKind::NumLit));
+    }
+
+    #[test]
+    fn int_lit_overflow_is_rejected() {
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 99999999999999999999;");
+
+        assert!(lexer.lex(false).is_err());
+
+        // The `-` is not part of the literal, so `i64::MIN` has to be built from `i64::MIN + 1`
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- -9223372036854775808;");
+        assert!(lexer.lex(false).is_err());
+
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- -9223372036854775807 - 1;");
+        assert!(lexer.lex(false).is_ok());
+    }
+
+    #[test]
+    fn num_lit_suffixes() {
+        use token::{NumSuffix, TokenKind};
+
+        let source = "let x <- 5i + 3.0f * 2f - 7; y <- 0i;\n";
+        let tokens = lexer::Lexer::new("test.az", source).lex(false).unwrap();
+
+        let lits = tokens
+            .iter()
+            .filter(|tok| tok.is_a(TokenKind::NumLit) || tok.is_a(TokenKind::FloatLit))
+            .map(|tok| (tok.get_raw_content(), tok.get_token_kind(), tok.num_suffix()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lits,
+            [
+                ("5", TokenKind::NumLit, Some(NumSuffix::Int)),
+                ("3.0", TokenKind::FloatLit, Some(NumSuffix::Float)),
+                ("2", TokenKind::FloatLit, Some(NumSuffix::Float)),
+                ("7", TokenKind::NumLit, None),
+                ("0", TokenKind::NumLit, Some(NumSuffix::Int)),
+            ]
+        );
+
+        // Printed with the suffix they were written with
+        assert_eq!(tokens[3].to_string(), "5i");
+        assert_eq!(tokens[5].to_string(), "3.0f");
+    }
+
+    #[test]
+    fn bad_num_lit_suffixes_are_rejected() {
+        // A float cannot have an `int` suffix
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 3.0i;");
+        assert!(lexer.lex(false).is_err());
+
+        // An integer with a `float` suffix must still fit in an `int`
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 99999999999999999999f;");
+        assert!(lexer.lex(false).is_err());
+
+        // Only a lone `i` or `f` is a suffix
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 5if;");
+        assert!(lexer.lex(false).is_err());
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 5u;");
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn floats_without_leading_or_trailing_digits_are_rejected() {
+        use diagnostics::MessageFormat;
+
+        diagnostics::set_message_format(MessageFormat::Json);
+        for (source, fix) in [("let x <- .5;\n", "`0.5`"), ("let x <- 5.;\n", "`5.0`")]
+        {
+            assert!(lexer::Lexer::new("test.az", source).lex(false).is_err(), "{source}");
+
+            let reported = diagnostics::take_diagnostics();
+            assert_eq!(reported.len(), 1, "{source}");
+            assert!(reported[0].message.contains(&format!("Write {fix} instead")), "{source}");
+        }
+        diagnostics::set_message_format(MessageFormat::Human);
+    }
+
+    #[test]
+    fn float_lit_overflow_is_rejected() {
+        // Parses to infinity
+        let source = format!("let x <- 1{}.0;", "0".repeat(400));
+        let mut lexer = lexer::Lexer::new("test.az", &source);
+        assert!(lexer.lex(false).is_err());
+
+        // Exponents are not supported
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 1e400;");
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn lex_bytes_with_invalid_byte() {
+        use diagnostics::MessageFormat;
+
+        let content = b"let x <- 1;\nlet y <- \xff;\n";
+
+        diagnostics::set_message_format(MessageFormat::Json);
+        let mut lexer = lexer::Lexer::from_bytes("test.az", content);
+        let (tokens, errors) = lexer.recover(false);
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert_eq!(errors.len(), 1);
+        let error_tokens = tokens.iter().filter(|tok| tok.is_a(token::TokenKind::Error));
+        assert_eq!(error_tokens.count(), 1);
+
+        // Reported right at the bad byte
+        let collected = diagnostics::take_diagnostics();
+        assert_eq!(collected.len(), 1);
+        assert_eq!((collected[0].line, collected[0].col), (2, 10));
+    }
+
+    #[test]
+    fn lex_bytes_with_control_byte() {
+        let mut lexer = lexer::Lexer::from_bytes("test.az", b"main :: (\x01 -> int { 0 }\n");
+
+        assert!(lexer.lex(false).is_err());
+    }
+
+    #[test]
+    fn two_char_token_spans() {
+        for op in ["->", ">=", "::", "<-", "<=", "==", "!=", "=>", ".."]
+        {
+            // In the middle of the file, then ending it
+            for source in [format!("x {op} y\n"), format!("x {op}")]
+            {
+                let tokens = lexer::Lexer::new("test.az", &source).lex(false).unwrap();
+                let tok = &tokens[1];
+
+                assert_eq!(tok.get_raw_content(), op, "in {source:?}");
+                assert_eq!(tok.get_file_index(), 2, "in {source:?}");
+                assert_eq!(tok.span_start_ref().get_col_num(), 3, "in {source:?}");
+                assert_eq!(tok.span_end_ref().get_col_num(), 4, "in {source:?}");
+                assert_eq!(tok.span_start_ref().get_line_num(), tok.span_end_ref().get_line_num());
+            }
+        }
+    }
+
+    #[test]
+    fn one_char_token_ends_file() {
+        for op in ["-", ">", "=", "<", "!"]
+        {
+            let tokens = lexer::Lexer::new("test.az", format!("x {op}")).lex(false).unwrap();
+
+            assert_eq!(tokens[1].get_raw_content(), op);
+            assert_eq!(tokens[1].span_start_ref().get_col_num(), 3);
+            assert_eq!(tokens[1].span_end_ref().get_col_num(), 3);
+        }
+    }
+
+    #[test]
+    fn first_token_span_starts_at_file_start() {
+        for source in ["a_very_long_identifier <- 1;", "1234567890 + x;", "1234.5678 + x;"]
+        {
+            let tokens = lexer::Lexer::new("test.az", source).lex(false).unwrap();
+            let len = tokens[0].get_raw_content().len();
+
+            assert_eq!(tokens[0].get_file_index(), 0, "in {source:?}");
+            assert_eq!(tokens[0].span_start_ref().get_col_num(), 1, "in {source:?}");
+            assert_eq!(tokens[0].span_end_ref().get_col_num(), len, "in {source:?}");
+        }
+    }
+
+    #[test]
+    fn keyword_lookup_matches_keyword_table() {
+        use std::collections::BTreeMap;
+        use token::TokenKind;
+
+        let reserved = BTreeMap::from_iter(TokenKind::keywords());
+        assert_eq!(reserved.len(), 22);
+
+        // Every keyword plus idents that are close to one
+        let words = reserved.keys().copied().chain(["lets", "iff", "Int", "_if", "x"]);
+        let source = (words.clone().collect::<Vec<_>>().join(" ") + "\n").repeat(100);
+
+        let tokens = lexer::Lexer::new("test.az", &source).lex(false).unwrap();
+        let expected = words.cycle().map(|word| match reserved.get(word)
+        {
+            Some(kind) => (*kind, true),
+            None => (TokenKind::Ident, false),
+        });
+
+        // Skip the `EOF` token
+        assert_eq!(tokens.len(), 100 * (reserved.len() + 5) + 1);
+        for (tok, expected) in tokens[..tokens.len() - 1].iter().zip(expected)
+        {
+            assert_eq!(TokenKind::refined_or_ident(tok.get_raw_content()), expected);
+            assert_eq!(tok.get_token_kind(), expected.0);
+        }
+    }
+
+    #[test]
+    fn offset_to_line_col() {
+        let span = span::Span::new("let x <- 1;\n\nmain = {\n\t0 }\n");
+
+        assert_eq!(span.line_starts(), &[0, 12, 13, 22, 27]);
+        assert_eq!(span.offset_to_line_col(0), (1, 1));
+        assert_eq!(span.offset_to_line_col(4), (1, 5));
+        assert_eq!(span.offset_to_line_col(11), (1, 12));
+        assert_eq!(span.offset_to_line_col(12), (2, 1));
+        assert_eq!(span.offset_to_line_col(13), (3, 1));
+        assert_eq!(span.offset_to_line_col(20), (3, 8));
+        assert_eq!(span.offset_to_line_col(23), (4, 2));
+        assert_eq!(span.offset_to_line_col(27), (5, 1));
+
+        // `é` is two bytes, but only one char
+        let span = span::Span::new("é <- 1;\nx");
+        assert_eq!(span.line_starts(), &[0, 8]);
+        assert_eq!(span.offset_to_line_col(2), (1, 3));
+        assert_eq!(span.offset_to_line_col(8), (2, 1));
+    }
+
+    #[test]
+    fn file_index_is_clamped_to_eof() {
+        let mut lexer = lexer::Lexer::new("test.az", "let x <- 1;\n");
+        lexer.incre_file_index_by(3);
+        assert_eq!(lexer.get_file_index(), 3);
+
+        // Would overflow `usize` without saturating
+        lexer.incre_file_index_by(usize::MAX);
+        assert_eq!(lexer.get_file_index(), 12);
+        assert!(lexer.is_eof());
+
+        lexer.incre_file_index_by(100);
+        assert_eq!(lexer.get_file_index(), 12);
+
+        // The end of the file is counted in chars
+        let mut lexer = lexer::Lexer::new("test.az", "é\n");
+        lexer.incre_file_index_by(100);
+        assert_eq!(lexer.get_file_index(), 2);
+    }
+
+    #[test]
+    fn snippet_around_offset() {
+        let span = span::Span::new("a :: ()\nb :: ()\nc :: ()\nd :: ()\ne :: ()\n");
+
+        // Offset 17 is the `c` on line 3
+        assert_eq!(span.snippet(17, 0), "c :: ()");
+        assert_eq!(span.snippet(17, 1), "b :: ()\nc :: ()\nd :: ()");
+
+        // Context is cut off at the start and end of the file
+        assert_eq!(span.snippet(0, 1), "a :: ()\nb :: ()");
+        assert_eq!(span.snippet(33, 2), "c :: ()\nd :: ()\ne :: ()");
+    }
+
+    #[test]
+    fn interned_ids_round_trip() {
+        let mut interner = interner::StringInterner::new();
+
+        let x = interner.intern("x");
+        let add = interner.intern("add");
+        assert_eq!(interner.intern("x"), x);
+        assert_ne!(x, add);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(x), Some("x"));
+        assert_eq!(interner.resolve(add), Some("add"));
+        assert_eq!(interner.get("y"), None);
+
+        // Identifiers with the same text share a `Symbol`, wherever they are
+        let tokens = lexer::Lexer::new("test.az", "x <- add(x, y);\n").lex(false).unwrap();
+        assert_eq!(tokens[0].symbol(), tokens[4].symbol());
+        assert_ne!(tokens[0].symbol(), tokens[6].symbol());
+        for tok in &tokens
+        {
+            match tok.symbol()
+            {
+                Some(symbol) => assert_eq!(symbol.resolve(), tok.get_raw_content()),
+                None => assert!(!tok.is_a(token::TokenKind::Ident)),
+            }
+        }
+
+        // Punctuation is not interned
+        assert_eq!(tokens[1].symbol(), None);
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert_eq!(interner.get("x"), None);
+    }
+
+    #[test]
+    fn tokens_equal_by_kind_and_text() {
+        use std::collections::{HashMap, HashSet};
+
+        use token::{Token, TokenKind};
+
+        let tokens = lexer::Lexer::new("test.az", "x <- add(x, 1);
+add <- x;
+").lex(false).unwrap();
+        let (first_x, second_x, last_x) = (&tokens[0], &tokens[4], &tokens[11]);
+
+        // Different places, same name
+        assert_ne!(first_x.get_file_index(), second_x.get_file_index());
+        assert_eq!(first_x, second_x);
+        assert_ne!(first_x, &tokens[2]);
+
+        let mut seen = HashSet::new();
+        let dups = tokens
+            .iter()
+            .filter(|tok| tok.is_a(TokenKind::Ident))
+            .filter(|tok| !seen.insert(*tok))
+            .map(|tok| tok.get_raw_content())
+            .collect::<Vec<_>>();
+        assert_eq!(dups, ["x", "add", "x"]);
+        assert_eq!(seen.len(), 2);
+
+        let mut uses = HashMap::<&Token, usize>::new();
+        for tok in tokens.iter().filter(|tok| tok.is_a(TokenKind::Ident))
+        {
+            *uses.entry(tok).or_default() += 1;
+        }
+        assert_eq!(uses[last_x], 3);
+
+        // Same text, different kind
+        let ident = tokens[0].clone();
+        let text = Token::new(
+            "x".to_string(),
+            TokenKind::CharLit,
+            *ident.span_start_ref(),
+            *ident.span_end_ref(),
+            ident.get_file_index(),
+            false,
+        );
+        assert_ne!(ident, text);
+    }
+
+    #[test]
+    fn raw_idents_are_never_keywords() {
+        use token::TokenKind;
+
+        let source = "for r#for in 0..3 { let r#_if <- r#for; r <- r#x; }\n";
+        let tokens = lexer::Lexer::new("test.az", source).lex(false).unwrap();
+
+        assert_eq!(tokens[0].get_token_kind(), TokenKind::ForKw);
+        assert_eq!(tokens[1].get_token_kind(), TokenKind::Ident);
+        assert_eq!(tokens[1].get_raw_content(), "for");
+
+        // The `Token` covers the name, not the `r#` prefix
+        assert_eq!(tokens[1].get_file_index(), 6);
+        assert_eq!(tokens[1].span_start_ref().get_col_num(), 7);
+        assert_eq!(tokens[1].to_string(), "r#for");
+
+        let idents = tokens
+            .iter()
+            .filter(|tok| tok.is_a(TokenKind::Ident))
+            .map(|tok| tok.get_raw_content())
+            .collect::<Vec<_>>();
+        assert_eq!(idents, ["for", "_if", "for", "r", "x"]);
+    }
+
+    #[test]
+    fn custom_keywords_override_defaults() {
+        use std::collections::HashMap;
+
+        use token::TokenKind;
+
+        let keywords = HashMap::from([
+            ("fn".to_string(), TokenKind::FnDef),
+            ("let".to_string(), TokenKind::Ident),
+        ]);
+        let tokens = lexer::Lexer::new("test.az", "fn main let\n")
+            .with_keywords(keywords)
+            .lex(false)
+            .unwrap();
+
+        assert!(tokens[0].is_a(TokenKind::FnDef));
+        assert!(tokens[1].is_a(TokenKind::MainKw));
+        assert!(tokens[2].is_a(TokenKind::Ident));
+
+        // Without custom keywords, `fn` is a plain ident
+        let tokens = lexer::Lexer::new("test.az", "fn main\n").lex(false).unwrap();
+        assert!(tokens[0].is_a(TokenKind::Ident));
+    }
+
+    #[test]
+    fn token_kind_codes_are_unique() {
+        use std::collections::HashSet;
+
+        use token::TokenKind;
+
+        // One entry per variant of `TokenKind`
+        let all = TokenKind::all();
+        assert_eq!(all.len(), 56);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), all.len());
+
+        let codes = all.iter().map(TokenKind::code).collect::<HashSet<_>>();
+        assert_eq!(codes.len(), all.len());
+
+        // Codes that tools may already rely on
+        assert_eq!(TokenKind::Ident.code(), 0);
+        assert_eq!(TokenKind::Assign.code(), 7);
+        assert_eq!(TokenKind::EOF.code(), 52);
+        assert_eq!(TokenKind::StrLit.code(), 53);
+        assert_eq!(TokenKind::Newline.code(), 55);
+    }
+
+    #[test]
+    fn token_kind_groupings() {
+        use token::{TokenKind, INFIX_OP_TOKENS, LITERAL_TOKENS, PRIM_TYPE_TOKENS, TYPE_TOKENS};
+
+        assert!(PRIM_TYPE_TOKENS.contains(&TokenKind::IntTy));
+        assert!(!PRIM_TYPE_TOKENS.contains(&TokenKind::Ident));
+        assert!(TYPE_TOKENS.contains(&TokenKind::Ident));
+        assert!(LITERAL_TOKENS.contains(&TokenKind::FloatLit));
+        assert!(INFIX_OP_TOKENS.contains(&TokenKind::AndKw));
+        assert!(!INFIX_OP_TOKENS.contains(&TokenKind::Assign));
+
+        // Every primitive type keyword is also a type
+        assert!(PRIM_TYPE_TOKENS.iter().all(|kind| kind.is_type_keyword() && kind.is_type()));
+        assert!(TokenKind::Ident.is_type() && !TokenKind::Ident.is_type_keyword());
+    }
+
+    #[test]
+    fn lex_errors_say_what_went_wrong() {
+        use errors::LexError;
+
+        let lex_error = |source: &str| {
+            let result = diagnostics::collected(|| lexer::Lexer::new("test.az", source).lex(false));
+            result.0.unwrap_err()
+        };
+
+        assert_eq!(lex_error("let x <- 1 $ 2;"), LexError::UnsupportedChar { ch: '$', offset: 11 });
+        assert_eq!(lex_error("let x : int"), LexError::IncompleteTypeQualifier { offset: 6 });
+        assert_eq!(lex_error("let x <- .5;"), LexError::InvalidFloat { offset: 9 });
+        assert_eq!(lex_error("let 1x <- 5;"), LexError::InvalidIdent { offset: 4 });
+        assert_eq!(lex_error("let c <- 'ab';"), LexError::InvalidCharLit { offset: 9 });
+        assert_eq!(
+            lex_error("let x <- 99999999999999999999;"),
+            LexError::IntOverflow { offset: 9 }
+        );
+
+        // `lex` gives back the first error when there are several
+        assert_eq!(lex_error("$ 1x").offset(), 0);
+    }
+
+    #[test]
+    fn synthetic_token_covers_nothing() {
+        use token::{Token, TokenKind};
+
+        let semicolon = Token::synthetic(TokenKind::Semicolon).with_position(7, Default::default());
+
+        assert!(semicolon.is_synthetic());
+        assert_eq!(semicolon.get_raw_content(), ";");
+        assert_eq!(semicolon.file_range(), 7..7);
+
+        // Lexed tokens are all real
+        let tokens = lexer::Lexer::new("test.az", "let x <- 1;").lex(false).unwrap();
+        assert!(tokens.iter().all(|tok| !tok.is_synthetic()));
+    }
 }
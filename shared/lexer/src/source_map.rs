@@ -0,0 +1,86 @@
+//! Helpful API that maps offsets in the cleaned (preprocessed) source back to the original
+//! source file.
+//!
+//! The preprocessor strips comments, so the source the lexer and parser see is not always the
+//! same length as the file the user wrote. A `SourceMap` lets error reporters point into the
+//! original file instead.
+
+use crate::span::{Span, SpanPoint};
+
+/// A `#line N "file"` directive, saying that the line after it is line `N` of `file`. Tools
+/// that generate Azalea code use it so errors point into the file they generated it from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDirective {
+    /// Line of the directive in the original source file (starting at 1)
+    pub line: usize,
+
+    /// Line number of the line after the directive
+    pub target_line: usize,
+
+    /// File the lines after the directive came from, or `None` to keep the current file
+    pub target_file: Option<String>,
+}
+
+impl LineDirective {
+    /// File and line that `line` (a line after this directive) is remapped to
+    pub fn remap<'a>(&'a self, line: usize, file: &'a str) -> (&'a str, usize) {
+        let file = self.target_file.as_deref().unwrap_or(file);
+
+        (file, self.target_line + line.saturating_sub(self.line + 1))
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct SourceMap {
+    /// Content of the original source file
+    original_source: String,
+
+    /// Original char offset for each char offset in the cleaned source
+    offsets: Vec<usize>,
+}
+
+impl SourceMap {
+    pub fn new(original_source: String, offsets: Vec<usize>) -> Self {
+        Self {
+            original_source,
+            offsets,
+        }
+    }
+
+    /// Build a `SourceMap` where every cleaned offset maps to itself (e.g nothing was stripped)
+    pub fn identity<S: AsRef<str>>(source: S) -> Self {
+        let source = source.as_ref();
+        let offsets = (0..source.chars().count()).collect();
+
+        SourceMap::new(source.to_string(), offsets)
+    }
+
+    pub fn original_source(&self) -> &str {
+        &self.original_source
+    }
+
+    /// Maps a char offset in the cleaned source to the char offset in the original source
+    ///
+    /// # Parameters
+    ///
+    /// - `cleaned_offset`: Char offset into the cleaned source
+    ///
+    /// # Returns
+    ///
+    /// Returns the original char offset. Offsets past the end of the cleaned source (e.g the
+    /// `EOF` token) map to the end of the original source.
+    pub fn original_offset(&self, cleaned_offset: usize) -> usize {
+        match self.offsets.get(cleaned_offset)
+        {
+            Some(offset) => *offset,
+            None => self.original_source.chars().count(),
+        }
+    }
+
+    /// Maps a char offset in the cleaned source to its line and column in the original source
+    pub fn original_point(&self, cleaned_offset: usize) -> Option<SpanPoint> {
+        let span = Span::new(&self.original_source);
+
+        span.get(self.original_offset(cleaned_offset)).copied()
+    }
+}
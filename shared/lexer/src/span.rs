@@ -4,9 +4,12 @@
 
 use std::ops::Index;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Default, Debug, Clone, Copy)]
+/// UTF-8 byte order mark. Some editors put one at the start of a file. It takes up no columns.
+pub const BOM: char = '\u{feff}';
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
 /// `SpanPoint` represents an individual point within the range of a `Span`
 /// Specifically, you can use `SpanPoint` to get the
 pub struct SpanPoint {
@@ -46,6 +49,12 @@ impl SpanPoint {
 #[derive(Default, Debug, Clone)]
 pub struct Span {
     points: Vec<SpanPoint>,
+
+    /// Char offset of the start of each line, in order
+    line_starts: Vec<usize>,
+
+    /// The file the `Span` was built from, for pulling out snippets
+    file_content: String,
 }
 
 impl std::fmt::Display for SpanPoint {
@@ -56,25 +65,47 @@ impl std::fmt::Display for SpanPoint {
 
 impl Span {
     pub fn new<P: AsRef<str>>(file_content: P) -> Self {
+        // A tab is treated as a single column by default
+        let tab_width = 1;
+
+        Span::with_tab_width(file_content, tab_width)
+    }
+
+    /// Build a `Span` where a `\t` advances the column number by `tab_width` columns instead of
+    /// one. This keeps column numbers in line with how editors render tabs. Indexing into the
+    /// `Span` still happens by file offset, so offsets are unaffected.
+    pub fn with_tab_width<P: AsRef<str>>(file_content: P, tab_width: usize) -> Self {
         // Naturally, we start the span in the source file at (1, 1)
         let (mut line_num, mut col_num) = (1, 1);
 
         // Build `Span` from the chars of the source file
         let mut span = Vec::new();
-        for ch in file_content.as_ref().chars()
+        let mut chars = file_content.as_ref().chars().peekable();
+        while let Some(ch) = chars.next()
         {
-            if ch.is_ascii()
+            // Keep a point for a BOM so offsets after it still line up, but do not move the column
+            if ch == BOM
+            {
+                span.push(SpanPoint::new(line_num, col_num, ch));
+            }
+            else if ch.is_ascii()
             {
                 // Save `SpanPoint`
                 span.push(SpanPoint::new(line_num, col_num, ch));
 
                 // Update `Span` position via line and column number.
-                // If we hit a newline, move span to the next line.
-                if ch == '\n'
+                // If we hit a newline, move span to the next line. A `\r` ends a line too,
+                // unless it is the first half of a `\r\n`, where the `\n` ends it.
+                if ch == '\n' || (ch == '\r' && chars.peek() != Some(&'\n'))
                 {
                     line_num += 1;
                     col_num = 1;
                 }
+                // Tabs may be rendered wider than one column
+                else if ch == '\t'
+                {
+                    col_num += tab_width;
+                }
                 // Otherwise, just increment the span up in the current line
                 else
                 {
@@ -83,7 +114,75 @@ impl Span {
             }
         }
 
-        Span { points: span }
+        Span {
+            points: span,
+            line_starts: Span::line_starts_of(file_content.as_ref()),
+            file_content: file_content.as_ref().to_string(),
+        }
+    }
+
+    // Char offset of the start of every line in `file_content`. The first line starts at `0`.
+    // Lines end in `\n`, `\r\n` or a lone `\r`.
+    fn line_starts_of(file_content: &str) -> Vec<usize> {
+        let chars = file_content.chars().collect::<Vec<_>>();
+        let newlines = chars
+            .iter()
+            .enumerate()
+            .filter(|(offset, ch)| {
+                **ch == '\n' || (**ch == '\r' && chars.get(offset + 1) != Some(&'\n'))
+            })
+            .map(|(offset, _)| offset + 1);
+
+        std::iter::once(0).chain(newlines).collect()
+    }
+}
+
+impl Span {
+    /// Get the `SpanPoint` at `index`, or `None` if `index` is passed the end of the `Span`
+    pub fn get(&self, index: usize) -> Option<&SpanPoint> {
+        self.points.get(index)
+    }
+
+    /// Char offset of the start of each line of the file
+    pub fn line_starts(&self) -> &[usize] {
+        &self.line_starts
+    }
+
+    /// Line and column (both starting at 1) of the char at `offset` in the file
+    ///
+    /// Unlike the `SpanPoint`s, a `\t` is always a single column, which is what editors expect
+    /// when converting positions. An `offset` passed the end of the file lands on
+    /// the last line.
+    pub fn offset_to_line_col(&self, offset: usize) -> (usize, usize) {
+        // Index of the last line starting at or before `offset`
+        let line = match self.line_starts.binary_search(&offset)
+        {
+            Ok(line) => line,
+            Err(next_line) => next_line.saturating_sub(1),
+        };
+        let line_start = self.line_starts.get(line).copied().unwrap_or(0);
+
+        (line + 1, offset - line_start + 1)
+    }
+
+    /// The line holding the char at `offset`, with up to `context_lines` lines on either side of
+    /// it. Lines are joined with `\n` and the snippet does not end in one.
+    ///
+    /// Handy for embedding a code excerpt in a diagnostic (e.g JSON output) without the whole
+    /// file.
+    pub fn snippet(&self, offset: usize, context_lines: usize) -> String {
+        let (line, _) = self.offset_to_line_col(offset);
+
+        // `line` starts at 1
+        let first = (line - 1).saturating_sub(context_lines);
+        let count = line - first + context_lines;
+
+        self.file_content
+            .lines()
+            .skip(first)
+            .take(count)
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 }
 
@@ -2,13 +2,15 @@
 //!
 //! To add support for a new `Token`, you must first add it to this file.
 
-use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 
+use crate::interner::Symbol;
 use crate::span::SpanPoint;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
     
-#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TokenKind {
     Ident,
     IntTy,
@@ -25,6 +27,8 @@ pub enum TokenKind {
     NumLit,
     BoolLit,
     FloatLit,
+    CharLit,
+    StrLit,
     Lt,
     Lte,
     Gt,
@@ -43,6 +47,7 @@ pub enum TokenKind {
     Sep,
     FnDef,
     RetArrow,
+    FatArrow,
     StructKw,
     ChoiceKw,
     MainKw,
@@ -56,6 +61,12 @@ pub enum TokenKind {
     ElseKw,
     ForKw,
     WhileKw,
+    BreakKw,
+    ContinueKw,
+    MatchKw,
+    Whitespace,
+    Newline,
+    Error,
     EOF
 
 }
@@ -75,6 +86,8 @@ impl From<TokenKind> for &str {
             TokenKind::NumLit => "number literal",
             TokenKind::BoolLit => "bool literal",
             TokenKind::FloatLit => "float literal",
+            TokenKind::CharLit => "char literal",
+            TokenKind::StrLit => "string literal",
             TokenKind::Lt => "<",
             TokenKind::Lte => "<=",
             TokenKind::Gt => ">",
@@ -91,6 +104,7 @@ impl From<TokenKind> for &str {
             TokenKind::Sep => ",",
             TokenKind::FnDef => "=",
             TokenKind::RetArrow => "->",
+            TokenKind::FatArrow => "=>",
             TokenKind::IntTy => "int",
             TokenKind::FloatTy => "float",
             TokenKind::TextTy => "text",
@@ -108,42 +122,197 @@ impl From<TokenKind> for &str {
             TokenKind::ElseKw => "else",
             TokenKind::WhileKw => "while",
             TokenKind::ForKw => "for",
+            TokenKind::BreakKw => "break",
+            TokenKind::ContinueKw => "continue",
+            TokenKind::MatchKw => "match",
 	    TokenKind::NEq => "!=",
 	    TokenKind::Not => "!",
+            TokenKind::Whitespace => "whitespace",
+            TokenKind::Newline => "newline",
+            TokenKind::Error => "error",
             TokenKind::EOF => "$$"
         }
     }
 }
 
+// Way to print a `TokenKind` as it is written in source using println!()
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind: &str = (*self).into();
+
+        write!(f, "{kind}")
+    }
+}
+
+/// Primitive type keywords
+pub const PRIM_TYPE_TOKENS: &[TokenKind] =
+    &[TokenKind::IntTy, TokenKind::BoolTy, TokenKind::TextTy, TokenKind::FloatTy];
+
+/// Kinds that can name a type. An `Ident` names a structure or choice.
+pub const TYPE_TOKENS: &[TokenKind] = &[
+    TokenKind::Ident, TokenKind::IntTy, TokenKind::FloatTy, TokenKind::TextTy, TokenKind::BoolTy,
+];
+
+/// Literals an expression can be made of
+pub const LITERAL_TOKENS: &[TokenKind] =
+    &[TokenKind::BoolLit, TokenKind::NumLit, TokenKind::FloatLit, TokenKind::CharLit];
+
+/// Operators that can start an expression
+pub const PREFIX_OP_TOKENS: &[TokenKind] = &[TokenKind::Minus];
+
+/// Operators that sit between the two operands of an expression
+pub const INFIX_OP_TOKENS: &[TokenKind] = &[
+    TokenKind::Plus, TokenKind::Minus, TokenKind::Div, TokenKind::Mul, TokenKind::Lt,
+    TokenKind::Lte, TokenKind::Gt, TokenKind::Gte, TokenKind::Eq, TokenKind::OrKw,
+    TokenKind::AndKw, TokenKind::AsKw, TokenKind::RecordDot, TokenKind::TQualifer,
+];
+
 impl TokenKind {
-    // Helper to generically check if
+    /// Every `TokenKind`, in declaration order. New kinds must be added here as well.
+    pub fn all() -> &'static [TokenKind] {
+        &[
+            TokenKind::Ident, TokenKind::IntTy, TokenKind::FloatTy, TokenKind::TextTy,
+            TokenKind::BoolTy, TokenKind::TQualifer, TokenKind::Semicolon, TokenKind::Assign,
+            TokenKind::Plus, TokenKind::Minus, TokenKind::Div, TokenKind::Mul, TokenKind::NumLit,
+            TokenKind::BoolLit, TokenKind::FloatLit, TokenKind::CharLit, TokenKind::StrLit,
+            TokenKind::Lt, TokenKind::Lte, TokenKind::Gt, TokenKind::Gte, TokenKind::Eq,
+            TokenKind::NEq, TokenKind::Not, TokenKind::RecordDot, TokenKind::ExRange,
+            TokenKind::LParn, TokenKind::RParn, TokenKind::LBracket, TokenKind::RBracket,
+            TokenKind::LSBracket, TokenKind::RSBracket, TokenKind::Sep, TokenKind::FnDef,
+            TokenKind::RetArrow, TokenKind::FatArrow, TokenKind::StructKw, TokenKind::ChoiceKw,
+            TokenKind::MainKw, TokenKind::AsKw, TokenKind::AndKw, TokenKind::OrKw, TokenKind::InKw,
+            TokenKind::LetKw, TokenKind::IfKw, TokenKind::ElifKw, TokenKind::ElseKw,
+            TokenKind::ForKw, TokenKind::WhileKw, TokenKind::BreakKw, TokenKind::ContinueKw,
+            TokenKind::MatchKw, TokenKind::Whitespace, TokenKind::Newline, TokenKind::Error,
+            TokenKind::EOF,
+        ]
+    }
+
+    /// Every reserved word along with the `TokenKind` it lexes to, worked out from `all` and how
+    /// each kind is written. Slower than the lookup the lexer does, but handy to check it against.
+    pub fn keywords() -> Vec<(&'static str, TokenKind)> {
+        // Kinds with no fixed spelling, so their names are not reserved
+        const UNWRITTEN: &[TokenKind] =
+            &[TokenKind::Ident, TokenKind::Whitespace, TokenKind::Newline, TokenKind::Error];
+
+        let mut keywords = TokenKind::all()
+            .iter()
+            .filter(|kind| !UNWRITTEN.contains(kind))
+            .map(|kind| (<&str>::from(*kind), *kind))
+            .filter(|(word, _)| word.chars().all(|ch| ch.is_ascii_lowercase()))
+            .collect::<Vec<_>>();
+        keywords.extend([("true", TokenKind::BoolLit), ("false", TokenKind::BoolLit)]);
+
+        keywords
+    }
+
+    /// `true` if this is one of the primitive type keywords (e.g `int`)
+    pub fn is_type_keyword(&self) -> bool {
+        PRIM_TYPE_TOKENS.contains(self)
+    }
+
+    /// `true` if this can name a type: a primitive type keyword, or an `Ident` naming a structure
+    /// or choice
+    pub fn is_type(&self) -> bool {
+        TYPE_TOKENS.contains(self)
+    }
+
+    /// Stable numeric code of this `TokenKind` for external tools (e.g a syntax highlighter).
+    ///
+    /// NOTE: Codes never change once given out, even if the variants are reordered. A new kind
+    /// gets the next unused code.
+    pub fn code(&self) -> u16 {
+        match self
+        {
+            TokenKind::Ident => 0,
+            TokenKind::IntTy => 1,
+            TokenKind::FloatTy => 2,
+            TokenKind::TextTy => 3,
+            TokenKind::BoolTy => 4,
+            TokenKind::TQualifer => 5,
+            TokenKind::Semicolon => 6,
+            TokenKind::Assign => 7,
+            TokenKind::Plus => 8,
+            TokenKind::Minus => 9,
+            TokenKind::Div => 10,
+            TokenKind::Mul => 11,
+            TokenKind::NumLit => 12,
+            TokenKind::BoolLit => 13,
+            TokenKind::FloatLit => 14,
+            TokenKind::CharLit => 15,
+            TokenKind::Lt => 16,
+            TokenKind::Lte => 17,
+            TokenKind::Gt => 18,
+            TokenKind::Gte => 19,
+            TokenKind::Eq => 20,
+            TokenKind::NEq => 21,
+            TokenKind::Not => 22,
+            TokenKind::RecordDot => 23,
+            TokenKind::ExRange => 24,
+            TokenKind::LParn => 25,
+            TokenKind::RParn => 26,
+            TokenKind::LBracket => 27,
+            TokenKind::RBracket => 28,
+            TokenKind::LSBracket => 29,
+            TokenKind::RSBracket => 30,
+            TokenKind::Sep => 31,
+            TokenKind::FnDef => 32,
+            TokenKind::RetArrow => 33,
+            TokenKind::FatArrow => 34,
+            TokenKind::StructKw => 35,
+            TokenKind::ChoiceKw => 36,
+            TokenKind::MainKw => 37,
+            TokenKind::AsKw => 38,
+            TokenKind::AndKw => 39,
+            TokenKind::OrKw => 40,
+            TokenKind::InKw => 41,
+            TokenKind::LetKw => 42,
+            TokenKind::IfKw => 43,
+            TokenKind::ElifKw => 44,
+            TokenKind::ElseKw => 45,
+            TokenKind::ForKw => 46,
+            TokenKind::WhileKw => 47,
+            TokenKind::BreakKw => 48,
+            TokenKind::ContinueKw => 49,
+            TokenKind::MatchKw => 50,
+            TokenKind::Error => 51,
+            TokenKind::EOF => 52,
+            TokenKind::StrLit => 53,
+            TokenKind::Whitespace => 54,
+            TokenKind::Newline => 55,
+        }
+    }
+
+    // Helper to generically check if a word is reserved. A `match` on the word compiles down to a
+    // few comparisons, so nothing is built per lexed ident.
     fn is_reserved<P: AsRef<str>>(raw_token_content: P) -> Option<TokenKind> {
-        let mut reserved = BTreeMap::from([
-            ("float", TokenKind::FloatTy),
-            ("int", TokenKind::IntTy),
-            ("text", TokenKind::TextTy),
-            ("bool", TokenKind::BoolTy),
-            ("structure", TokenKind::StructKw),
-            ("choice", TokenKind::ChoiceKw),
-            ("main", TokenKind::MainKw),
-            ("as", TokenKind::AsKw),
-            ("or", TokenKind::OrKw),
-            ("and", TokenKind::AndKw),
-            ("in", TokenKind::InKw),
-            ("let", TokenKind::LetKw),
-            ("if", TokenKind::IfKw),
-            ("elif", TokenKind::ElifKw),
-            ("else", TokenKind::ElseKw),
-            ("while", TokenKind::WhileKw),
-            ("for", TokenKind::ForKw),
-            ("true", TokenKind::BoolLit),
-            ("false", TokenKind::BoolLit),
-
-        ]);
-
-        let tok_kind = reserved.get(&raw_token_content.as_ref());
-
-        tok_kind.copied()
+        let tok_kind = match raw_token_content.as_ref()
+        {
+            "float" => TokenKind::FloatTy,
+            "int" => TokenKind::IntTy,
+            "text" => TokenKind::TextTy,
+            "bool" => TokenKind::BoolTy,
+            "structure" => TokenKind::StructKw,
+            "choice" => TokenKind::ChoiceKw,
+            "main" => TokenKind::MainKw,
+            "as" => TokenKind::AsKw,
+            "or" => TokenKind::OrKw,
+            "and" => TokenKind::AndKw,
+            "in" => TokenKind::InKw,
+            "let" => TokenKind::LetKw,
+            "if" => TokenKind::IfKw,
+            "elif" => TokenKind::ElifKw,
+            "else" => TokenKind::ElseKw,
+            "while" => TokenKind::WhileKw,
+            "for" => TokenKind::ForKw,
+            "break" => TokenKind::BreakKw,
+            "continue" => TokenKind::ContinueKw,
+            "match" => TokenKind::MatchKw,
+            "true" | "false" => TokenKind::BoolLit,
+            _ => return None,
+        };
+
+        Some(tok_kind)
     }
 
     // Helper to refine ident to bool literal.
@@ -167,6 +336,35 @@ impl TokenKind {
     }
 }
 
+/// Type suffix written after a number literal (e.g the `i` in `5i`, or the `f` in `3.0f`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumSuffix {
+    Int,
+    Float,
+}
+
+impl NumSuffix {
+    /// Suffix spelled by `ch`, if any
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch
+        {
+            'i' => Some(NumSuffix::Int),
+            'f' => Some(NumSuffix::Float),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NumSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self
+        {
+            NumSuffix::Int => write!(f, "i"),
+            NumSuffix::Float => write!(f, "f"),
+        }
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Eq)]
 pub enum TokenHint {
     #[default]
@@ -175,24 +373,101 @@ pub enum TokenHint {
     Number,
 }
 
-#[derive(Serialize, Debug, Clone)]
+/// A lexed token.
+///
+/// Two `Token`s are equal (and hash the same) when they have the same kind and text, wherever
+/// they are: span, file index and source file are ignored. This lets a `HashSet<Token>` or
+/// `HashMap<Token, _>` find names that are used or declared more than once.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "TokenData")]
 pub struct Token {
     raw_content: String,
-    #[serde(skip)]
+    // Interned `raw_content` of an identifier. It is interned again when a `Token` is loaded,
+    // since ids are only valid on the thread that made them.
+    #[serde(skip_serializing)]
+    symbol: Option<Symbol>,
     kind: TokenKind,
-    #[serde(skip)]
     span_start: SpanPoint,
-    #[serde(skip)]
     span_end: SpanPoint,
-    #[serde(skip)]
     file_index: usize,
+    reserved: bool,
+    // Type suffix of a number literal. It is not part of `raw_content`, so the literal still
+    // parses as a number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_suffix: Option<NumSuffix>,
+    // Ids only mean something within one run of the compiler, so a loaded `Token` gets `0`
     #[serde(skip)]
+    source_id: usize,
+    // Inserted by the parser while recovering from an error, so not written in the source file
+    synthetic: bool,
+}
+
+// Fields of a `Token` as they are serialized, so that a loaded `Token` goes through `Token::new`
+#[derive(Deserialize)]
+struct TokenData {
+    raw_content: String,
+    kind: TokenKind,
+    span_start: SpanPoint,
+    span_end: SpanPoint,
+    file_index: usize,
     reserved: bool,
+    #[serde(default)]
+    num_suffix: Option<NumSuffix>,
+    #[serde(default)]
+    synthetic: bool,
+}
+
+impl From<TokenData> for Token {
+    fn from(data: TokenData) -> Self {
+        let tok = Token::new(
+            data.raw_content,
+            data.kind,
+            data.span_start,
+            data.span_end,
+            data.file_index,
+            data.reserved,
+        );
+
+        let tok = Token { synthetic: data.synthetic, ..tok };
+
+        match data.num_suffix
+        {
+            Some(suffix) => tok.with_num_suffix(suffix),
+            None => tok,
+        }
+    }
+}
+
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.raw_content == other.raw_content
+    }
 }
 
-// Way to print a `Token` using println!()
+impl Eq for Token {}
+
+// Must agree with `PartialEq`, so only the kind and text are hashed
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.raw_content.hash(state);
+    }
+}
+
+// Way to print a `Token` using println!(). An ident spelled like a keyword gets its `r#` prefix
+// back, so the printed `Token` lexes the same way again.
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind == TokenKind::Ident && TokenKind::is_reserved(&self.raw_content).is_some()
+        {
+            return write!(f, "r#{}", self.raw_content);
+        }
+
+        if let Some(suffix) = self.num_suffix
+        {
+            return write!(f, "{}{suffix}", self.raw_content);
+        }
+
 	write!(f, "{}", self.raw_content)
     }
 }
@@ -207,16 +482,83 @@ impl Token {
         file_index: usize,
         reserved: bool,
     ) -> Self {
+        // Only names are compared often enough to be worth interning
+        let symbol = matches!(kind, TokenKind::Ident | TokenKind::MainKw)
+            .then(|| Symbol::intern(&raw_content));
+
         Self {
             raw_content,
+            symbol,
             kind,
             span_start,
             span_end,
             file_index,
             reserved,
+            num_suffix: None,
+            source_id: 0,
+            synthetic: false,
         }
     }
 
+    /// A `Token` of `kind` that was not written in the source file, for the parser to insert
+    /// while recovering from an error (e.g a missing `;`). It is placed at the start of the file
+    /// until moved `with_position`.
+    pub fn synthetic(kind: TokenKind) -> Self {
+        let raw_content: &str = kind.into();
+        let tok = Token::new(
+            raw_content.to_string(),
+            kind,
+            SpanPoint::default(),
+            SpanPoint::default(),
+            0,
+            false,
+        );
+
+        Token { synthetic: true, ..tok }
+    }
+
+    /// A `Token` of `kind` spelled `raw` that was not lexed from any source file, placed at the
+    /// start of the file (e.g for building a `SymbolTable` or AST by hand in tests)
+    pub fn dummy(raw: &str, kind: TokenKind) -> Self {
+        Token::new(raw.to_string(), kind, SpanPoint::default(), SpanPoint::default(), 0, false)
+    }
+
+    /// Move this `Token` to `file_index`, with both ends of its span at `span_point`
+    pub fn with_position(mut self, file_index: usize, span_point: SpanPoint) -> Self {
+        self.file_index = file_index;
+        self.span_start = span_point;
+        self.span_end = span_point;
+        self
+    }
+
+    /// `true` if this `Token` was inserted by the parser instead of written in the source file
+    pub fn is_synthetic(&self) -> bool {
+        self.synthetic
+    }
+
+    /// Mark this number literal as written with the type suffix `suffix` (e.g `5i`)
+    pub fn with_num_suffix(mut self, suffix: NumSuffix) -> Self {
+        self.num_suffix = Some(suffix);
+        self
+    }
+
+    /// Type suffix this number literal was written with, if any
+    pub fn num_suffix(&self) -> Option<NumSuffix> {
+        self.num_suffix
+    }
+
+    /// Mark this `Token` as coming from the source file registered as `source_id` (see
+    /// `diagnostics::register_source`)
+    pub fn with_source_id(mut self, source_id: usize) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
+    /// Id of the source file this `Token` came from. `0` is the file currently being compiled.
+    pub fn source_id(&self) -> usize {
+        self.source_id
+    }
+
     pub fn span_end_mut(&mut self) -> &mut SpanPoint {
         &mut self.span_end
     }
@@ -247,4 +589,22 @@ impl Token {
     pub fn get_raw_content(&self) -> &str {
         &self.raw_content
     }
+
+    /// Interned `raw_content` of an identifier (or `main`), and `None` for any other `Token`.
+    /// Two identifiers have the same `Symbol` exactly when they have the same text, so comparing
+    /// `Symbol`s is a cheap way to compare names.
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.symbol
+    }
+
+    /// Char offsets into the source file that this `Token` covers. A synthetic `Token` covers
+    /// nothing, so reports about it point at the gap where it was inserted.
+    pub fn file_range(&self) -> Range<usize> {
+        if self.synthetic
+        {
+            return self.file_index..self.file_index;
+        }
+
+        self.file_index..self.file_index + self.raw_content.chars().count()
+    }
 }
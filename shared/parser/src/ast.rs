@@ -4,17 +4,70 @@
 //! `formal_grammar.pest`.
 
 use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use lexer::token::Token;
+use lexer::token::{Token, TokenKind};
 use derive_new::new;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
     
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct TypeTok(pub Token);
 
-#[derive(Serialize, Debug, Clone ,new)]
+#[derive(Serialize, Deserialize, Debug, Clone, new)]
 pub struct Program {
     pub declarations: Option<Vec<Declaration>>,
+
+    // File each declaration came from, in the same order as `declarations`
+    #[new(default)]
+    #[serde(skip)]
+    origins: Vec<Option<PathBuf>>,
+}
+
+impl Program {
+    /// Record that every declaration came from the file at `path`
+    pub fn with_origin<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.origins = vec![Some(path.as_ref().to_path_buf()); self.declaration_count()];
+        self
+    }
+
+    /// File that the declaration at `idx` came from, if known
+    pub fn origin_of(&self, idx: usize) -> Option<&Path> {
+        self.origins.get(idx).and_then(Option::as_deref)
+    }
+
+    /// Combine the programs of several files into one. Each declaration keeps the file it came
+    /// from (see `Program::origin_of`).
+    pub fn merge(programs: Vec<Program>) -> Program {
+        let mut declarations = Vec::new();
+        let mut origins      = Vec::new();
+
+        for program in programs
+        {
+            let count = program.declaration_count();
+
+            // Declarations without a recorded file have an unknown origin
+            let program_origins = program.origins.into_iter().chain(std::iter::repeat(None));
+            origins.extend(program_origins.take(count));
+            declarations.extend(program.declarations.unwrap_or_default());
+        }
+
+        Program {
+            declarations: (!declarations.is_empty()).then_some(declarations),
+            origins,
+        }
+    }
+
+    fn declaration_count(&self) -> usize {
+        self.declarations.as_ref().map_or(0, Vec::len)
+    }
+}
+
+// Only the declarations are compared; the files they came from are not part of the dump
+impl PartialEq for Program {
+    fn eq(&self, other: &Self) -> bool {
+        self.declarations == other.declarations
+    }
 }
 
 //impl Program {
@@ -37,47 +90,203 @@ pub struct Program {
     //} 
 //}
 
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub enum Declaration {
     Function {
         signature: FuncSignature,
         definition: FuncDefinition,
+        // Closing `}` of the block, where the declaration ends
+        r_bracket: Token,
     },
 
     // TODO: Add support for enums and structs
     Choice {
         name: Token,
         variants: Option<Vec<Token>>,
+        r_bracket: Token,
     },
 
     Struct {
         name: Token,
         // Tuple is `(field_name, field_type)`
         typed_fields: Option<Vec<(Token, Token)>>,
+        r_bracket: Token,
     },
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+impl Declaration {
+    pub fn name(&self) -> &Token {
+        match self
+        {
+            Declaration::Function { signature, .. } => signature.name(),
+            Declaration::Choice { name, .. } => name,
+            Declaration::Struct { name, .. } => name,
+        }
+    }
+
+    /// Char offsets into the source file covered by the whole declaration, from its name to its
+    /// closing `}` (e.g for jumping to a definition)
+    pub fn span(&self) -> Range<usize> {
+        let (Declaration::Function { r_bracket, .. }
+        | Declaration::Choice { r_bracket, .. }
+        | Declaration::Struct { r_bracket, .. }) = self;
+
+        self.name().file_range().start..r_bracket.file_range().end
+    }
+
+    pub fn as_function(&self) -> Option<(&FuncSignature, &FuncDefinition)> {
+        match self
+        {
+            Declaration::Function { signature, definition, .. } => Some((signature, definition)),
+            _ => None,
+        }
+    }
+
+    pub fn is_function(&self) -> bool {
+        matches!(self, Declaration::Function { .. })
+    }
+
+    pub fn is_choice(&self) -> bool {
+        matches!(self, Declaration::Choice { .. })
+    }
+
+    pub fn is_struct(&self) -> bool {
+        matches!(self, Declaration::Struct { .. })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct FuncSignature {
     func_name: Token,
-    ty_list: Option<Vec<Token>>,
+    ty_list: Option<Vec<ParamTy>>,
     ty_ret: Option<Token>,
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+impl FuncSignature {
+    pub fn name(&self) -> &Token {
+        &self.func_name
+    }
+
+    pub fn ty_list(&self) -> Option<&[ParamTy]> {
+        self.ty_list.as_deref()
+    }
+
+    pub fn ty_ret(&self) -> Option<&Token> {
+        self.ty_ret.as_ref()
+    }
+}
+
+/// Type of a function input (e.g `int` or `(int) -> int`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ParamTy {
+    Prim(Token),
+    Func(FuncTy),
+}
+
+impl ParamTy {
+    pub fn as_prim(&self) -> Option<&Token> {
+        match self
+        {
+            ParamTy::Prim(ty) => Some(ty),
+            ParamTy::Func(_) => None,
+        }
+    }
+
+    pub fn as_func(&self) -> Option<&FuncTy> {
+        match self
+        {
+            ParamTy::Prim(_) => None,
+            ParamTy::Func(func_ty) => Some(func_ty),
+        }
+    }
+}
+
+/// Type of a function passed as an input to another function (e.g `(int) -> int`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
+pub struct FuncTy {
+    ty_list: Option<Vec<ParamTy>>,
+    ty_ret: Option<Token>,
+}
+
+impl FuncTy {
+    pub fn ty_list(&self) -> Option<&[ParamTy]> {
+        self.ty_list.as_deref()
+    }
+
+    pub fn ty_ret(&self) -> Option<&Token> {
+        self.ty_ret.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct FuncDefinition {
     func_name: Token,
     arg_list: Option<Vec<Token>>,
     block: Block,
+
+    // Type written inline for each parameter (e.g `f (x :: int) = ...`), in the same order as
+    // `arg_list`. Empty when the parameters were not put in parentheses.
+    #[new(default)]
+    #[serde(default)]
+    param_tys: Vec<Option<TypeTok>>,
+}
+
+impl FuncDefinition {
+    /// Give the parameters the types written inline for them, in the same order
+    pub fn with_param_tys(mut self, param_tys: Vec<Option<TypeTok>>) -> Self {
+        self.param_tys = param_tys;
+        self
+    }
+
+    pub fn name(&self) -> &Token {
+        &self.func_name
+    }
+
+    pub fn params(&self) -> Option<&[Token]> {
+        self.arg_list.as_deref()
+    }
+
+    /// Each parameter along with the type written inline for it, if any
+    pub fn typed_params(&self) -> impl Iterator<Item = (&Token, Option<&Token>)> {
+        let tys = self.param_tys.iter().map(|ty| ty.as_ref().map(|TypeTok(ty)| ty));
+
+        self.params().unwrap_or_default().iter().zip(tys.chain(std::iter::repeat(None)))
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct Block {
     statements: Option<Vec<Statement>>,
     expression: Option<Expression>,
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+impl Block {
+    pub fn statements(&self) -> Option<&[Statement]> {
+        self.statements.as_deref()
+    }
+
+    pub fn expression(&self) -> Option<&Expression> {
+        self.expression.as_ref()
+    }
+
+    // Blocks used as values in the trailing expression (e.g `{ 2 }` in `{ 1 + { 2 } }`)
+    pub fn expression_blocks(&self) -> Vec<&Block> {
+        self.expression.as_ref().map_or_else(Vec::new, Expression::blocks)
+    }
+
+    // A block is empty if it has neither statements nor a trailing expression (e.g `{}`)
+    pub fn is_empty(&self) -> bool {
+        let no_statements = self.statements.as_ref().map_or(true, |stmts| stmts.is_empty());
+
+        no_statements && self.expression.is_none()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub enum RValue {
     Expr(Option<Expression>),
     List(Vec<Option<Expression>>),
@@ -85,7 +294,7 @@ pub enum RValue {
     FuncCall((Token, Vec<Option<Expression>>)),
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub enum Statement {
     VarBindingInit {
         bind_name: Token,
@@ -120,31 +329,290 @@ pub enum Statement {
     FuncCall {
         name: Token,
         args: Vec<Option<Expression>>
+    },
+
+    Break {
+        break_kw: Token,
+    },
+
+    Continue {
+        continue_kw: Token,
+    },
+
+    Match {
+        scrutinee: Expression,
+        // Tuple is `(variant_name, arm_block)`
+        arms: Vec<(Token, Block)>,
+    },
+
+    /// Expression evaluated for its side effects, followed by `;` (e.g `compute(x) + 1;`)
+    Expr(Expression),
+}
+
+impl Statement {
+    // Name of the binding a statement introduces or mutates (e.g `x` in `let x <- 5;`)
+    pub fn bind_name(&self) -> Option<&Token> {
+        match self
+        {
+            Statement::VarBindingInit { bind_name, .. } => Some(bind_name),
+            Statement::VarBindingMut { bind_name, .. } => Some(bind_name),
+            _ => None,
+        }
+    }
+
+    // Blocks that make up the body of a statement (e.g the branches of an `if`), in source order
+    pub fn body_blocks(&self) -> Vec<&Block> {
+        match self
+        {
+            Statement::Selection { if_comp, elif_comp, else_comp } =>
+            {
+                let mut blocks = vec![if_comp.block()];
+                blocks.extend(elif_comp.as_ref().map(|elif| elif.block()));
+                blocks.extend(else_comp.as_ref().map(|els| els.block()));
+                blocks
+            }
+            Statement::IndefiniteLoop { block, .. } => vec![block],
+            Statement::DefiniteLoop { block, .. } => vec![block],
+            Statement::Match { arms, .. } => arms.iter().map(|(_, block)| block).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Expressions written directly in a statement (e.g the condition of a `while` loop or the
+    // right hand side of a binding), in source order
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self
+        {
+            Statement::VarBindingInit { rhs, .. } => match rhs
+            {
+                RValue::Expr(expr) => expr.iter().collect(),
+                RValue::List(exprs)
+                | RValue::Struct((_, exprs))
+                | RValue::FuncCall((_, exprs)) => exprs.iter().flatten().collect(),
+            },
+            Statement::VarBindingMut { expr, .. } => vec![expr],
+            Statement::Selection { if_comp, elif_comp, .. } =>
+            {
+                let mut exprs = vec![if_comp.bool_expr()];
+                exprs.extend(elif_comp.as_ref().map(|elif| elif.bool_expr()));
+                exprs
+            }
+            Statement::IndefiniteLoop { expr, .. } => vec![expr],
+            Statement::FuncCall { args, .. } => args.iter().flatten().collect(),
+            Statement::Match { scrutinee, .. } => vec![scrutinee],
+            Statement::Expr(expr) => vec![expr],
+            Statement::DefiniteLoop { .. } | Statement::Break { .. } | Statement::Continue { .. } =>
+            {
+                Vec::new()
+            }
+        }
+    }
+
+    // Blocks nested directly inside a statement, in source order. Along with its body, this has
+    // the blocks used as values in its expressions (e.g `{ 1 }` in `let x <- { 1 };`).
+    pub fn blocks(&self) -> Vec<&Block> {
+        let mut blocks = Vec::new();
+
+        match self
+        {
+            // The condition of each branch comes before its block
+            Statement::Selection { if_comp, elif_comp, else_comp } =>
+            {
+                blocks.extend(if_comp.bool_expr().blocks());
+                blocks.push(if_comp.block());
+                if let Some(elif) = elif_comp
+                {
+                    blocks.extend(elif.bool_expr().blocks());
+                    blocks.push(elif.block());
+                }
+                blocks.extend(else_comp.as_ref().map(|els| els.block()));
+            }
+            _ =>
+            {
+                blocks.extend(self.expressions().into_iter().flat_map(Expression::blocks));
+                blocks.extend(self.body_blocks());
+            }
+        }
+
+        blocks
     }
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct IfComp {
     bool_expr: Expression,
     block: Block,
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct ElifComp {
     bool_expr: Expression,
     block: Block,
 }
 
-#[derive(Serialize, Debug, Clone, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub struct ElseComp {
     block: Block,
 }
 
+impl IfComp {
+    pub fn bool_expr(&self) -> &Expression {
+        &self.bool_expr
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+}
+
+impl ElifComp {
+    pub fn bool_expr(&self) -> &Expression {
+        &self.bool_expr
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+}
+
+impl ElseComp {
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+}
+
+/// Value of a literal `Token` (e.g `5`, `1.5`, `true` or `'a'`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub enum LitValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+}
+
+impl LitValue {
+    /// Reads the value out of a literal `Token`
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if `tok` is not a literal or its value does not fit (e.g an `int` larger
+    /// than `i64::MAX`)
+    pub fn from_token(tok: &Token) -> Option<LitValue> {
+        let raw = tok.get_raw_content();
+
+        match tok.get_token_kind()
+        {
+            TokenKind::NumLit => raw.parse().ok().map(LitValue::Int),
+            TokenKind::FloatLit => raw.parse().ok().map(LitValue::Float),
+            TokenKind::BoolLit => raw.parse().ok().map(LitValue::Bool),
+            TokenKind::CharLit =>
+            {
+                // Strip the surrounding `'`s and resolve the escape (if any)
+                let inner = raw.strip_prefix('\'')?.strip_suffix('\'')?;
+                match inner
+                {
+                    "\\n" => Some(LitValue::Char('\n')),
+                    "\\t" => Some(LitValue::Char('\t')),
+                    "\\\\" => Some(LitValue::Char('\\')),
+                    "\\'" => Some(LitValue::Char('\'')),
+                    _ =>
+                    {
+                        let mut chars = inner.chars();
+                        let ch = chars.next()?;
+                        chars.next().is_none().then_some(LitValue::Char(ch))
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
 // S-expressions!
-#[derive(Serialize, Debug, new, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, new)]
 pub enum Expression {
+    /// An identifier or type name (e.g `x` or `float`)
     Atom(Token),
+
+    /// A literal along with the `Token` it was read from (e.g `5`)
+    Literal(Token, LitValue),
+
     Cons(Token, Vec<Expression>),
+
+    /// A block used as a value, along with its `{` (e.g `{ let t <- 1; t + 1 }`). Its value is
+    /// the value of its trailing expression.
+    Block(Token, Box<Block>),
+
+    /// An `if` used as a value (e.g `if a { 1 } else { 2 }`). Both branches are required so it
+    /// has a value either way.
+    If {
+        if_kw: Token,
+        cond: Box<Expression>,
+        then_block: Box<Block>,
+        else_block: Box<Block>,
+    },
+}
+
+impl Expression {
+    /// Blocks used as values in the expression, in source order. Blocks nested inside of those
+    /// blocks are left out.
+    pub fn blocks(&self) -> Vec<&Block> {
+        match self
+        {
+            Expression::Atom(_) | Expression::Literal(..) => Vec::new(),
+            Expression::Cons(_, rest) => rest.iter().flat_map(Expression::blocks).collect(),
+            Expression::Block(_, block) => vec![block],
+            Expression::If { cond, then_block, else_block, .. } =>
+            {
+                let mut blocks = cond.blocks();
+                blocks.extend([&**then_block, &**else_block]);
+                blocks
+            }
+        }
+    }
+
+    /// Token at the head of the expression (e.g the `+` of `a + b` or the `if` of an `if` value).
+    /// Handy for finding which file the expression came from.
+    pub fn head(&self) -> &Token {
+        match self
+        {
+            Expression::Atom(tok) | Expression::Literal(tok, _) | Expression::Cons(tok, _) => tok,
+            Expression::Block(l_bracket, _) => l_bracket,
+            Expression::If { if_kw, .. } => if_kw,
+        }
+    }
+
+    /// Char offsets into the source file covered by the whole expression, from its leftmost
+    /// token to its rightmost token (e.g `a + b` covers `a` through `b`)
+    pub fn span(&self) -> Range<usize> {
+        match self
+        {
+            Expression::Atom(tok) | Expression::Literal(tok, _) => tok.file_range(),
+            Expression::Cons(head, rest) =>
+            {
+                rest.iter().map(Expression::span).fold(head.file_range(), |span, child| {
+                    span.start.min(child.start)..span.end.max(child.end)
+                })
+            }
+            // Blocks do not keep their `}`, so these end at their last value
+            Expression::Block(l_bracket, block) => match block.expression()
+            {
+                Some(expr) => l_bracket.file_range().start..expr.span().end,
+                None => l_bracket.file_range(),
+            },
+            Expression::If { if_kw, cond, then_block, else_block } =>
+            {
+                let branch_values = [then_block, else_block]
+                    .into_iter()
+                    .filter_map(|block| block.expression())
+                    .map(Expression::span);
+
+                branch_values.fold(if_kw.file_range().start..cond.span().end, |span, child| {
+                    span.start..span.end.max(child.end)
+                })
+            }
+        }
+    }
 }
 
 // Way to print an `Expression` using println!()
@@ -153,6 +621,17 @@ impl fmt::Display for Expression {
         match self
         {
             Expression::Atom(i) => write!(f, "{}", i),
+            Expression::Literal(tok, _) => write!(f, "{}", tok),
+            // Call through a field access (e.g `(call (. m f) 1)` for `m.f(1)`)
+            Expression::Cons(head, rest) if head.is_a(TokenKind::LParn) =>
+            {
+                write!(f, "(call")?;
+                for s in rest
+                {
+                    write!(f, " {}", s)?
+                }
+                write!(f, ")")
+            }
             Expression::Cons(head, rest) =>
             {
                 write!(f, "({}", head)?;
@@ -162,7 +641,28 @@ impl fmt::Display for Expression {
                 }
                 write!(f, ")")
             }
+            Expression::Block(_, block) => fmt_block_value(block, f),
+            Expression::If { cond, then_block, else_block, .. } =>
+            {
+                write!(f, "(if {cond} ")?;
+                fmt_block_value(then_block, f)?;
+                write!(f, " ")?;
+                fmt_block_value(else_block, f)?;
+                write!(f, ")")
+            }
         }
     }
 }
 
+// Print a block used as a value by its trailing expression (e.g `{ x }`), eliding its statements
+fn fmt_block_value(block: &Block, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let has_statements = block.statements().is_some_and(|stmts| !stmts.is_empty());
+    let stmts = if has_statements { "... " } else { "" };
+
+    match block.expression()
+    {
+        Some(expr) => write!(f, "{{ {stmts}{expr} }}"),
+        None => write!(f, "{{ {stmts}}}"),
+    }
+}
+
@@ -1,13 +1,38 @@
-use std::{cell::Cell, path::Path};
+use std::{cell::{Cell, RefCell}, path::Path, rc::Rc};
 use std::collections::BTreeSet;
 
 use crate::ast;
 use crate::ast::TypeTok;
 use crate::errors::{ParserErrorReporter, ParserError};
 
+use lexer::diagnostics::{self, Diagnostic, ErrorCounter};
+use lexer::source_map::SourceMap;
 use lexer::token::{Token, TokenKind};
+use lexer::token::{INFIX_OP_TOKENS, LITERAL_TOKENS, PREFIX_OP_TOKENS, TYPE_TOKENS};
 use symbol_table::{SymbolTable, SymbolNode, Type, SymbolKind};
 
+// @todo: Add support for ADTs in function input types
+use lexer::token::PRIM_TYPE_TOKENS as PRIM_TYPES;
+
+// Punctuation and keywords that can start (or end, for the closing ones) the LHS of an
+// expression
+const LHS_PUNC_TOKENS: &[TokenKind] =
+    &[TokenKind::RBracket, TokenKind::Semicolon, TokenKind::LParn, TokenKind::Sep];
+const LHS_KW_TOKENS: &[TokenKind] = &[TokenKind::IfKw, TokenKind::LBracket];
+
+// Punctuation that can follow an operand of an expression
+const OP_PUNC_TOKENS: &[TokenKind] = &[
+    TokenKind::LSBracket, TokenKind::RSBracket, TokenKind::LParn, TokenKind::RParn,
+    TokenKind::LBracket, TokenKind::RBracket, TokenKind::Semicolon, TokenKind::Sep,
+];
+
+/// Deepest that expressions and blocks (e.g the body of an `if`) may be nested inside each other
+/// by default. Each level of nesting recurses, so deeper input would risk overflowing the stack.
+///
+/// NOTE: This fits in the 2 MiB stack of a spawned thread, even in debug builds, where each level
+/// of nested blocks takes about 30 KiB of stack.
+pub const DEFAULT_MAX_EXPR_DEPTH: usize = 48;
+
 pub struct Parser<'parser> {
     tokens: Vec<Token>,
 
@@ -19,11 +44,38 @@ pub struct Parser<'parser> {
 
     cleaned_source: &'parser str,
 
+    /// Maps offsets in `cleaned_source` back to the original source file for error reporting
+    source_map: Option<&'parser SourceMap>,
+
+    /// Counts reported errors so we stop reporting after the limit
+    error_counter: Rc<ErrorCounter>,
+
     scope_stream: Vec<Cell<usize>>,
 
-    scope_cursor: Cell<usize>
+    scope_cursor: Cell<usize>,
+
+    /// Number of currently open `(` while parsing expressions and call arguments
+    paren_depth: Cell<usize>,
+
+    /// Number of expressions and blocks currently being parsed inside each other
+    expr_depth: Cell<usize>,
 
+    /// Deepest that expressions and blocks may be nested before parsing fails
+    max_expr_depth: usize,
 
+    /// Symbols bound inside blocks used as values (e.g `{ let t <- 1; t + 1 }`), which
+    /// are parsed without access to the `SymbolTable`. They are moved into it by `parse`.
+    expr_symbols: RefCell<SymbolTable>,
+
+    /// Flag to say errors are collected into `errors` instead of printed, and parsing goes on
+    /// with the next declaration after one
+    collect_errors: bool,
+
+    /// Errors collected while `collect_errors` is set
+    errors: RefCell<Vec<Diagnostic>>,
+
+    /// Flag to say a synthetic `Token` was inserted to recover from an error
+    recovered: Cell<bool>,
 }
 
 /// CTOR for the `Parser`
@@ -34,14 +86,78 @@ impl<'parser> Parser<'parser> {
             pos: Cell::new(0),
             path,
             cleaned_source,
+            source_map: None,
+            error_counter: Rc::default(),
             scope_stream: vec![0.into(); 256],
-            scope_cursor: 0.into()
+            scope_cursor: 0.into(),
+            paren_depth: 0.into(),
+            expr_depth: 0.into(),
+            max_expr_depth: DEFAULT_MAX_EXPR_DEPTH,
+            expr_symbols: RefCell::new(SymbolTable::new()),
+            collect_errors: false,
+            errors: RefCell::new(Vec::new()),
+            recovered: Cell::new(false),
         }
     }
+
+    /// Collect errors instead of printing them, and keep parsing at the next declaration after
+    /// one so a single parse finds as many as it can. See `Parser::into_errors`.
+    ///
+    /// A missing `;` is recovered from by inserting a synthetic one (see `Token::is_synthetic`).
+    /// `parse` still fails after that, so use `parse_recovered` to get the recovered program.
+    pub fn with_collected_errors(mut self) -> Self {
+        self.collect_errors = true;
+        self
+    }
+
+    /// Errors collected by a `Parser` made `with_collected_errors`, in the order they were found
+    pub fn into_errors(self) -> Vec<Diagnostic> {
+        self.errors.into_inner()
+    }
+
+    /// Report errors against the original source file described by `source_map` instead of
+    /// `cleaned_source`
+    pub fn with_source_map(mut self, source_map: &'parser SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    /// Fail once expressions and blocks are nested more than `max_expr_depth` deep instead of after
+    /// `DEFAULT_MAX_EXPR_DEPTH`
+    pub fn with_max_expr_depth(mut self, max_expr_depth: usize) -> Self {
+        self.max_expr_depth = max_expr_depth;
+        self
+    }
+
+    /// Share `error_counter` with the other compiler stages so reporting stops once it hits its
+    /// limit
+    pub fn with_error_counter(mut self, error_counter: Rc<ErrorCounter>) -> Self {
+        self.error_counter = error_counter;
+        self
+    }
 }
 
 /// Internal helper functions to build smaller parsers  
 impl Parser<'_> {
+    /// Source that error reports are printed against. This is the original source file when a
+    /// `SourceMap` was given, else `cleaned_source`.
+    fn report_source(&self) -> &str {
+        match self.source_map
+        {
+            Some(source_map) => source_map.original_source(),
+            None => self.cleaned_source,
+        }
+    }
+
+    /// Maps an offset in `cleaned_source` to an offset within `report_source`
+    fn report_offset(&self, offset: usize) -> usize {
+        match self.source_map
+        {
+            Some(source_map) => source_map.original_offset(offset),
+            None => offset,
+        }
+    }
+
     /// Get the current `Token` by ownership based on current `Parser`
     /// index `pos`.
     fn peek(&self) -> Option<Token> {
@@ -52,12 +168,17 @@ impl Parser<'_> {
         tok.cloned()
     }
 
-    /// Get the next `Token` by ownership based on current `Parser`
-    /// index `pos`.
-    fn peek_next(&self) -> Option<Token> {
-        let curr_tok_pos = &self.pos;
+    /// Get the last consumed `Token` by ownership, or `None` if nothing was consumed yet
+    fn peek_previous(&self) -> Option<Token> {
+        let prev_tok_pos = self.pos.get().checked_sub(1)?;
+
+        self.tokens.get(prev_tok_pos).cloned()
+    }
 
-        let tok = self.tokens.get(curr_tok_pos.get().wrapping_add(1)).clone();
+    /// Get the `Token` `n` positions ahead of the current `Parser` index `pos` by ownership
+    /// without advancing. `peek_nth(0)` is the same as `peek()`.
+    fn peek_nth(&self, n: usize) -> Option<Token> {
+        let tok = self.tokens.get(self.pos.get().saturating_add(n));
 
         tok.cloned()
     }
@@ -67,7 +188,13 @@ impl Parser<'_> {
     fn is_a(&self, kind: TokenKind) -> bool {
         let at_eof = self.pos.get() >= self.tokens.len();
 
-        self.peek().unwrap().get_token_kind() == kind && !at_eof
+        // Nothing left to peek at, so it can't be a `kind`
+        if at_eof
+        {
+            return false;
+        }
+
+        self.peek().map_or(false, |tok| tok.get_token_kind() == kind)
     }
 
     /// `advance_parser_pos()` moves the parser's position index by one.
@@ -85,13 +212,6 @@ impl Parser<'_> {
         self.pos.set(new_pos);
     }
 
-    fn decrement_parser_pos_by(&self, decre: usize) {
-        // Make sure we do not decrment passed the size of a `usize`
-        let new_pos = self.pos.get().saturating_sub(decre);
-
-        self.pos.set(new_pos);
-    }
-
     fn at_end_of_token_stream(&self) -> bool {
         let stream_size = self.tokens.len();
 
@@ -116,6 +236,46 @@ impl Parser<'_> {
             .set(self.scope_stream[self.scope_cursor.get()].get() - 1);
     }
 
+    /// Record that we have consumed an opening `(`
+    fn open_paren(&self) {
+        self.paren_depth.set(self.paren_depth.get() + 1);
+    }
+
+    /// Record that we have consumed a closing `)`
+    fn close_paren(&self) {
+        // A `)` without a matching `(` must be reported before getting here
+        debug_assert!(self.paren_depth.get() > 0, "Closed a paren that was never opened");
+
+        self.paren_depth.set(self.paren_depth.get().saturating_sub(1));
+    }
+
+    /// Consume the `)` matching the `(` found at `open_offset`, or report the
+    /// `(` as unbalanced if it is missing.
+    fn try_close_paren(&self, open_offset: usize) -> Result<Token, ParserError> {
+        let Some(r_parn) = self.optional_consume(&[TokenKind::RParn])
+        else
+        {
+            let offset = self.report_offset(open_offset);
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unbalanced_paren(
+                    &TokenKind::LParn,
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::UnbalancedParen { paren: TokenKind::LParn, offset });
+        };
+
+        self.close_paren();
+
+        Ok(r_parn)
+    }
+
    
 
     //fn reset_and_save_scope_bredth(&self) {
@@ -131,33 +291,82 @@ impl Parser<'_> {
 
 impl Parser<'_> {
     pub fn parse(&self, verbose: bool, sym_table: &mut SymbolTable) -> Result<ast::Program, ParserError> {
-        let declarations = self.parse_declarations(sym_table)?;
+        let program = self.parse_program(verbose, sym_table)?;
+
+        // The program only parsed because of tokens that are not in the source
+        if self.recovered.get()
+        {
+            return Err(ParserError::ParseFail);
+        }
+
+        Ok(program)
+    }
+
+    /// Same as `parse` on a `Parser` made `with_collected_errors`, but also gives back the
+    /// program built by recovering from errors (e.g a missing `;`), along with every error found
+    ///
+    /// # Returns
+    ///
+    /// Returns the program, or `None` if an error could not be recovered from, and the errors
+    pub fn parse_recovered(
+        self,
+        verbose: bool,
+        sym_table: &mut SymbolTable,
+    ) -> (Option<ast::Program>, Vec<Diagnostic>) {
+        let program = self.parse_program(verbose, sym_table).ok();
+
+        (program, self.into_errors())
+    }
+
+    // Parse the whole program, even if that took recovering from errors
+    fn parse_program(
+        &self,
+        verbose: bool,
+        sym_table: &mut SymbolTable,
+    ) -> Result<ast::Program, ParserError> {
+        let declarations = if self.collect_errors
+        {
+            let (declarations, mut errors) =
+                diagnostics::collected(|| self.parse_declarations(sym_table));
+            self.errors.borrow_mut().append(&mut errors);
+
+            declarations?
+        }
+        else
+        {
+            self.parse_declarations(sym_table)?
+        };
+        sym_table.append(&mut self.expr_symbols.borrow_mut());
 
         if verbose 
         {
             println!("[Generated AST]:");
-            dbg!(&declarations);
+            for decl in declarations.iter().flatten()
+            {
+                println!("{decl}");
+            }
         }
 
-        Ok(ast::Program::new(declarations))
+        Ok(ast::Program::new(declarations).with_origin(self.path))
     }
 
     fn parse_declarations(&self, sym_table: &mut SymbolTable) -> Result<Option<Vec<ast::Declaration>>, ParserError> {
         use TokenKind::*;
         let mut declarations = Vec::new();
+        let mut found_error  = false;
 
         // Loop to parse declarationa. Terminates
         'parse_decls: loop
         {
             // Try to get the name of the declaration
-            let curr_token = self.try_consume(&[Ident, MainKw, EOF]);
+            let curr_token = self.try_peek(&[Ident, MainKw, EOF]);
 
-            if curr_token.is_err() && declarations.is_empty()
+            if curr_token.is_err() && declarations.is_empty() && !found_error
             {
                 return Ok(None);
             }
 
-            let name_token = curr_token.unwrap();
+            let name_token = curr_token?;
 
             // Escape if we reach EOF token
             if name_token.is_a(EOF)
@@ -165,46 +374,78 @@ impl Parser<'_> {
                 break 'parse_decls;
             }
 
-            let declaration = match name_token.get_token_kind()
+            let declaration = match self.parse_declaration(sym_table)
             {
-                Ident | MainKw =>
+                Ok(declaration) => declaration,
+                // Skip to the next declaration and look for more errors there
+                Err(_) if self.collect_errors =>
                 {
-                    let _decl_t_qualifier = self.try_consume(&[TQualifer])?;
+                    found_error = true;
+                    self.skip_to_next_declaration();
+                    continue 'parse_decls;
+                }
+                Err(error) => return Err(error),
+            };
 
-                    // `LParn` if the start of a function declaration
-                    let decl_tok = self.try_consume(&[StructKw, ChoiceKw, LParn])?;
+            declarations.push(declaration);
+        }
 
-                    if decl_tok.is_a(StructKw)
-                    {
-                        // Reposition stream position
-                        self.decrement_parser_pos_by(3);
+        if found_error
+        {
+            return Err(ParserError::ParseFail);
+        }
 
-                        self.parse_struct_declaration(sym_table)?
-                    }
-                    else if decl_tok.is_a(ChoiceKw)
-                    {
-                        // Reposition stream position
-                        self.decrement_parser_pos_by(3);
+        Ok(Some(declarations))
+    }
 
-                        self.parse_choice_declaration(sym_table)?
-                    }
-                    else
-                    {
-                        // Reposition stream position
-                        self.decrement_parser_pos_by(3);
-                        
-                        self.parse_function_declaration(sym_table)?
-                    }
-                }
+    // Parse the declaration whose name is the current `Token`
+    fn parse_declaration(&self, sym_table: &mut SymbolTable) -> Result<ast::Declaration, ParserError> {
+        use TokenKind::*;
 
-                // @todo: Add support for `struct` and `choice` decls
-                _ => break 'parse_decls,
-            };
+        // Look ahead passed `name ::` to see what kind of declaration this is, leaving the whole
+        // declaration for the sub-parsers
+        let _decl_t_qualifier = self.try_peek_nth(1, &[TQualifer])?;
 
-            declarations.push(declaration);
+        // `LParn` if the start of a function declaration
+        let decl_tok = self.try_peek_nth(2, &[StructKw, ChoiceKw, LParn])?;
+
+        if decl_tok.is_a(StructKw)
+        {
+            self.parse_struct_declaration(sym_table)
+        }
+        else if decl_tok.is_a(ChoiceKw)
+        {
+            self.parse_choice_declaration(sym_table)
+        }
+        else
+        {
+            self.parse_function_declaration(sym_table)
         }
+    }
 
-        Ok(Some(declarations))
+    // Move passed the declaration that failed to parse, to the next `Token`s that look like the
+    // start of one (e.g `name :: (`), or to `EOF`
+    fn skip_to_next_declaration(&self) {
+        use TokenKind::*;
+
+        // The failed declaration may have left blocks and parentheses open
+        self.scope_cursor.set(0);
+        self.paren_depth.set(0);
+
+        self.advance_parser_pos();
+        while !self.at_end_of_token_stream() && !self.is_a(EOF)
+        {
+            let starts_declaration = self.optional_peek(&[Ident, MainKw]).is_some()
+                && self.optional_peek_nth(1, &[TQualifer]).is_some()
+                && self.optional_peek_nth(2, &[StructKw, ChoiceKw, LParn]).is_some();
+
+            if starts_declaration
+            {
+                return;
+            }
+
+            self.advance_parser_pos();
+        }
     }
 
     fn parse_choice_declaration(&self, sym_table: &mut SymbolTable) -> Result<ast::Declaration, ParserError> {
@@ -226,7 +467,7 @@ impl Parser<'_> {
         let choice_variants = self.try_optional_consume_list_with_seps(&[Ident])?;
 
         // Check for closing right bracket for choice
-        let _choice_r_bracket = self.try_consume(&[RBracket])?;
+        let choice_r_bracket = self.try_consume(&[RBracket])?;
 
         // Create symbol node
         let choice_sym_node = SymbolNode::new(choice_name.clone(), Type::Choice, 
@@ -237,7 +478,7 @@ impl Parser<'_> {
         sym_table.push(choice_sym_node);
 
 
-        Ok(ast::Declaration::new_choice(choice_name, choice_variants))
+        Ok(ast::Declaration::new_choice(choice_name, choice_variants, choice_r_bracket))
     }
 
     fn parse_struct_declaration(&self, sym_table: &mut SymbolTable) -> Result<ast::Declaration, ParserError> {
@@ -259,7 +500,7 @@ impl Parser<'_> {
         let struct_fields = self.try_optional_consume_typed_list_with_seps()?;
 
         // Check for closing right bracket for structure (i.e. `}`)
-        let _struct_r_bracket = self.try_consume(&[RBracket])?;
+        let struct_r_bracket = self.try_consume(&[RBracket])?;
 
         // Create symbol node
         let struct_sym_node = SymbolNode::new(struct_name.clone(), Type::Struct, 
@@ -269,16 +510,35 @@ impl Parser<'_> {
         // Update Symbol Table 
         sym_table.push(struct_sym_node);
 
-        Ok(ast::Declaration::new_struct(struct_name, struct_fields))
+        Ok(ast::Declaration::new_struct(struct_name, struct_fields, struct_r_bracket))
     }
 
     fn parse_function_declaration(&self, sym_table: &mut SymbolTable) -> Result<ast::Declaration, ParserError> {
         let function_signature = self.parse_function_signature(sym_table)?;
-        let function_definition = self.parse_function_definition(sym_table)?;
+
+        // A function without parameters may give its block right after the signature
+        // (e.g `main :: () -> int { 0 }`)
+        let inline_block = function_signature.ty_list().is_none()
+            && self.optional_peek(&[TokenKind::LBracket]).is_some();
+
+        let function_definition = if inline_block
+        {
+            let block = self.parse_block(sym_table)?;
+
+            ast::FuncDefinition::new(function_signature.name().clone(), None, block)
+        }
+        else
+        {
+            self.parse_function_definition(sym_table)?
+        };
+
+        // The block was just closed
+        let r_bracket = self.peek_previous().expect("a block was parsed");
 
         Ok(ast::Declaration::new_function(
             function_signature,
             function_definition,
+            r_bracket,
         ))
     }
 
@@ -294,25 +554,19 @@ impl Parser<'_> {
         // See if left parenthesis is given (e.g `(`) and ignore
         let _l_parn = self.try_consume(&[LParn])?;
 
-        // @todo: Add support for ADTs in function input types
-        let types = &[
-            // TokenKind::Adt,
-            IntTy, BoolTy, TextTy, FloatTy,
-        ];
-
-        // Get function input types (e.g `int` or `bool`)
-        let func_input_tys = self.optional_consume_list(types);
+        // Get function input types (e.g `int` or `(int) -> int`)
+        let func_input_tys = self.parse_param_tys()?;
 
         // See if right parenthesis is given (e.g `)`) and ignore
         let _r_parn = self.try_consume(&[RParn])?;
 
         // Consume either both return arrow and type, or neither (they can be omitted together)
-        let (_r_arrow, ret_ty) = self.try_consume2_or_none(&[RetArrow], types)?;
+        let (_r_arrow, ret_ty) = self.try_consume2_or_none(&[RetArrow], PRIM_TYPES)?;
         
     
         // Create symbol node
-        let func_sym_node = SymbolNode::new(func_name.clone(), Type::Func, 
-                                            self.scope_cursor.get(), self.scope_stream[self.scope_cursor.get()].get());
+        let func_sym_node = SymbolNode::from_function(&func_name, self.scope_cursor.get(),
+                                                      self.scope_stream[self.scope_cursor.get()].get());
         func_sym_node.refine_sym_kind_to(SymbolKind::Global);
 
         // Update Symbol Table 
@@ -321,46 +575,194 @@ impl Parser<'_> {
         Ok(ast::FuncSignature::new(func_name, func_input_tys, ret_ty))
     }
 
+    // Parse the comma separated input types of a function signature or function type
+    // (e.g `int, (int) -> int`)
+    fn parse_param_tys(&self) -> Result<Option<Vec<ast::ParamTy>>, ParserError> {
+        use TokenKind::*;
+
+        // An empty list (e.g `()`)
+        if self.optional_peek(&[RParn]).is_some()
+        {
+            return Ok(None);
+        }
+
+        let mut param_tys = Vec::new();
+        loop
+        {
+            // error detected -- Missing type after a comma (e.g `(int,)`)
+            if self.optional_peek(&[LParn]).is_some()
+            {
+                param_tys.push(ast::ParamTy::Func(self.parse_func_ty()?));
+            }
+            else
+            {
+                param_tys.push(ast::ParamTy::Prim(self.try_consume(PRIM_TYPES)?));
+            }
+
+            // error detected -- Missing comma in list (e.g `(int int)`)
+            let next_tok = self.try_peek(&[Sep, RParn])?;
+            if next_tok.is_a(RParn)
+            {
+                break;
+            }
+            self.advance_parser_pos();
+        }
+
+        Ok(Some(param_tys))
+    }
+
+    // Parse the type of a function input that is itself a function (e.g `(int) -> int`)
+    fn parse_func_ty(&self) -> Result<ast::FuncTy, ParserError> {
+        use TokenKind::*;
+
+        let _l_parn = self.try_consume(&[LParn])?;
+        let ty_list = self.parse_param_tys()?;
+        let _r_parn = self.try_consume(&[RParn])?;
+
+        // Consume either both return arrow and type, or neither (they can be omitted together)
+        let (_r_arrow, ty_ret) = self.try_consume2_or_none(&[RetArrow], PRIM_TYPES)?;
+
+        Ok(ast::FuncTy::new(ty_list, ty_ret))
+    }
+
     fn parse_function_definition(&self, sym_table: &mut SymbolTable) -> Result<ast::FuncDefinition, ParserError> {
         use TokenKind::*;
 
         // Get name of function (e.g identifier)
         let func_name = self.try_consume(&[Ident, MainKw])?;
 
-        // Get function parameters (e.g identifiers). Can omit entirely.
-        let func_params = self.optional_consume_list(&[Ident]);
+        // Get function parameters (e.g identifiers), which may be put in parentheses to give
+        // their types inline (e.g `(x :: int, y :: bool)`). Can omit entirely, which leaves
+        // `None`.
+        let (func_params, param_tys) = if self.optional_consume(&[LParn]).is_some()
+        {
+            let (params, param_tys): (Vec<_>, _) = self.parse_typed_params()?.into_iter().unzip();
+
+            ((!params.is_empty()).then_some(params), param_tys)
+        }
+        else
+        {
+            (self.optional_consume_list(&[Ident]), Vec::new())
+        };
+
+        // Check for and ignore function def operator (e.g `=`). Anything else is a stray token
+        // among the parameters (e.g `add a 2 = ...`).
+        if self.optional_consume(&[FnDef]).is_none()
+        {
+            let stray_tok = self.peek().or_else(|| self.tokens.last().cloned()).unwrap();
+
+            let offset = self.report_offset(stray_tok.get_file_index());
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::expected_param_or_def(
+                    &stray_tok.get_token_kind(),
+                    func_name.get_raw_content(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
-        // Check for and ignore function def operator (e.g `=`)
-        let _func_def_op = self.try_consume(&[FnDef])?;
+            return Err(ParserError::UnexpectedToken {
+                found: stray_tok.get_token_kind(),
+                expected: vec![Ident, FnDef],
+                offset,
+            });
+        }
 
         // Parse out `Block`
         let block = self.parse_block(sym_table)?;
 
-        Ok(ast::FuncDefinition::new(func_name, func_params, block))
+        Ok(ast::FuncDefinition::new(func_name, func_params, block).with_param_tys(param_tys))
+    }
+
+    // Parse parameters in parentheses after the `(`, each with an optional type (e.g
+    // `x :: int, y)`)
+    fn parse_typed_params(&self) -> Result<Vec<(Token, Option<TypeTok>)>, ParserError> {
+        use TokenKind::*;
+
+        let mut params = Vec::new();
+
+        while let Some(param) = self.optional_consume(&[Ident])
+        {
+            let param_ty = if self.optional_consume(&[TQualifer]).is_some()
+            {
+                Some(TypeTok(self.try_consume_ty(&format!("parameter `{param}`"))?))
+            }
+            else
+            {
+                None
+            };
+
+            params.push((param, param_ty));
+
+            if self.optional_consume(&[Sep]).is_none()
+            {
+                break;
+            }
+        }
+
+        let _r_paren = self.try_consume(&[RParn])?;
+
+        Ok(params)
     }
 
     fn parse_block(&self, sym_table: &mut SymbolTable) -> Result<ast::Block, ParserError> {
         use TokenKind::*;
         
+        let _block_open = self.try_consume(&[LBracket])?;
+
+        self.parse_block_rest(sym_table)
+    }
+
+    // Parse the rest of a block once its `{` was consumed
+    fn parse_block_rest(&self, sym_table: &mut SymbolTable) -> Result<ast::Block, ParserError> {
+        self.nested(|| self.parse_nested_block(sym_table))
+    }
+
+    fn parse_nested_block(&self, sym_table: &mut SymbolTable) -> Result<ast::Block, ParserError> {
+        use TokenKind::*;
+
         self.incre_scope_depth();
-        let _block_open       = self.try_consume(&[LBracket])?;
-        let statements        = self.parse_statements(sym_table)?;
+
+        // An expression followed by `;` is a statement, and one followed by `}` is the value of
+        // the block. Which one it is only shows once the whole expression was parsed.
+        let mut statements    = Vec::new();
         let min_binding_power = 0;
-        let expression        = self.parse_expression(min_binding_power)?;
-        
-        if expression.is_some()
+        let expression        = loop
         {
-            println!("S-Expr: {expr}", expr=expression.clone().unwrap());
-        }
+            statements.append(&mut self.parse_statements(sym_table)?);
+
+            let Some(expr) = self.parse_expression(min_binding_power)?
+            else
+            {
+                break None;
+            };
+
+            if self.optional_consume(&[Semicolon]).is_none()
+            {
+                // Nothing but `}` may follow the value, so a missing `;` is reported as well
+                self.try_peek(&[Semicolon, RBracket])?;
+                break Some(expr);
+            }
+
+            statements.push(Parser::expr_statement(expr));
+        };
 
         let _block_close      = self.try_consume(&[RBracket])?;
         self.incre_scope_bredth();
         self.decre_scope_depth();
 
+        let statements = if statements.is_empty() { None } else { Some(statements) };
+
         Ok(ast::Block::new(statements, expression))
     }
 
-    fn parse_statements(&self, sym_table: &mut SymbolTable) -> Result<Option<Vec<ast::Statement>>, ParserError> {
+    // Parse the statements that start with a keyword or are a mutation (e.g `x <- 1;`), stopping
+    // at anything else
+    fn parse_statements(&self, sym_table: &mut SymbolTable) -> Result<Vec<ast::Statement>, ParserError> {
         use TokenKind::*;
 
         let mut statements = Vec::new();
@@ -376,21 +778,17 @@ impl Parser<'_> {
                                                   LetKw,
                                                   StructKw,
                                                   ChoiceKw,
+                                                  BreakKw,
+                                                  ContinueKw,
+                                                  MatchKw,
                                                   Ident]);
 
-            // No statements to parse
-            if curr_token.is_none() && statements.is_empty()
-            {
-                return Ok(None);
-            }
-
             // *No more* statements to parse
-            if curr_token.is_none() && !statements.is_empty()
+            let Some(curr_token) = curr_token
+            else
             {
-                return Ok(Some(statements));
-            }
-
-            let curr_token = curr_token.unwrap();
+                break 'parse_stmts;
+            };
 
             // Parse statement
             let statement = match curr_token.get_token_kind()
@@ -399,17 +797,15 @@ impl Parser<'_> {
                 IfKw    => self.parse_selection(sym_table)?,
                 WhileKw => self.parse_indefinite_loop(sym_table)?,
                 ForKw   => self.parse_definite_loop(sym_table)?,
+                BreakKw | ContinueKw => self.parse_loop_control()?,
+                MatchKw => self.parse_match(sym_table)?,
                 // Parse `VarBindingMut` if current is `Ident` and next is `<-`
-                Ident if self.optional_peek_next(&[Assign]).is_some() =>
+                Ident if self.optional_peek_nth(1, &[Assign]).is_some() =>
                 {
                     self.parse_var_binding_mutation()?
                 }
-                Ident if self.optional_peek_next(&[LParn]).is_some() =>
-                {
-                    self.parse_func_call()?
-                }
                 // @todo: Add support for `struct` and `choice` decls
-                _ if statements.is_empty() => return Ok(None),
+                // Anything else is an expression, which `parse_block_rest` handles
                 _ => break 'parse_stmts,
             };
 
@@ -417,68 +813,84 @@ impl Parser<'_> {
             statements.push(statement);
         }
 
-        Ok(Some(statements))
+        Ok(statements)
     }
 
-    fn parse_func_call(&self) -> Result<ast::Statement, ParserError> {
-        use TokenKind::*;
-
-        //let _let_kw = self.try_consume(&[LetKw])?;
+    // Statement for an expression that was followed by `;`. A call on its own (e.g `print(x);`)
+    // is a `FuncCall`.
+    fn expr_statement(expr: ast::Expression) -> ast::Statement {
+        match expr
+        {
+            ast::Expression::Cons(name, args) if name.is_a(TokenKind::Ident) =>
+            {
+                ast::Statement::new_func_call(name, args.into_iter().map(Some).collect())
+            }
+            expr => ast::Statement::new_expr(expr),
+        }
+    }
 
-        //let var_bind_name = self.try_consume(&[Ident])?;
+    fn parse_loop_control(&self) -> Result<ast::Statement, ParserError> {
+        use TokenKind::*;
 
-        // Try to see if user supplied ty-hint and get it.
-        // We either have `("::", ty_hint)` or `(None, None)` or error.
-        //let types = &[Ident, IntTy, FloatTy, TextTy, BoolTy];
-        //let (_t_qualifier, ty_hint) = self.try_consume2_or_none(&[TQualifer], types)?;
+        // Parse `break;` or `continue;`
+        let control_kw = self.try_consume(&[BreakKw, ContinueKw])?;
+        let _semicolon = self.consume_semicolon()?;
 
-        // If hint was supplied, we have `Some(type_hint)`. Otherwise, `None`.
-        //let ty_hint = if ty_hint.is_some() { Some(Type(ty_hint.unwrap())) } else { None };
-	
-        //let _assign_op = self.try_consume(&[Assign])?;
+        if control_kw.is_a(BreakKw)
+        {
+            Ok(ast::Statement::new_break(control_kw))
+        }
+        else
+        {
+            Ok(ast::Statement::new_continue(control_kw))
+        }
+    }
 
-        // Check for `[`, as we might have a list 
-        //let _l_bracket = self.optional_peek(&[LSBracket]);
-        
-        // We have a list to parse
-        let mut args;
-    
-        let func_call_name = self.try_consume(&[Ident])?;
-        self.increment_parser_pos_by(1);
+    fn parse_match(&self, sym_table: &mut SymbolTable) -> Result<ast::Statement, ParserError> {
+        use TokenKind::*;
 
+        // Parse `match expr {`
+        let match_kw          = self.try_consume(&[MatchKw])?;
         let min_binding_power = 0;
-        args = Vec::new();
-            
-        'parse_func_call: loop 
+        let Some(scrutinee)   = self.parse_expression(min_binding_power)?
+        else
         {
-            // Hit the end of the struct init 
-            if self.optional_peek(&[RParn]).is_some()
-            {
-                self.increment_parser_pos_by(1);
-                self.try_consume(&[Semicolon])?;
+            let offset = self.report_offset(match_kw.get_file_index());
 
-               break 'parse_func_call;
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::missing_expr_at(
+                    "match",
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
             }
 
-            // Try to parse struct init parm
-            args.push(self.parse_expression(min_binding_power)?);
+            return Err(ParserError::MissingExpression { at: "match".to_string(), offset });
+        };
+        let _l_bracket = self.try_consume(&[LBracket])?;
 
-            // Hit a comma seperator 
-            if self.optional_peek(&[Sep]).is_some()
-            {
-                self.increment_parser_pos_by(1);
-                
-                continue 'parse_func_call; 
-            } 
-            else 
+        // Parse comma separated arms (e.g `Red => { ... }, Blue => { ... }`)
+        let mut arms = Vec::new();
+        while self.optional_peek(&[RBracket]).is_none()
+        {
+            let variant    = self.try_consume(&[Ident])?;
+            let _fat_arrow = self.try_consume(&[FatArrow])?;
+            let arm_block  = self.parse_block(sym_table)?;
+            arms.push((variant, arm_block));
+
+            // The last arm may leave out its comma (e.g `A => {}, B => {} }`)
+            let next_tok = self.try_peek(&[Sep, RBracket])?;
+            if next_tok.is_a(Sep)
             {
-                self.try_consume(&[RParn])?;
-                break 'parse_func_call;
+                self.advance_parser_pos();
             }
         }
+        let _r_bracket = self.try_consume(&[RBracket])?;
 
-        let _semicolon = self.try_consume(&[Semicolon])?;
-        Ok(ast::Statement::new_func_call(func_call_name, args))
+        Ok(ast::Statement::new_match(scrutinee, arms))
     }
 
     fn parse_definite_loop(&self, sym_table: &mut SymbolTable) -> Result<ast::Statement, ParserError> {
@@ -488,9 +900,10 @@ impl Parser<'_> {
         let _for_kw        = self.try_consume(&[ForKw])?;
         let for_index      = self.try_consume(&[Ident])?;
         let _in_kw         = self.try_consume(&[InKw])?;
-        let for_low_bound  = self.try_consume(&[NumLit])?;
+        // Float bounds are parsed so the semantic analyzer can say why they are not allowed
+        let for_low_bound  = self.try_consume(&[NumLit, FloatLit])?;
         let _for_range     = self.try_consume(&[ExRange])?;
-        let for_high_bound = self.try_consume(&[NumLit])?;
+        let for_high_bound = self.try_consume(&[NumLit, FloatLit])?;
         let for_block      = self.parse_block(sym_table)?;
 
         Ok(ast::Statement::new_definite_loop(for_index, for_low_bound, for_high_bound, for_block))
@@ -505,15 +918,20 @@ impl Parser<'_> {
         let Some(while_expr)  = self.parse_expression(min_binding_power)?
         else
         {
+            let offset = self.report_offset(while_kw.get_file_index());
+
             // Fancy compiler error
-                ParserErrorReporter::missing_expr_at(
-            "while-loop",
-                    self.path.to_str().unwrap(),
-                    self.cleaned_source,
-                    while_kw.get_file_index(),
-                );
+                if self.error_counter.report()
+                {
+                    ParserErrorReporter::missing_expr_at(
+                        "while-loop",
+                        self.path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
 
-            return Err(ParserError::ParseFail);
+            return Err(ParserError::MissingExpression { at: "while-loop".to_string(), offset });
         };
         let while_block  = self.parse_block(sym_table)?;
 
@@ -544,15 +962,20 @@ impl Parser<'_> {
         let Some(if_expr)     = self.parse_expression(min_binding_power)?
         else
         {
+            let offset = self.report_offset(if_kw.get_file_index());
+
             // Fancy compiler error
-                ParserErrorReporter::missing_expr_at(
-                "if-branch",
-                self.path.to_str().unwrap(),
-                self.cleaned_source,
-                if_kw.get_file_index(),
-            );
+                if self.error_counter.report()
+                {
+                    ParserErrorReporter::missing_expr_at(
+                        "if-branch",
+                        self.path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
 
-	    return Err(ParserError::ParseFail);
+	    return Err(ParserError::MissingExpression { at: "if-branch".to_string(), offset });
 	};
         let if_block     = self.parse_block(sym_table)?;
         let if_comp      = ast::IfComp::new(if_expr, if_block);
@@ -560,6 +983,60 @@ impl Parser<'_> {
         Ok(if_comp)
     }
 
+    // Parse the rest of an `if` used as a value, after its `if_kw`
+    fn parse_if_expression(&self, if_kw: Token) -> Result<ast::Expression, ParserError> {
+        use TokenKind::*;
+
+        let min_binding_power = 0;
+        let Some(cond)        = self.parse_expression(min_binding_power)?
+        else
+        {
+            let offset = self.report_offset(if_kw.get_file_index());
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::missing_expr_at(
+                    "if-branch",
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::MissingExpression { at: "if-branch".to_string(), offset });
+        };
+
+        // Bindings inside the branches still belong in the `SymbolTable`
+        let mut sym_table = SymbolTable::new();
+        let then_block    = self.parse_block(&mut sym_table)?;
+
+        if self.optional_peek(&[ElseKw]).is_none()
+        {
+            let offset = self.report_offset(if_kw.get_file_index());
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::if_value_missing_else(
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::MissingElse { offset });
+        }
+        self.advance_parser_pos();
+
+        let else_block = self.parse_block(&mut sym_table)?;
+        self.expr_symbols.borrow_mut().append(&mut sym_table);
+
+        let (then_block, else_block) = (Box::new(then_block), Box::new(else_block));
+
+        Ok(ast::Expression::new_if(if_kw, Box::new(cond), then_block, else_block))
+    }
+
     fn parse_elif_comp(&self, sym_table: &mut SymbolTable) -> Result<Option<ast::ElifComp>, ParserError> {
         use TokenKind::*;
 	
@@ -577,15 +1054,20 @@ impl Parser<'_> {
         let Some(elif_expr)   = self.parse_expression(min_binding_power)?
         else
         {
+            let offset = self.report_offset(elif_kw.get_file_index());
+
             // Fancy compiler error
-            ParserErrorReporter::missing_expr_at(
-            "elif-branch",
-            self.path.to_str().unwrap(),
-            self.cleaned_source,
-            elif_kw.get_file_index(),
-        );
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::missing_expr_at(
+                    "elif-branch",
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
-            return Err(ParserError::ParseFail);
+            return Err(ParserError::MissingExpression { at: "elif-branch".to_string(), offset });
         };
         let elif_block     = self.parse_block(sym_table)?;
         let elif_comp      = Some(ast::ElifComp::new(elif_expr, elif_block));
@@ -622,8 +1104,7 @@ impl Parser<'_> {
 
         // Try to see if user supplied ty-hint and get it.
         // We either have `("::", ty_hint)` or `(None, None)` or error.
-        let types = &[Ident, IntTy, FloatTy, TextTy, BoolTy];
-        let (_t_qualifier, ty_hint) = self.try_consume2_or_none(&[TQualifer], types)?;
+        let (_t_qualifier, ty_hint) = self.try_consume2_or_none(&[TQualifer], TYPE_TOKENS)?;
 
         // If hint was supplied, we have `Some(type_hint)`. Otherwise, `None`.
         let ty_hint = if ty_hint.is_some() { Some(TypeTok(ty_hint.unwrap())) } else { None };
@@ -649,7 +1130,7 @@ impl Parser<'_> {
                 if self.optional_peek(&[RSBracket]).is_some()
                 {
                     self.increment_parser_pos_by(1);
-                    self.try_consume(&[Semicolon])?;
+                    self.consume_semicolon()?;
 
                    break 'parse_list;
                 }
@@ -679,7 +1160,7 @@ impl Parser<'_> {
             // Update Symbol Table 
             sym_table.push(var_sym_node);
 
-            let _semicolon = self.try_consume(&[Semicolon])?;
+            let _semicolon = self.consume_semicolon()?;
             return Ok(ast::Statement::new_var_binding_init(
                 var_bind_name,
                 ty_hint,
@@ -687,7 +1168,7 @@ impl Parser<'_> {
             ));
         } 
         else if self.optional_peek(&[Ident]).is_some() 
-            && self.optional_peek_next(&[LBracket]).is_some()
+            && self.optional_peek_nth(1, &[LBracket]).is_some()
         {
             let struct_init_name = self.try_consume(&[Ident])?;
             self.increment_parser_pos_by(1);
@@ -701,7 +1182,7 @@ impl Parser<'_> {
                 if self.optional_peek(&[RBracket]).is_some()
                 {
                     self.increment_parser_pos_by(1);
-                    self.try_consume(&[Semicolon])?;
+                    self.consume_semicolon()?;
 
                    break 'parse_struct_init;
                 }
@@ -731,7 +1212,7 @@ impl Parser<'_> {
             // Update Symbol Table 
             sym_table.push(var_sym_node);
 
-            let _semicolon = self.try_consume(&[Semicolon])?;
+            let _semicolon = self.consume_semicolon()?;
             return Ok(ast::Statement::new_var_binding_init(
                 var_bind_name,
                 ty_hint,
@@ -740,42 +1221,40 @@ impl Parser<'_> {
 
         }
         else if self.optional_peek(&[Ident]).is_some() 
-            && self.optional_peek_next(&[LParn]).is_some()
+            && self.optional_peek_nth(1, &[LParn]).is_some()
         {
             let func_call_name = self.try_consume(&[Ident])?;
-            self.increment_parser_pos_by(1);
+            let l_parn         = self.try_consume(&[LParn])?;
+            self.open_paren();
 
             let min_binding_power = 0;
             rhs = Vec::new();
-            
-            'parse_func_call: loop 
+
+            'parse_func_call: loop
             {
-                // Hit the end of the struct init 
+                // Hit the end of the function call args
                 if self.optional_peek(&[RParn]).is_some()
                 {
-                    self.increment_parser_pos_by(1);
-                    self.try_consume(&[Semicolon])?;
-
                    break 'parse_func_call;
                 }
 
-                // Try to parse struct init parm
+                // Try to parse function call arg
                 rhs.push(self.parse_expression(min_binding_power)?);
 
-                // Hit a comma seperator 
+                // Hit a comma seperator
                 if self.optional_peek(&[Sep]).is_some()
                 {
                     self.increment_parser_pos_by(1);
-                    
-                    continue 'parse_func_call; 
-                } 
-                else 
-                {
-                    self.try_consume(&[RParn])?;
-                    break 'parse_func_call;
+
+                    continue 'parse_func_call;
                 }
+
+                break 'parse_func_call;
             }
 
+            // Make sure the call's `(` is closed
+            let _r_parn = self.try_close_paren(l_parn.get_file_index())?;
+
             // Create symbol node
             let var_sym_node = SymbolNode::new(var_bind_name.clone(), Type::Undetermined, 
                                                self.scope_cursor.get(), self.scope_stream[self.scope_cursor.get()].get());
@@ -784,7 +1263,7 @@ impl Parser<'_> {
             // Update Symbol Table 
             sym_table.push(var_sym_node);
 
-            let _semicolon = self.try_consume(&[Semicolon])?;
+            let _semicolon = self.consume_semicolon()?;
             return Ok(ast::Statement::new_var_binding_init(
                 var_bind_name,
                 ty_hint,
@@ -798,23 +1277,29 @@ impl Parser<'_> {
             let min_binding_power = 0;
             let rhs = self.parse_expression(min_binding_power)?;
 
-            println!("S-Expr: `{rhs}`", rhs=rhs.clone().unwrap());
-
             if rhs.is_none()
             {
                 // Fancy compiler error
+                let offset = self.report_offset(var_bind_name.get_file_index());
+
                 // Print fancy compiler error
-                ParserErrorReporter::var_bind_missing_rhs(
-                    &var_bind_name,
-                    self.path.to_str().unwrap(),
-                    self.cleaned_source,
-                    var_bind_name.get_file_index(),
-                );
+                if self.error_counter.report()
+                {
+                    ParserErrorReporter::var_bind_missing_rhs(
+                        &var_bind_name,
+                        self.path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
 
-                return Err(ParserError::ParseFail);
+                return Err(ParserError::MissingRhs {
+                    name: var_bind_name.get_raw_content().to_string(),
+                    offset,
+                });
             };
 
-            let _semicolon = self.try_consume(&[Semicolon])?;
+            let _semicolon = self.consume_semicolon()?;
 
             // Create symbol node
             let var_sym_node = SymbolNode::new(var_bind_name.clone(), Type::Undetermined, 
@@ -848,18 +1333,26 @@ impl Parser<'_> {
         if rhs.is_none()
         {
             // Fancy compiler error
+            let offset = self.report_offset(var_bind_name.get_file_index());
+
             // Print fancy compiler error
-            ParserErrorReporter::var_bind_missing_rhs(
-                &var_bind_name,
-                self.path.to_str().unwrap(),
-                self.cleaned_source,
-                var_bind_name.get_file_index(),
-            );
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::var_bind_missing_rhs(
+                    &var_bind_name,
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
-            return Err(ParserError::ParseFail);
+            return Err(ParserError::MissingRhs {
+                name: var_bind_name.get_raw_content().to_string(),
+                offset,
+            });
         };
 
-        let _semicolon = self.try_consume(&[Semicolon])?;
+        let _semicolon = self.consume_semicolon()?;
 
         Ok(ast::Statement::new_var_binding_mut(
             var_bind_name,
@@ -867,17 +1360,33 @@ impl Parser<'_> {
         ))
     }
     
-    fn get_prefix_bind_power(op: &Token) -> ((), u8) {
+    // Operator precedence, from loosest to tightest binding:
+    //
+    // | Operators                     | Kind    | Binding power |
+    // |-------------------------------|---------|---------------|
+    // | `or`                          | infix   | (1, 2)        |
+    // | `and`                         | infix   | (3, 4)        |
+    // | `==`, `<`, `<=`, `>`, `>=`    | infix   | (5, 6)        |
+    // | `+`, `-`                      | infix   | (7, 8)        |
+    // | `*`, `/`                      | infix   | (9, 10)       |
+    // | `-` (negation)                | prefix  | 11            |
+    // | `as`                          | infix   | (13, 14)      |
+    // | `.`, `::`                     | infix   | (15, 16)      |
+    // | `[]` (subscript), `()` (call) | postfix | 17            |
+    //
+    // Every infix operator is left associative. Casts bind tighter than negation, so
+    // `-1 as float` is `(- (as 1 float))`.
+    pub(crate) fn get_prefix_bind_power(op: &Token) -> ((), u8) {
 	use TokenKind::*;
 
 	// Note: binding power gives us precedence AND associatvity 
 	match op {
-            tok if tok.is_a(Minus) => ((), 15),
+            tok if tok.is_a(Minus) => ((), 11),
             bad_tok => panic!("Unsupported op: {:?}", bad_tok)
 	}
     }
 
-    fn get_infix_bind_power(op: &Token) -> Option<(u8, u8)> {
+    pub(crate) fn get_infix_bind_power(op: &Token) -> Option<(u8, u8)> {
 	use TokenKind::*;
 
 	// Note: binding power gives us precedence AND associatvity 
@@ -893,46 +1402,106 @@ impl Parser<'_> {
             tok if tok.is_a(Minus)      => (7, 8),
             tok if tok.is_a(Mul)        => (9, 10),
             tok if tok.is_a(Div)        => (9, 10),
-            tok if tok.is_a(AsKw)       => (11, 12),
-            tok if tok.is_a(RecordDot)  => (13, 14),
-            tok if tok.is_a(TQualifer)  => (13, 14),
+            tok if tok.is_a(AsKw)       => (13, 14),
+            tok if tok.is_a(RecordDot)  => (15, 16),
+            tok if tok.is_a(TQualifer)  => (15, 16),
             bad_tok => panic!("Unsupported op: {:?}", bad_tok)
 	};
 
 	Some(infix_bp)
     }
 
+    // Is `op` one of the comparison operators (e.g `<` or `==`)?
+    pub(crate) fn is_comparison(op: &Token) -> bool {
+	use TokenKind::*;
+
+	[Eq, Lt, Lte, Gt, Gte].contains(&op.get_token_kind())
+    }
     fn get_postfix_bind_power(op: &Token) -> Option<(u8, ())> {
 	use TokenKind::*;
 
 	// Note: binding power gives us precedence AND associatvity 
 	let postfix_bp = match op {
             tok if tok.is_a(LSBracket) => (17, ()),
+            // Looser than `.`, so `a.b(c)` calls `a.b` instead of reading field `b(c)` of `a`
+            tok if tok.is_a(LParn)     => (15, ()),
             _ => return None,
 	};
 
 	Some(postfix_bp)
     }
 
-    // Pratt parsing of expressions into S-Expressions
+    // Parse an expression, failing instead of overflowing the stack if it is nested too deep
+    // (see `Parser::nested`)
     fn parse_expression(&self, minimum_bp: u8) -> Result<Option<ast::Expression>, ParserError> {
+        self.nested(|| self.parse_nested_expression(minimum_bp))
+    }
+
+    // Run `parse` one level of nesting deeper, failing instead of overflowing the stack once
+    // expressions and blocks are nested more than `max_expr_depth` deep (e.g a thousand `(` or
+    // nested `if` bodies)
+    fn nested<T>(&self, parse: impl FnOnce() -> Result<T, ParserError>) -> Result<T, ParserError> {
+        let depth = self.expr_depth.get() + 1;
+        if depth > self.max_expr_depth
+        {
+            let tok = self.peek().or_else(|| self.tokens.last().cloned()).unwrap();
+            let offset = self.report_offset(tok.get_file_index());
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::expression_too_deep(
+                    self.max_expr_depth,
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::ExpressionTooDeep { offset });
+        }
+
+        self.expr_depth.set(depth);
+        let parsed = parse();
+        self.expr_depth.set(depth - 1);
+
+        parsed
+    }
+
+    // Pratt parsing of expressions into S-Expressions
+    fn parse_nested_expression(
+        &self,
+        minimum_bp: u8,
+    ) -> Result<Option<ast::Expression>, ParserError> {
 	use TokenKind::*;
 
 	// Parse LHS of expression 
-	let op_kind    = &[Minus]; 
-	let ty_kind    = &[Ident, FloatTy, IntTy, BoolTy, TextTy];
-	let value_kind = &[BoolLit, NumLit, FloatLit];
-	let punc_kind  = &[RBracket, Semicolon, LParn, Sep];
-	let all_kind   = &[&op_kind[..], &ty_kind[..], &value_kind[..], &punc_kind[..]].concat();
-	let mut lhs = match self.try_consume(&all_kind)? {
-            good_tok if good_tok.is_a(RBracket)  => {self.decrement_parser_pos_by(1); return Ok(None);},
-	    good_tok if good_tok.is_a(Semicolon) => {self.decrement_parser_pos_by(1); return Ok(None);},
-	    good_tok if good_tok.is_a(Sep) => {self.decrement_parser_pos_by(1); return Ok(None);},
+	let all_kind = [PREFIX_OP_TOKENS, TYPE_TOKENS, LITERAL_TOKENS, LHS_PUNC_TOKENS, LHS_KW_TOKENS]
+	    .concat();
+	let lhs_tok = self.try_peek(&all_kind)?;
+
+	// No expression here, so leave the closing punctuation for the caller
+	if lhs_tok.is_a(RBracket) || lhs_tok.is_a(Semicolon) || lhs_tok.is_a(Sep)
+	{
+	    return Ok(None);
+	}
+	self.advance_parser_pos();
+
+	// A parenthesized comparison may be compared again (e.g `(a < b) == c`)
+	let mut lhs_is_parenthesized = lhs_tok.is_a(LParn);
+
+	let mut lhs = match lhs_tok {
 	    // `(` Expression `)` support 
 	    good_tok if good_tok.is_a(LParn) => {
+		self.open_paren();
+		let paren_depth = self.paren_depth.get();
+
 		let min_bp = 0;
 		let lhs = self.parse_expression(min_bp)?;
-		self.try_consume(&[RParn])?;
+
+		// Every `(` opened by the sub-expression must also be closed by it
+		debug_assert_eq!(self.paren_depth.get(), paren_depth, "Paren depth changed in sub-expression");
+		self.try_close_paren(good_tok.get_file_index())?;
 
 		lhs.unwrap()
 	    },
@@ -949,22 +1518,60 @@ impl Parser<'_> {
 		
 	    },
 
+	    // `if` expression support (e.g `if a { 1 } else { 2 }`)
+	    good_tok if good_tok.is_a(IfKw) => self.parse_if_expression(good_tok)?,
+
+	    // Block expression support (e.g `{ let t <- 1; t + 1 }`)
+	    good_tok if good_tok.is_a(LBracket) => {
+		// Bindings inside the block still belong in the `SymbolTable`
+		let mut sym_table = SymbolTable::new();
+		let block = self.parse_block_rest(&mut sym_table)?;
+		self.expr_symbols.borrow_mut().append(&mut sym_table);
+
+		ast::Expression::new_block(good_tok, Box::new(block))
+	    },
+
+	    // Literal support (e.g `5` or `true`)
+	    good_tok if LITERAL_TOKENS.contains(&good_tok.get_token_kind()) => {
+		match ast::LitValue::from_token(&good_tok)
+		{
+		    Some(value) => ast::Expression::new_literal(good_tok, value),
+		    None => ast::Expression::new_atom(good_tok),
+		}
+	    },
+
             good_tok => ast::Expression::new_atom(good_tok),
 	};
 
 	loop {
 	    
 	    // Parse operator of expression (if found)
-	    let op_kind   = &[Plus, Minus, Div, Mul, Lt, Lte, Gt, Gte, Eq, OrKw, AndKw, AsKw, RecordDot, TQualifer]; 
-	    let punc_kind = &[LSBracket, RSBracket,RParn, LBracket, RBracket, Semicolon, Sep];
-	    let all_kind  = &[&op_kind[..], &punc_kind[..]].concat();
+	    let all_kind = [INFIX_OP_TOKENS, OP_PUNC_TOKENS].concat();
             let op = match self.try_peek(&all_kind)? {
 		tok if tok.is_a(EOF) => break,
-		tok if tok.is_a(Semicolon) => {self.decrement_parser_pos_by(0); break},
-		tok if op_kind.contains(&tok.get_token_kind()) => tok,
-		// tok if ty_kind.contains(&tok.get_token_kind()) => tok,
+		tok if tok.is_a(Semicolon) => break,
+		tok if INFIX_OP_TOKENS.contains(&tok.get_token_kind()) => tok,
+		// tok if TYPE_TOKENS.contains(&tok.get_token_kind()) => tok,
 		tok if tok.is_a(LSBracket) => tok,
+		tok if tok.is_a(LParn) => tok,
 		tok if tok.is_a(RSBracket) => break,
+		// A `)` with no `(` left open to match it
+		tok if tok.is_a(RParn) && self.paren_depth.get() == 0 => {
+		    let offset = self.report_offset(tok.get_file_index());
+
+		    // Fancy compiler error
+                    if self.error_counter.report()
+                    {
+                        ParserErrorReporter::unbalanced_paren(
+                            &RParn,
+                            self.path.to_str().unwrap(),
+                            self.report_source(),
+                            offset,
+                        );
+                    }
+
+		    return Err(ParserError::UnbalancedParen { paren: RParn, offset });
+		},
 		tok if tok.is_a(RParn) => break,
 		tok if tok.is_a(LBracket) => break,
 		tok if tok.is_a(RBracket) => break,
@@ -992,9 +1599,13 @@ impl Parser<'_> {
 		    self.try_consume(&[RSBracket])?;
 		    
 		    ast::Expression::new_cons(op, vec![lhs, rhs])
+		} else if op.is_a(LParn) {
+		    // Parse function call (e.g `f(g(1), 2)`)
+		    self.parse_call_expression(op, lhs)?
 		} else {
 		    ast::Expression::new_cons(op, vec![lhs])
 		};
+		lhs_is_parenthesized = false;
 
 		continue;
 	    }
@@ -1008,10 +1619,37 @@ impl Parser<'_> {
 		    break;
 		}
 
+		// Catch `a < b < c`, which would compare the bool `a < b` to `c`
+		if Parser::is_comparison(&op) && !lhs_is_parenthesized
+		{
+		    if let ast::Expression::Cons(inner_op, _) = &lhs
+		    {
+			if Parser::is_comparison(inner_op)
+			{
+			    let offset = self.report_offset(op.get_file_index());
+
+			    // Fancy compiler error
+			    if self.error_counter.report()
+			    {
+				ParserErrorReporter::chained_comparison(
+				    inner_op.get_token_kind(),
+				    op.get_token_kind(),
+				    self.path.to_str().unwrap(),
+				    self.report_source(),
+				    offset,
+				);
+			    }
+
+			    return Err(ParserError::ChainedComparison { offset });
+			}
+		    }
+		}
+
 		self.advance_parser_pos();
+		lhs_is_parenthesized = false;
 		lhs =  if op.is_a(AsKw) {
 		    // Make sure next token is a type
-		    self.try_peek(&[IntTy, Ident, FloatTy, BoolTy, TextTy])?;
+		    self.try_peek(TYPE_TOKENS)?;
 		    let Some(rhs) = self.parse_expression(right_bp)?
 		    else
 		    {
@@ -1042,13 +1680,137 @@ impl Parser<'_> {
 	Ok(Some(lhs))
     }
     
-    fn try_consume_ty(&self) -> Result<Token, ParserError> {
+    // Parse the arguments of a function call in expression position. The call is stored as an
+    // S-Expression headed by the function name (e.g `f(g(1), 2)` becomes `(f (g 1) 2)`), or by
+    // `(` if the function is named by a field access (e.g `m.f(1)` becomes `(( (. m f) 1)`).
+    fn parse_call_expression(&self, l_parn: Token, callee: ast::Expression) -> Result<ast::Expression, ParserError> {
         use TokenKind::*;
 
-        // Fetch next token
-        // @todo: add support for ADTs
-        let ty_token = self.try_consume(&[IntTy, FloatTy, BoolTy, TextTy])?;
+        // Only named functions can be called, either by name or through a field access (e.g
+        // `p.norm()` or `math::sqrt(x)`)
+        let func_call_name = match &callee
+        {
+            ast::Expression::Atom(func_call_name) => Some(func_call_name.clone()),
+            ast::Expression::Cons(op, _) if op.is_a(RecordDot) || op.is_a(TQualifer) => None,
+            _ =>
+            {
+                let offset = self.report_offset(l_parn.get_file_index());
+
+                // Fancy compiler error
+                if self.error_counter.report()
+                {
+                    ParserErrorReporter::unexpected_token(
+                        &l_parn.get_token_kind(),
+                        &[Semicolon],
+                        self.path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
+
+                return Err(ParserError::UnexpectedToken {
+                    found: l_parn.get_token_kind(),
+                    expected: vec![Semicolon],
+                    offset,
+                });
+            }
+        };
+
+        if let Some(func_call_name) = func_call_name.as_ref().filter(|name| !name.is_a(Ident))
+        {
+            let offset = self.report_offset(func_call_name.get_file_index());
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &func_call_name.get_token_kind(),
+                    &[Ident],
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::UnexpectedToken {
+                found: func_call_name.get_token_kind(),
+                expected: vec![Ident],
+                offset,
+            });
+        }
+
+        // `(` was already consumed by the caller
+        self.open_paren();
+
+        let min_binding_power = 0;
+        let mut args = Vec::new();
+
+        'parse_call_args: loop
+        {
+            // Hit the end of the function call args
+            if self.optional_peek(&[RParn]).is_some()
+            {
+                break 'parse_call_args;
+            }
+
+            // Try to parse function call arg
+            let Some(arg) = self.parse_expression(min_binding_power)?
+            else
+            {
+                break 'parse_call_args;
+            };
+            args.push(arg);
+
+            // Hit a comma seperator
+            if self.optional_peek(&[Sep]).is_some()
+            {
+                self.advance_parser_pos();
+
+                continue 'parse_call_args;
+            }
+
+            break 'parse_call_args;
+        }
+
+        // Make sure the call's `(` is closed
+        let _r_parn = self.try_close_paren(l_parn.get_file_index())?;
+
+        // A qualified call is headed by its `(`, with the field access as the first operand
+        match func_call_name
+        {
+            Some(func_call_name) => Ok(ast::Expression::new_cons(func_call_name, args)),
+            None => Ok(ast::Expression::new_cons(l_parn, [vec![callee], args].concat())),
+        }
+    }
+
+    // Consume the type of `needs_ty` (e.g "field `age`"), which is named in the error if no type
+    // was given
+    fn try_consume_ty(&self, needs_ty: &str) -> Result<Token, ParserError> {
+        // Fetch next token, falling back to the final token (e.g `EOF`) if we run off the end. An
+        // `Ident` names a structure or choice (e.g `friend :: Person`), which the semantic stage
+        // checks was declared.
+        let ty_token = self.peek().or_else(|| self.tokens.last().cloned()).unwrap();
+
+        if !ty_token.get_token_kind().is_type()
+        {
+            let offset = self.report_offset(ty_token.get_file_index());
+
+            // Print fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::expected_type_for(
+                    needs_ty,
+                    &ty_token.get_token_kind(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::MissingType { offset });
+        }
 
+        self.advance_parser_pos();
         Ok(ty_token)
     }
 
@@ -1067,7 +1829,7 @@ impl Parser<'_> {
             let _t_qualifier_tok = self.try_consume(&[TQualifer])?;
 
             // Get explict type
-            let explicit_ty = self.try_consume_ty()?;
+            let explicit_ty = self.try_consume_ty(&format!("field `{ident_tok}`"))?;
 
             Ok(Some((ident_tok, explicit_ty)))
         }
@@ -1085,20 +1847,93 @@ impl Parser<'_> {
 
         if !valid_tokens.contains(&curr_tok.get_token_kind())
         {
+            let offset = self.report_offset(curr_tok.get_file_index());
+
             // Print fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &curr_tok.get_token_kind(),
+                    valid_tokens.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::UnexpectedToken {
+                found: curr_tok.get_token_kind(),
+                expected: valid_tokens.to_vec(),
+                offset,
+            });
+        }
+
+        self.advance_parser_pos();
+        Ok(curr_tok)
+    }
+
+    // Consume the `;` ending a statement. While collecting errors, a missing `;` is reported and
+    // a synthetic one is inserted right after the previous `Token`, so parsing goes on with the
+    // rest of the declaration instead of skipping it.
+    fn consume_semicolon(&self) -> Result<Token, ParserError> {
+        let recovering = self.collect_errors && !self.is_a(TokenKind::Semicolon);
+        let Some(prev_tok) = self.peek_previous().filter(|_| recovering)
+        else
+        {
+            return self.try_consume(&[TokenKind::Semicolon]);
+        };
+
+        let found_tok = self.peek().or_else(|| self.tokens.last().cloned()).unwrap();
+
+        // Print fancy compiler error
+        if self.error_counter.report()
+        {
             ParserErrorReporter::unexpected_token(
-                &curr_tok.get_token_kind(),
-                valid_tokens.into(),
+                &found_tok.get_token_kind(),
+                &[TokenKind::Semicolon],
                 self.path.to_str().unwrap(),
-                self.cleaned_source,
-                curr_tok.get_file_index(),
+                self.report_source(),
+                self.report_offset(found_tok.get_file_index()),
             );
+        }
 
-            return Err(ParserError::ParseFail);
+        let semicolon = Token::synthetic(TokenKind::Semicolon)
+            .with_position(prev_tok.file_range().end, *prev_tok.span_end_ref());
+        self.recovered.set(true);
+
+        Ok(semicolon)
+    }
+
+    // Tries to peek the Token `n` positions ahead in stream with the provided set of Tokens that
+    // are acceptable via `expected_token` without advancing pos in token stream
+    fn try_peek_nth(&self, n: usize, valid_tokens: &[TokenKind]) -> Result<Token, ParserError> {
+        // Fetch token in stream, falling back to the final token (e.g `EOF`) if we run off the end
+        let tok = self.peek_nth(n).or_else(|| self.tokens.last().cloned()).unwrap();
+
+        if !valid_tokens.contains(&tok.get_token_kind())
+        {
+            let offset = self.report_offset(tok.get_file_index());
+
+            // Print fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &tok.get_token_kind(),
+                    valid_tokens.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::UnexpectedToken {
+                found: tok.get_token_kind(),
+                expected: valid_tokens.to_vec(),
+                offset,
+            });
         }
 
-        self.advance_parser_pos();
-        Ok(curr_tok)
+        Ok(tok)
     }
 
     // Tries to peek a single Token in stream with the provided set of Tokens that are
@@ -1109,16 +1944,25 @@ impl Parser<'_> {
 
         if !valid_tokens.contains(&curr_tok.get_token_kind())
         {
+            let offset = self.report_offset(curr_tok.get_file_index());
+
             // Print fancy compiler error
-            ParserErrorReporter::unexpected_token(
-                &curr_tok.get_token_kind(),
-                valid_tokens.into(),
-                self.path.to_str().unwrap(),
-                self.cleaned_source,
-                curr_tok.get_file_index(),
-            );
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &curr_tok.get_token_kind(),
+                    valid_tokens.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
-            return Err(ParserError::ParseFail);
+            return Err(ParserError::UnexpectedToken {
+                found: curr_tok.get_token_kind(),
+                expected: valid_tokens.to_vec(),
+                offset,
+            });
         }
 
         Ok(curr_tok)
@@ -1147,16 +1991,21 @@ impl Parser<'_> {
             // NOTE: note a performance issue since its an error case.
             let token1 = token1.unwrap().clone();
 
+            let offset = self.report_offset(token1.get_file_index());
+
             // Print fancy compiler error
-            ParserErrorReporter::missing_ty(
-                &token1.get_token_kind(),
-                valid_tokens2.into(),
-                self.path.to_str().unwrap(),
-                self.cleaned_source,
-                token1.get_file_index(),
-            );
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::missing_ty(
+                    &token1.get_token_kind(),
+                    valid_tokens2.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
-            return Err(ParserError::ParseFail);
+            return Err(ParserError::MissingType { offset });
         }
 
         if token1.is_none() && token2.is_some()
@@ -1165,17 +2014,26 @@ impl Parser<'_> {
             // NOTE: note a performance issue since its an error case.
             let token2 = token2.unwrap().clone();
 
-            // Print fancy compiler error
-            ParserErrorReporter::unexpected_token(
-                &token2.get_token_kind(),
-                valid_tokens1.into(),
-                self.path.to_str().unwrap(),
-                self.cleaned_source,
-                token2.get_file_index(),
-            );
-
-            return Err(ParserError::ParseFail);
-        }
+            let offset = self.report_offset(token2.get_file_index());
+
+            // Print fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &token2.get_token_kind(),
+                    valid_tokens1.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::UnexpectedToken {
+                found: token2.get_token_kind(),
+                expected: valid_tokens1.to_vec(),
+                offset,
+            });
+        }
 
         Ok((Some(token1.unwrap()), Some(token2.unwrap())))
     }
@@ -1211,12 +2069,12 @@ impl Parser<'_> {
         Some(curr_tok)
     }
 
-    // Tries to optionally peek *the next* single Token in stream with the provided set of Tokens that are
-    // acceptable via `expected_token`. If it is not immediately found, just return `None`. No big
-    // deal! Note: same as `optional_consume()`, however we do not advance token pos index
-    fn optional_peek_next(&self, valid_tokens: &[TokenKind]) -> Option<Token> {
-        // Fetch next token in stream
-        let curr_tok = self.peek_next().unwrap();
+    // Tries to optionally peek the Token `n` positions ahead in stream with the provided set of
+    // Tokens that are acceptable via `expected_token`. If it is not found (or we run off the end of
+    // the stream), just return `None`. No big deal! Note: we do not advance token pos index
+    fn optional_peek_nth(&self, n: usize, valid_tokens: &[TokenKind]) -> Option<Token> {
+        // Fetch token in stream
+        let curr_tok = self.peek_nth(n)?;
 
         if !valid_tokens.contains(&curr_tok.get_token_kind())
         {
@@ -1233,16 +2091,25 @@ impl Parser<'_> {
         // See if the next token even corresponds to what we expect
         if !valid_tokens.contains(&curr_tok.get_token_kind())
         {
+            let offset = self.report_offset(curr_tok.get_file_index());
+
             // Print fancy compiler error
-            ParserErrorReporter::unexpected_token(
-                &curr_tok.get_token_kind(),
-                valid_tokens.into(),
-                self.path.to_str().unwrap(),
-                self.cleaned_source,
-                curr_tok.get_file_index(),
-            );
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &curr_tok.get_token_kind(),
+                    valid_tokens.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
 
-            return Err(ParserError::ParseFail);
+            return Err(ParserError::UnexpectedToken {
+                found: curr_tok.get_token_kind(),
+                expected: valid_tokens.to_vec(),
+                offset,
+            });
         }
 
         // Accumulate tokens that we consume based on `expected_token`s
@@ -1311,6 +2178,30 @@ impl Parser<'_> {
         // Fetch next token in stream
         let mut curr_tok = self.peek().unwrap();
 
+        // error detected -- A list can not start with a comma (e.g `{ , A }`)
+        if curr_tok.is_a(TokenKind::Sep)
+        {
+            let offset = self.report_offset(curr_tok.get_file_index());
+
+            // Fancy compiler error
+            if self.error_counter.report()
+            {
+                ParserErrorReporter::unexpected_token(
+                    &curr_tok.get_token_kind(),
+                    valid_tokens.into(),
+                    self.path.to_str().unwrap(),
+                    self.report_source(),
+                    offset,
+                );
+            }
+
+            return Err(ParserError::UnexpectedToken {
+                found: curr_tok.get_token_kind(),
+                expected: valid_tokens.to_vec(),
+                offset,
+            });
+        }
+
         // See if the next token even corresponds to what we expect.
         // NOTE: it is OK to not find a match since lists can be empty!
         if !valid_tokens.contains(&curr_tok.get_token_kind())
@@ -1329,6 +2220,12 @@ impl Parser<'_> {
                 self.advance_parser_pos();
                 curr_tok = self.peek().unwrap();
 
+                // A trailing comma is allowed right before the closing bracket (e.g `{ A, B, }`)
+                if curr_tok.is_a(TokenKind::RBracket)
+                {
+                    break;
+                }
+
                 // Next token should not be a separator
                 expected_sep = false;
                 continue;
@@ -1337,26 +2234,47 @@ impl Parser<'_> {
             // error detected -- Missing comma in list
             if !curr_tok.is_a(TokenKind::Sep) && expected_sep
             {
+                let offset = self.report_offset(curr_tok.get_file_index());
+
                 // Fancy compiler error
-                ParserErrorReporter::missing_sep(
-                    &curr_tok.get_token_kind(),
-                    self.path.to_str().unwrap(),
-                    self.cleaned_source,
-                    curr_tok.get_file_index(),
-                );
+                if self.error_counter.report()
+                {
+                    ParserErrorReporter::missing_sep(
+                        &curr_tok.get_token_kind(),
+                        self.path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
+
+                return Err(ParserError::MissingSeparator {
+                    found: curr_tok.get_token_kind(),
+                    offset,
+                });
             }
 
             // error detected -- Erroneous comma found in list
             if curr_tok.is_a(TokenKind::Sep) && !expected_sep
             {
+                let offset = self.report_offset(curr_tok.get_file_index());
+
                 // Fancy compiler error
-                ParserErrorReporter::unexpected_token(
-                    &curr_tok.get_token_kind(),
-                    valid_tokens.into(),
-                    self.path.to_str().unwrap(),
-                    self.cleaned_source,
-                    curr_tok.get_file_index(),
-                );
+                if self.error_counter.report()
+                {
+                    ParserErrorReporter::unexpected_token(
+                        &curr_tok.get_token_kind(),
+                        valid_tokens.into(),
+                        self.path.to_str().unwrap(),
+                        self.report_source(),
+                        offset,
+                    );
+                }
+
+                return Err(ParserError::UnexpectedToken {
+                    found: curr_tok.get_token_kind(),
+                    expected: valid_tokens.to_vec(),
+                    offset,
+                });
             }
 
             consumed_toks.push(curr_tok);
@@ -1410,6 +2328,13 @@ impl Parser<'_> {
                 // Advance to next token in stream to parse next struct field
                 self.increment_parser_pos_by(1);
 
+                // A trailing comma is allowed right before the closing bracket
+                // (e.g `{ a :: int, }`)
+                if self.optional_peek(&[RBracket]).is_some()
+                {
+                    break 'build_ty_idents;
+                }
+
                 continue 'build_ty_idents;
             }
 
@@ -1427,3 +2352,793 @@ impl Parser<'_> {
         Ok(Some(consumed_ty_idents))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lexer::Lexer;
+
+    // Lex `source` and parse it as a whole program
+    fn parse_program_from(source: &str) -> Result<ast::Program, ParserError> {
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source);
+
+        parser.parse(false, &mut SymbolTable::new())
+    }
+
+    // Lex `source` and parse a single expression out of it
+    fn parse_expr_from(source: &str) -> Result<Option<ast::Expression>, ParserError> {
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source);
+
+        parser.parse_expression(0)
+    }
+
+    #[test]
+    fn unclosed_paren_is_rejected() {
+        assert!(parse_expr_from("(1 + 2;").is_err());
+    }
+
+    #[test]
+    fn unopened_paren_is_rejected() {
+        assert!(parse_expr_from("1 + 2);").is_err());
+    }
+
+    #[test]
+    fn too_deeply_nested_expression_is_rejected() {
+        use lexer::diagnostics;
+
+        // `(((1)));` nested `depth` deep. The tokens are repeated instead of lexing the whole
+        // source, which would be slow at this size.
+        let nested = |depth: usize| {
+            let tokens = Lexer::new("test.az", "(1);").lex(false).unwrap();
+            let (l_parn, one, r_parn, rest) = (&tokens[0], &tokens[1], &tokens[2], &tokens[3..]);
+
+            let mut nested = vec![l_parn.clone(); depth];
+            nested.push(one.clone());
+            nested.extend(vec![r_parn.clone(); depth]);
+            nested.extend_from_slice(rest);
+            nested
+        };
+
+        let parse = |tokens: Vec<Token>| {
+            let parser = Parser::new(tokens, Path::new("test.az"), "(1);");
+            diagnostics::collected(|| parser.parse_expression(0))
+        };
+
+        let (result, reported) = parse(nested(10_000));
+        assert!(matches!(result, Err(ParserError::ExpressionTooDeep { .. })));
+        assert_eq!(reported.len(), 1);
+
+        // Right at the limit still parses, even with the stack of a test thread
+        let (result, _) = parse(nested(DEFAULT_MAX_EXPR_DEPTH - 1));
+        assert_eq!(result.unwrap().unwrap().to_string(), "1");
+    }
+
+    #[test]
+    fn too_deeply_nested_blocks_are_rejected() {
+        use lexer::diagnostics;
+
+        let source = "main :: ()\nmain = { if c { } }\n";
+
+        // `if c {` repeated `depth` times inside of `main`, with as many `}` to close them
+        let nested = |depth: usize| {
+            let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+            let (head, if_open, close) = (&tokens[..7], &tokens[7..10], &tokens[10]);
+
+            let mut nested = head.to_vec();
+            for _ in 0..depth
+            {
+                nested.extend_from_slice(if_open);
+            }
+            nested.extend(vec![close.clone(); depth]);
+            nested.extend_from_slice(&tokens[11..]);
+            nested
+        };
+
+        let parse = |tokens: Vec<Token>| {
+            let parser = Parser::new(tokens, Path::new("test.az"), source);
+            diagnostics::collected(|| parser.parse(false, &mut SymbolTable::new()))
+        };
+
+        let (result, reported) = parse(nested(10_000));
+        assert!(matches!(result, Err(ParserError::ExpressionTooDeep { .. })));
+        assert_eq!(reported.len(), 1);
+
+        // Right at the limit still parses, even with the stack of a test thread. The body of
+        // `main` is one more level of nesting.
+        let (result, _) = parse(nested(DEFAULT_MAX_EXPR_DEPTH - 2));
+        let program = result.unwrap();
+        let decls = program.declarations.unwrap();
+        let body = decls[0].as_function().unwrap().1.block();
+        assert_eq!(body.statements().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn deeply_nested_parens_are_balanced() {
+        let expr = parse_expr_from("(((1)));").unwrap().unwrap();
+
+        assert_eq!(expr.to_string(), "1");
+    }
+
+    #[test]
+    fn nested_function_call_args() {
+        let expr = parse_expr_from("f(g(1), (2 + 3));").unwrap().unwrap();
+
+        assert_eq!(expr.to_string(), "(f (g 1) (+ 2 3))");
+    }
+
+    #[test]
+    fn unclosed_function_call_is_rejected() {
+        assert!(parse_expr_from("f(g(1);").is_err());
+    }
+
+    #[test]
+    fn params_typed_inline() {
+        let program = parse_program_from("f :: (int, bool)\nf (x :: int, y) = { }\n").unwrap();
+        let decls = program.declarations.unwrap();
+        let (_signature, definition) = decls[0].as_function().unwrap();
+
+        let params = definition
+            .typed_params()
+            .map(|(param, ty)| (param.get_raw_content(), ty.map(Token::get_raw_content)))
+            .collect::<Vec<_>>();
+        assert_eq!(params, [("x", Some("int")), ("y", None)]);
+
+        assert!(parse_program_from("f :: (int)\nf (x :: ) = { }\n").is_err());
+        assert!(parse_program_from("f :: (int)\nf (x :: int = { }\n").is_err());
+    }
+
+    #[test]
+    fn main_with_inline_block() {
+        let program = parse_program_from("main :: () -> int { 0 }\n").unwrap();
+        let decls = program.declarations.unwrap();
+        let (signature, definition) = decls[0].as_function().unwrap();
+
+        assert!(signature.name().is_a(TokenKind::MainKw));
+        assert!(signature.ty_ret().unwrap().is_a(TokenKind::IntTy));
+        assert!(definition.params().is_none());
+        assert_eq!(definition.block().expression().unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn main_followed_by_declarations() {
+        let source = "main :: () -> int\nmain = { 0 }\nPoint :: structure { x :: int }\n";
+        let decls = parse_program_from(source).unwrap().declarations.unwrap();
+
+        assert!(decls[0].is_function());
+        assert!(decls[1].is_struct());
+    }
+
+    #[test]
+    fn declarations_dispatch_on_lookahead() {
+        let source = "Color :: choice { Red, Blue }\n\
+                      Point :: structure { x :: int }\n\
+                      add_two :: (int, int) -> int\n\
+                      add_two n1 n2 = { n1 + n2 }\n";
+        let decls = parse_program_from(source).unwrap().declarations.unwrap();
+
+        assert_eq!(decls.len(), 3);
+        assert!(decls[0].is_choice());
+        assert!(decls[1].is_struct());
+        assert!(decls[2].is_function());
+        assert_eq!(decls[2].name().get_raw_content(), "add_two");
+    }
+
+    #[test]
+    fn missing_t_qualifier_is_rejected() {
+        assert!(parse_program_from("Point structure { x :: int }\n").is_err());
+        assert!(parse_program_from("main :: () -> int { 0 }\nPoint\n").is_err());
+    }
+
+    #[test]
+    fn peek_nth_before_at_and_past_eof() {
+        let source = "let x;";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source);
+
+        // Before EOF
+        assert!(parser.peek_nth(0).unwrap().is_a(TokenKind::LetKw));
+        assert!(parser.peek_nth(2).unwrap().is_a(TokenKind::Semicolon));
+
+        // At EOF
+        assert!(parser.peek_nth(3).unwrap().is_a(TokenKind::EOF));
+
+        // Past EOF
+        assert!(parser.peek_nth(4).is_none());
+        assert!(parser.peek_nth(usize::MAX).is_none());
+        assert!(parser.optional_peek_nth(4, &[TokenKind::EOF]).is_none());
+
+        // Peeking never moves the parser
+        assert!(parser.peek().unwrap().is_a(TokenKind::LetKw));
+    }
+
+    #[test]
+    fn is_a_at_end_of_stream() {
+        let source = "x;";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source);
+
+        // `x`, `;`, then the `EOF` token
+        parser.increment_parser_pos_by(2);
+        assert!(parser.is_a(TokenKind::EOF));
+
+        // Passed the `EOF` token
+        parser.increment_parser_pos_by(1);
+        assert!(parser.at_end_of_token_stream());
+        assert!(!parser.is_a(TokenKind::EOF));
+        assert!(!parser.is_a(TokenKind::Ident));
+    }
+
+    #[test]
+    fn unexpected_token_as_json() {
+        use lexer::diagnostics::{self, MessageFormat, Severity};
+
+        diagnostics::set_message_format(MessageFormat::Json);
+        let result = parse_program_from("main :: ()\nmain = { let x <- 5 }\n");
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert!(result.is_err());
+
+        let collected = diagnostics::take_diagnostics();
+        assert_eq!(collected.len(), 1);
+
+        let diagnostic = &collected[0];
+        assert_eq!(diagnostic.file, "test.az");
+        assert_eq!((diagnostic.line, diagnostic.col), (2, 21));
+        assert_eq!(diagnostic.code, 0);
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert!(diagnostic.message.starts_with("Unexpected Token (syntax error)"));
+    }
+
+    #[test]
+    fn function_parameters() {
+        use lexer::diagnostics;
+
+        let params_of = |source: &str| {
+            let program = parse_program_from(source).unwrap();
+            let decls = program.declarations.unwrap();
+
+            decls[0].as_function().unwrap().1.params().map(<[Token]>::len)
+        };
+        assert_eq!(params_of("f :: ()\nf = { }\n"), None);
+        assert_eq!(params_of("f :: (int, int)\nf a b = { }\n"), Some(2));
+
+        let source = "f :: (int, int)\nf a 2 = { }\n";
+        let (result, reported) = diagnostics::collected(|| parse_program_from(source));
+        assert!(result.is_err());
+        assert_eq!((reported[0].line, reported[0].col), (2, 5));
+        assert!(reported[0].message.contains("Expected a parameter name of `f` or `=`"));
+    }
+
+    #[test]
+    fn struct_field_missing_type_is_named() {
+        use lexer::diagnostics;
+
+        let source = "Person :: structure { age :: , name :: text }\n";
+        let (result, reported) = diagnostics::collected(|| parse_program_from(source));
+
+        assert!(result.is_err());
+        assert!(reported[0].message.contains("Expected a type for field `age`, but found `,`"));
+    }
+
+    #[test]
+    fn reports_name_things_as_written() {
+        let report = |source: &str| diagnostics::collected(|| parse_program_from(source)).1;
+
+        let reported = report("main :: () -> int\nmain = { let x <- ; 0 }\n");
+        assert!(reported[0].message.contains("`x` is missing expression after `<-`"));
+
+        let reported = report("main :: () -> int\nmain = { while ; 0 }\n");
+        assert!(reported[0].message.contains("Missing bool expression at while-loop"));
+    }
+
+    #[test]
+    fn parse_failures_say_what_went_wrong() {
+        use lexer::diagnostics;
+
+        let expr_error = |source: &str| diagnostics::collected(|| parse_expr_from(source)).0;
+        let program_error = |source: &str| diagnostics::collected(|| parse_program_from(source)).0;
+
+        assert!(matches!(
+            expr_error("(1 + 2;"),
+            Err(ParserError::UnbalancedParen { paren: TokenKind::LParn, offset: 0 })
+        ));
+        assert!(matches!(expr_error("a < b < c;"), Err(ParserError::ChainedComparison { .. })));
+        assert!(matches!(
+            program_error("P :: structure { age :: , name :: text }\n"),
+            Err(ParserError::MissingType { offset: 24 })
+        ));
+        assert!(matches!(
+            program_error("C :: choice { A B }\n"),
+            Err(ParserError::MissingSeparator { found: TokenKind::Ident, .. })
+        ));
+        assert_eq!(
+            program_error("main :: () -> int\nmain = { let x <- ; 0 }\n").unwrap_err(),
+            ParserError::MissingRhs { name: "x".to_string(), offset: 31 }
+        );
+        assert_eq!(
+            program_error("main :: () -> int\nmain = { while ; 0 }\n").unwrap_err(),
+            ParserError::MissingExpression { at: "while-loop".to_string(), offset: 27 }
+        );
+
+        let Err(ParserError::UnexpectedToken { found, expected, offset }) =
+            program_error("f :: ()\nf 1 = {}\n")
+        else
+        {
+            panic!("expected an unexpected token");
+        };
+        assert_eq!(found, TokenKind::NumLit);
+        assert_eq!(expected, [TokenKind::Ident, TokenKind::FnDef]);
+        assert_eq!(offset, 10);
+    }
+
+    #[test]
+    fn struct_fields_with_trailing_comma() {
+        let source = "P :: structure { a :: int, b :: int, }\n";
+        let decls = parse_program_from(source).unwrap().declarations.unwrap();
+
+        let ast::Declaration::Struct { typed_fields, .. } = &decls[0]
+        else
+        {
+            panic!("expected a structure declaration");
+        };
+        assert_eq!(typed_fields.as_ref().unwrap().len(), 2);
+
+        // Without the trailing comma too
+        assert!(parse_program_from("P :: structure { a :: int, b :: int }\n").is_ok());
+    }
+
+    #[test]
+    fn choice_variants_with_trailing_comma() {
+        let source = "Color :: choice { Red, Blue, }\n";
+        let decls = parse_program_from(source).unwrap().declarations.unwrap();
+
+        let ast::Declaration::Choice { variants, .. } = &decls[0]
+        else
+        {
+            panic!("expected a choice declaration");
+        };
+        assert_eq!(variants.as_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn lone_comma_in_struct_is_rejected() {
+        assert!(parse_program_from("P :: structure { , }\n").is_err());
+        assert!(parse_program_from("P :: structure { a :: int,, }\n").is_err());
+    }
+
+    #[test]
+    fn choice_variant_lists() {
+        let variants_of = |source: &str| {
+            let decls = parse_program_from(source).unwrap().declarations.unwrap();
+            let ast::Declaration::Choice { variants, .. } = &decls[0]
+            else
+            {
+                panic!("expected a choice declaration");
+            };
+
+            variants.as_ref().map(|variants| variants.len())
+        };
+
+        assert_eq!(variants_of("Empty :: choice { }\n"), None);
+        assert_eq!(variants_of("Single :: choice { A }\n"), Some(1));
+    }
+
+    #[test]
+    fn bad_commas_in_choice_are_rejected() {
+        // Leading comma
+        assert!(parse_program_from("Bad :: choice { , A }\n").is_err());
+
+        // Doubled comma
+        assert!(parse_program_from("Bad :: choice { A,, B }\n").is_err());
+
+        // Missing comma
+        assert!(parse_program_from("Bad :: choice { A B }\n").is_err());
+    }
+
+    #[test]
+    fn expression_span_covers_children() {
+        let expr = parse_expr_from("a + b;").unwrap().unwrap();
+        assert_eq!(expr.to_string(), "(+ a b)");
+        assert_eq!(expr.span(), 0..5);
+
+        let expr = parse_expr_from("-(foo * 20);").unwrap().unwrap();
+        assert_eq!(expr.span(), 0..10);
+
+        let expr = parse_expr_from("f(a, bb);").unwrap().unwrap();
+        assert_eq!(expr.span(), 0..7);
+    }
+
+    // Parse `source` as an expression and print its S-Expression
+    fn sexpr_of(source: &str) -> String {
+        parse_expr_from(source).unwrap().unwrap().to_string()
+    }
+
+    #[test]
+    fn arithmetic_precedence() {
+        assert_eq!(sexpr_of("1 + 2 * 3;"), "(+ 1 (* 2 3))");
+        assert_eq!(sexpr_of("1 * 2 + 3;"), "(+ (* 1 2) 3)");
+        assert_eq!(sexpr_of("1 - 2 - 3;"), "(- (- 1 2) 3)");
+        assert_eq!(sexpr_of("8 / 4 / 2;"), "(/ (/ 8 4) 2)");
+        assert_eq!(sexpr_of("(1 + 2) * 3;"), "(* (+ 1 2) 3)");
+    }
+
+    #[test]
+    fn logic_and_comparison_precedence() {
+        assert_eq!(sexpr_of("a or b and c;"), "(or a (and b c))");
+        assert_eq!(sexpr_of("a and b or c;"), "(or (and a b) c)");
+        assert_eq!(sexpr_of("a < b + 1 and c;"), "(and (< a (+ b 1)) c)");
+        assert_eq!(sexpr_of("a + 1 == b * 2;"), "(== (+ a 1) (* b 2))");
+    }
+
+    #[test]
+    fn negation_precedence() {
+        assert_eq!(sexpr_of("-a * b;"), "(* (- a) b)");
+        assert_eq!(sexpr_of("-a + b;"), "(+ (- a) b)");
+        assert_eq!(sexpr_of("a - -b;"), "(- a (- b))");
+        assert_eq!(sexpr_of("-f(1);"), "(- (f 1))");
+    }
+
+    #[test]
+    fn call_on_dotted_name() {
+        assert_eq!(sexpr_of("p.norm();"), "(call (. p norm))");
+        assert_eq!(sexpr_of("m.f(1, 2);"), "(call (. m f) 1 2)");
+        assert_eq!(sexpr_of("a.b.c(1) + 2;"), "(+ (call (. (. a b) c) 1) 2)");
+        assert_eq!(sexpr_of("-p.norm();"), "(- (call (. p norm)))");
+        assert!(parse_expr_from("(1 + 2)(3);").is_err());
+    }
+
+    #[test]
+    fn cast_precedence() {
+        assert_eq!(sexpr_of("1 + 2 as float;"), "(+ 1 (as 2 float))");
+        assert_eq!(sexpr_of("-1 as float;"), "(- (as 1 float))");
+        assert_eq!(sexpr_of("1 as int as float;"), "(as (as 1 int) float)");
+        assert_eq!(sexpr_of("a * b as float;"), "(* a (as b float))");
+        assert_eq!(sexpr_of("(-1) as float;"), "(as (- 1) float)");
+    }
+
+    #[test]
+    fn chained_comparison_is_rejected() {
+        assert!(parse_expr_from("a < b < c;").is_err());
+        assert!(parse_expr_from("a == b < c;").is_err());
+    }
+
+    #[test]
+    fn separate_or_parenthesized_comparisons_are_accepted() {
+        assert_eq!(sexpr_of("(a < b) and (b < c);"), "(and (< a b) (< b c))");
+        assert_eq!(sexpr_of("(a < b) == c;"), "(== (< a b) c)");
+        assert_eq!(sexpr_of("a == (b < c);"), "(== a (< b c))");
+    }
+
+    #[test]
+    fn literals_are_classified() {
+        let Some(ast::Expression::Literal(_, value)) = parse_expr_from("5;").unwrap()
+        else
+        {
+            panic!("`5` should be a literal");
+        };
+        assert_eq!(value, ast::LitValue::Int(5));
+
+        let Some(ast::Expression::Literal(_, value)) = parse_expr_from("true;").unwrap()
+        else
+        {
+            panic!("`true` should be a literal");
+        };
+        assert_eq!(value, ast::LitValue::Bool(true));
+
+        let Some(ast::Expression::Literal(_, value)) = parse_expr_from("'\\n';").unwrap()
+        else
+        {
+            panic!("`'\\n'` should be a literal");
+        };
+        assert_eq!(value, ast::LitValue::Char('\n'));
+    }
+
+    #[test]
+    fn identifiers_stay_atoms() {
+        let expr = parse_expr_from("x;").unwrap().unwrap();
+
+        assert!(matches!(expr, ast::Expression::Atom(_)));
+    }
+
+    #[test]
+    fn function_typed_param() {
+        let program = parse_program_from(
+            "apply :: ((int) -> int, int) -> int\napply f x = { 0 + f(x) }\n",
+        )
+        .unwrap();
+        let declarations = program.declarations.unwrap();
+        let (signature, _) = declarations[0].as_function().unwrap();
+
+        let ty_list = signature.ty_list().unwrap();
+        assert_eq!(ty_list.len(), 2);
+        assert!(ty_list[1].as_prim().unwrap().is_a(TokenKind::IntTy));
+
+        let func_ty = ty_list[0].as_func().unwrap();
+        assert!(func_ty.ty_list().unwrap()[0].as_prim().unwrap().is_a(TokenKind::IntTy));
+        assert!(func_ty.ty_ret().unwrap().is_a(TokenKind::IntTy));
+    }
+
+    #[test]
+    fn malformed_function_type_is_rejected() {
+        // Missing return type after `->`
+        let source = "apply :: ((int) ->, int) -> int\napply f x = { x }\n";
+        assert!(parse_program_from(source).is_err());
+
+        // Missing comma between input types
+        let source = "apply :: ((int) -> int int) -> int\napply f x = { x }\n";
+        assert!(parse_program_from(source).is_err());
+
+        // Unclosed function type
+        let source = "apply :: ((int -> int) -> int\napply f = { 0 }\n";
+        assert!(parse_program_from(source).is_err());
+
+        // Trailing comma after the input types
+        let source = "apply :: ((int) -> int, int,) -> int\napply f x = { x }\n";
+        assert!(parse_program_from(source).is_err());
+    }
+
+    #[test]
+    fn two_arm_match() {
+        let source = "main :: ()\nmain = { match color { Red => { x <- 1; }, Blue => { x <- 2; } } }\n";
+        let program = parse_program_from(source).unwrap();
+        let declarations = program.declarations.unwrap();
+        let (_, definition) = declarations[0].as_function().unwrap();
+        let stmts = definition.block().statements().unwrap();
+
+        let ast::Statement::Match { scrutinee, arms } = &stmts[0]
+        else
+        {
+            panic!("Expected a `match` statement");
+        };
+        assert_eq!(scrutinee.to_string(), "color");
+
+        let variants: Vec<_> = arms.iter().map(|(variant, _)| variant.get_raw_content()).collect();
+        assert_eq!(variants, ["Red", "Blue"]);
+        assert_eq!(stmts[0].blocks().len(), 2);
+    }
+
+    #[test]
+    fn match_arm_missing_arrow_is_rejected() {
+        let source = "main :: ()\nmain = { match color { Red { x <- 1; } } }\n";
+
+        assert!(parse_program_from(source).is_err());
+    }
+
+    #[test]
+    fn merge_programs_keeps_origins() {
+        let parse_file = |path: &str, source: &str| {
+            let tokens = Lexer::new(path, source).lex(false).unwrap();
+
+            Parser::new(tokens, Path::new(path), source)
+                .parse(false, &mut SymbolTable::new())
+                .unwrap()
+        };
+        let a = parse_file("a.az", "main :: () -> int { 0 }\n");
+        let b = parse_file("b.az", "helper :: () -> int { 1 }\n");
+
+        let merged = ast::Program::merge(vec![a, b]);
+        let declarations = merged.declarations.as_ref().unwrap();
+
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[1].name().get_raw_content(), "helper");
+        assert_eq!(merged.origin_of(0), Some(Path::new("a.az")));
+        assert_eq!(merged.origin_of(1), Some(Path::new("b.az")));
+    }
+
+    #[test]
+    fn duplicate_function_across_files() {
+        // Both files share one `SymbolTable`
+        let mut sym_table = SymbolTable::new();
+        let files = [("a.az", "dup :: () -> int { 0 }\n"), ("b.az", "dup :: () -> int { 1 }\n")];
+        for (path, source) in files
+        {
+            let tokens = Lexer::new(path, source).lex(false).unwrap();
+            Parser::new(tokens, Path::new(path), source).parse(false, &mut sym_table).unwrap();
+        }
+
+        let source = "dup :: () -> int { 1 }\n";
+        let result = symbol_table::check_for_dup_funcs_syms(&sym_table, Path::new("b.az"), source);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn declaration_spans_cover_their_source() {
+        let add = "add :: (int, int) -> int\nadd x y = { x + y }";
+        let main = "main :: () -> int { add(1, 2) }";
+        let point = "Point :: structure { x :: int }";
+        let source = format!("{add}\n\n{main}\n{point}\n");
+        let program = parse_program_from(&source).unwrap();
+
+        let spans = program
+            .declarations
+            .unwrap()
+            .iter()
+            .map(|decl| decl.span())
+            .collect::<Vec<_>>();
+
+        // Everything is ASCII, so char offsets are byte offsets
+        assert_eq!(&source[spans[0].clone()], add);
+        assert_eq!(&source[spans[1].clone()], main);
+        assert_eq!(&source[spans[2].clone()], point);
+        assert!(spans[0].end < spans[1].start);
+    }
+
+    #[test]
+    fn definitions_are_indexed_while_parsing() {
+        let source = "add :: (int, int) -> int\nadd x y = { x + y }\n\
+                      main :: () -> int { add(1, 2) }\n";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let mut sym_table = SymbolTable::new();
+        Parser::new(tokens, Path::new("test.az"), source).parse(false, &mut sym_table).unwrap();
+
+        let definitions = sym_table.definitions();
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions.location_of("add"), Some(0..3));
+        assert_eq!(definitions.location_of("main"), Some(45..49));
+
+        // Bindings and parameters are not top level definitions
+        assert_eq!(definitions.location_of("x"), None);
+    }
+
+    #[test]
+    fn ast_dump_round_trips() {
+        let source = "Point :: structure { x :: int }\n\
+                      Color :: choice { Red, Blue }\n\
+                      add :: (int, int) -> int\n\
+                      add x y = { let z <- x * 2; z + y }\n";
+        let program = parse_program_from(source).unwrap();
+
+        let dump = serde_json::to_string(&program).unwrap();
+        let loaded: ast::Program = serde_json::from_str(&dump).unwrap();
+
+        assert_eq!(loaded, program);
+    }
+
+    #[test]
+    fn if_expression_in_binding() {
+        let source = "main :: () -> int\n\
+                      main = { let x <- if a < 2 { let y <- 1; y } else { 2 }; x }\n";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let mut sym_table = SymbolTable::new();
+        let program = Parser::new(tokens, Path::new("test.az"), source)
+            .parse(false, &mut sym_table)
+            .unwrap();
+
+        let decls = program.declarations.unwrap();
+        let (_, definition) = decls[0].as_function().unwrap();
+        let stmts = definition.block().statements().unwrap();
+        let ast::Statement::VarBindingInit { rhs: ast::RValue::Expr(Some(rhs)), .. } = &stmts[0]
+        else
+        {
+            panic!("expected a binding");
+        };
+
+        assert_eq!(rhs.to_string(), "(if (< a 2) { ... y } { 2 })");
+
+        // `y` is bound inside of the `if`
+        assert!(sym_table.iter().any(|node| node.name().get_raw_content() == "y"));
+    }
+
+    #[test]
+    fn if_expression_needs_else() {
+        let source = "main :: ()\nmain = { let x <- if a { 1 }; }\n";
+
+        assert!(parse_program_from(source).is_err());
+    }
+
+    #[test]
+    fn block_expression_in_binding() {
+        let source = "main :: () -> int\nmain = { let x <- { let t <- 1; t + 1 }; x }\n";
+        let program = parse_program_from(source).unwrap();
+
+        let decls = program.declarations.unwrap();
+        let (_, definition) = decls[0].as_function().unwrap();
+        let stmts = definition.block().statements().unwrap();
+        let ast::Statement::VarBindingInit { rhs: ast::RValue::Expr(Some(rhs)), .. } = &stmts[0]
+        else
+        {
+            panic!("expected a binding");
+        };
+
+        assert!(matches!(rhs, ast::Expression::Block(..)));
+        assert_eq!(rhs.to_string(), "{ ... (+ t 1) }");
+        assert_eq!(&source[rhs.span()], "{ let t <- 1; t + 1");
+    }
+
+    #[test]
+    fn expression_statement_before_block_value() {
+        let source = "f :: () -> int\nf = { compute(x) + 1; -y * 2; print(x); x + 1 }\n";
+        let program = parse_program_from(source).unwrap();
+
+        let decls = program.declarations.unwrap();
+        let (_, definition) = decls[0].as_function().unwrap();
+        let block = definition.block();
+        let stmts = block.statements().unwrap();
+
+        let exprs = stmts
+            .iter()
+            .filter_map(|stmt| match stmt
+            {
+                ast::Statement::Expr(expr) => Some(expr.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(exprs, ["(+ (compute x) 1)", "(* (- y) 2)"]);
+
+        // A call on its own is still a call statement, and the last expression is the value
+        assert!(matches!(&stmts[2], ast::Statement::FuncCall { .. }));
+        assert_eq!(block.expression().unwrap().to_string(), "(+ x 1)");
+    }
+
+    #[test]
+    fn trailing_expression_is_value_or_statement() {
+        let block_of = |body: &str| {
+            let source = format!("f :: () -> int\nf = {body}\n");
+            let program = parse_program_from(&source).unwrap();
+
+            let decls = program.declarations.unwrap();
+            decls[0].as_function().unwrap().1.block().clone()
+        };
+
+        // Followed by `}`, so `g(y)` is the value
+        let block = block_of("{ f(x); g(y) }");
+        assert_eq!(block.statements().unwrap().len(), 1);
+        assert_eq!(block.expression().unwrap().to_string(), "(g y)");
+
+        // Followed by `;`, so there is no value
+        let block = block_of("{ f(x); }");
+        assert_eq!(block.statements().unwrap().len(), 1);
+        assert!(block.expression().is_none());
+
+        // An expression must be followed by one or the other
+        assert!(parse_program_from("f :: () -> int\nf = { f(x) g(y) }\n").is_err());
+    }
+
+    #[test]
+    fn missing_semicolon_is_recovered_from() {
+        let source = "f :: () -> int\nf = { let x <- 1 }\ng :: () -> int\ng = { 2 }\n";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source).with_collected_errors();
+
+        // A `;` was inserted after `1`, so `f` is kept along with `g`
+        let (program, errors) = parser.parse_recovered(false, &mut SymbolTable::new());
+        let declarations = program.unwrap().declarations.unwrap();
+        assert_eq!(declarations.len(), 2);
+
+        let (_, f) = declarations[0].as_function().unwrap();
+        let statements = f.block().statements().unwrap();
+        assert!(matches!(&statements[0], ast::Statement::VarBindingInit { bind_name, .. }
+            if bind_name.get_raw_content() == "x"));
+
+        // The missing `;` is still an error, reported at what was found instead
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].offset, source.find("}").unwrap());
+
+        // So a plain parse fails, even though it recovered
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source).with_collected_errors();
+        assert_eq!(parser.parse(false, &mut SymbolTable::new()), Err(ParserError::ParseFail));
+
+        // Without collecting errors, nothing is inserted
+        assert!(diagnostics::collected(|| parse_program_from(source)).0.is_err());
+    }
+
+    #[test]
+    fn collected_errors_have_offsets() {
+        let source = "f :: () -> int\nf = { let x 5; x }\n\
+                      g :: () -> int\ng = { 1 }\n\
+                      h :: () -> int\nh = { let y <- 1 y }\n";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let parser = Parser::new(tokens, Path::new("test.az"), source).with_collected_errors();
+
+        assert!(parser.parse(false, &mut SymbolTable::new()).is_err());
+
+        // One error in `f` and one in `h`, with `g` parsed fine in between
+        let errors = parser.into_errors();
+        let offsets = errors.iter().map(|error| error.offset).collect::<Vec<_>>();
+        assert_eq!(offsets, [source.find("5;").unwrap(), source.find("y }").unwrap()]);
+        assert_eq!((errors[1].line, errors[1].col), (6, 18));
+    }
+}
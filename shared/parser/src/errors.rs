@@ -3,20 +3,79 @@
 //! NOTE: The formal grammar is defined in the `grammar/` directory inside the file
 //! `formal_grammar.pest`.
 
-use ariadne::{Label, Report, ReportKind, Source};
+use lexer::diagnostics::report_error;
 use thiserror::Error;
 use lexer::token::{Token, TokenKind};
 
-// `ParserError` represents a general failure to parse a Azalea program
-#[derive(Debug, Error)]
+// `ParserError` represents a failure to parse a Azalea program. Every variant but `ParseFail`
+// says what went wrong, so callers can match on it instead of reading the printed report.
+//
+// NOTE: `offset` is the char offset of the error in the original source file, which is where the
+// report points.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum ParserError {
     #[error("Failed to parse Azalea program.")]
     ParseFail,
+
+    #[error("Unexpected token `{found}`, expected one of {}", fmt_token_kinds(expected))]
+    UnexpectedToken { found: TokenKind, expected: Vec<TokenKind>, offset: usize },
+
+    #[error("Missing type")]
+    MissingType { offset: usize },
+
+    #[error("Binding `{name}` is missing an expression after `<-`")]
+    MissingRhs { name: String, offset: usize },
+
+    #[error("Missing expression at {at}")]
+    MissingExpression { at: String, offset: usize },
+
+    #[error("`{found}` was unexpected, expected a comma `,`")]
+    MissingSeparator { found: TokenKind, offset: usize },
+
+    #[error("Unbalanced `{paren}`")]
+    UnbalancedParen { paren: TokenKind, offset: usize },
+
+    #[error("Comparisons cannot be chained")]
+    ChainedComparison { offset: usize },
+
+    #[error("An `if` used as a value is missing its `else` branch")]
+    MissingElse { offset: usize },
+
+    #[error("Expression or block is nested too deeply")]
+    ExpressionTooDeep { offset: usize },
+}
+
+impl ParserError {
+    /// Char offset of the error in the original source file, or `None` for `ParseFail`
+    pub fn offset(&self) -> Option<usize> {
+        match self
+        {
+            ParserError::ParseFail => None,
+            ParserError::UnexpectedToken { offset, .. }
+            | ParserError::MissingType { offset }
+            | ParserError::MissingRhs { offset, .. }
+            | ParserError::MissingExpression { offset, .. }
+            | ParserError::MissingSeparator { offset, .. }
+            | ParserError::UnbalancedParen { offset, .. }
+            | ParserError::ChainedComparison { offset }
+            | ParserError::MissingElse { offset }
+            | ParserError::ExpressionTooDeep { offset } => Some(*offset),
+        }
+    }
 }
 
 // `ParserErrorReporter` helps with reporting pretty compiler errors for parsing stage
 pub struct ParserErrorReporter;
 
+// Helper to print a list of `TokenKind`s as they are written in source (e.g "`(`, `)`")
+fn fmt_token_kinds(kinds: &[TokenKind]) -> String {
+    kinds
+        .iter()
+        .map(|kind| format!("`{kind}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 // Specific error report handlers
 impl ParserErrorReporter {
     // Error example: `let x <- 5!`
@@ -28,21 +87,11 @@ impl ParserErrorReporter {
         offset: usize,
     ) {
         let note = format!(
-            "`{0:?}` is an unexpected token. Expected: `{1:?}`",
-            unexpected, expected_toks
+            "`{0}` is an unexpected token. Expected: {1}",
+            unexpected,
+            fmt_token_kinds(expected_toks)
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Unexpected Token (syntax error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(0, "Unexpected Token (syntax error)", &note, path, source, offset..offset);
     }
     
     pub fn missing_expr_at<'a>(
@@ -52,21 +101,10 @@ impl ParserErrorReporter {
         offset: usize,
     ) {
         let note = format!(
-            "Missing bool expression at {:?}",
+            "Missing bool expression at {}",
             at 
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Missing Expression (syntax error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(0, "Missing Expression (syntax error)", &note, path, source, offset..offset);
     }
 
 
@@ -79,41 +117,60 @@ impl ParserErrorReporter {
         offset: usize,
     ) {
         let note = format!(
-            "`{0:?}` expected `{1:?}`, but no type was given",
-            unexpected, expected_toks
+            "`{0}` expected {1}, but no type was given",
+            unexpected,
+            fmt_token_kinds(expected_toks)
+        );
+        report_error(0, "Missing Type (syntax error)", &note, path, source, offset..offset);
+    }
+
+    // Error example: `Person :: structure { age :: , name :: text }`
+    pub fn expected_type_for(
+        needs_ty: &str,
+        unexpected: &TokenKind,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!("Expected a type for {needs_ty}, but found `{unexpected}`");
+        report_error(0, "Missing Type (syntax error)", &note, path, source, offset..offset);
+    }
+
+    // Error example: `add_two a 2 = { a }`
+    pub fn expected_param_or_def(
+        unexpected: &TokenKind,
+        func_name: &str,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!(
+            "`{unexpected}` was unexpected. Expected a parameter name of `{func_name}` or `=`"
+        );
+        report_error(
+            0,
+            "Unexpected Token In Parameters (syntax error)",
+            &note,
+            path,
+            source,
+            offset..offset,
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Missing Type (syntax error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
     }
 
     // Error example: `add_two :: (int int) -> int`
     pub fn missing_sep<'a>(unexpected: &TokenKind, path: &str, source: &str, offset: usize) {
         let note = format!(
-            "`{0:?}` was unexpected. Expected to see a comma `,`",
+            "`{0}` was unexpected. Expected to see a comma `,`",
             unexpected
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Missing Comma In List (syntax error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(
+            0,
+            "Missing Comma In List (syntax error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
     }
     
     // Error example: `let x <- ;`
@@ -124,38 +181,88 @@ impl ParserErrorReporter {
         offset: usize,
     ) {
         let note = format!(
-            "`{0:?}` is missing expression after `<-`.",
+            "`{0}` is missing expression after `<-`.",
             var_bind_name.get_raw_content()
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Binding Incomplete (syntax error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(0, "Binding Incomplete (syntax error)", &note, path, source, offset..offset);
+    }
+
+    // Error example: `let x <- (1 + 2;` or `let x <- 1 + 2);`
+    pub fn unbalanced_paren(unmatched: &TokenKind, path: &str, source: &str, offset: usize) {
+        let note = if *unmatched == TokenKind::LParn
+        {
+            "This `(` is never closed. Expected a matching `)`".to_string()
+        }
+        else
+        {
+            "This `)` has no matching `(`".to_string()
+        };
+        report_error(
+            0,
+            "Unbalanced Parentheses (syntax error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `let x <- a < b < c;`
+    pub fn chained_comparison(
+        first: TokenKind,
+        second: TokenKind,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!(
+            "Comparisons cannot be chained. Try `(a {first} b) and (b {second} c)` instead, or \
+             add parentheses if comparing the result is intended"
+        );
+        report_error(
+            0,
+            "Chained Comparison (syntax error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `let x <- if a { 1 };`
+    pub fn if_value_missing_else(path: &str, source: &str, offset: usize) {
+        let note = "An `if` used as a value needs an `else` branch, so it has a value either way";
+        report_error(
+            0,
+            "Missing Else Branch (syntax error)",
+            note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `let x <- ((((...1...))));` with more `(` than `max_depth`, or as many nested
+    // `if` bodies
+    pub fn expression_too_deep(max_depth: usize, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "Expressions and blocks can be nested at most {max_depth} deep. Try splitting this \
+             one up with `let` bindings or functions"
+        );
+        report_error(
+            0,
+            "Nested Too Deep (syntax error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
     }
 
     // Error example: `let x <- 5 + ;`
     pub fn incomplete_binary_op<'a>(path: &str, source: &str, offset: usize) {
         let note = format!("`Binary Operation is incomplete (syntax error)");
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Unexpected Token (syntax error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(0, "Unexpected Token (syntax error)", &note, path, source, offset..offset);
     }
 }
 
@@ -0,0 +1,165 @@
+//! Expression evaluator for the Morehead Azalea Compiler
+//!
+//! Evaluates arithmetic, comparison and boolean expressions over literals and bound names, the
+//! same operations `opt::const_fold` folds. Anything else (e.g calls, blocks or `if`) has no value
+//! here.
+//!
+//! NOTE: `eval` recurses once per level of nesting, like the rest of the compiler. The parser
+//! limits how deep expressions go, but trees built some other way can be deeper, so
+//! `eval_iterative` keeps its own work stack instead and never overflows the native one.
+
+use lexer::token::{Token, TokenKind};
+
+use crate::ast::{Expression, LitValue};
+use crate::opt::{fold_binary, fold_unary};
+
+/// Values of the names an expression may use. Later bindings shadow earlier ones.
+pub type Env = [(String, LitValue)];
+
+/// Evaluate `expr`, recursing into its operands
+///
+/// # Returns
+///
+/// Returns `None` if `expr` uses a name not bound in `env`, something other than an operator
+/// (e.g a call), or an operation that would fail at runtime (e.g division by zero).
+pub fn eval(expr: &Expression, env: &Env) -> Option<LitValue> {
+    match expr
+    {
+        Expression::Literal(_, value) => Some(value.clone()),
+        Expression::Atom(tok) => lookup(tok, env),
+        Expression::Cons(op, operands) => match operands.as_slice()
+        {
+            [operand] => fold_unary(op, &eval(operand, env)?),
+            [lhs, rhs] => fold_binary(op, &eval(lhs, env)?, &eval(rhs, env)?),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Work left to do by `eval_iterative`
+enum Work<'expr> {
+    // Evaluate an expression, leaving its value on the value stack
+    Visit(&'expr Expression),
+
+    // Apply an operator to the values of its operands, which are on top of the value stack
+    Apply(&'expr Token, usize),
+}
+
+/// Same as `eval`, but with an explicit work stack instead of recursion, so a tree of any depth
+/// can be evaluated
+pub fn eval_iterative(expr: &Expression, env: &Env) -> Option<LitValue> {
+    let mut work = vec![Work::Visit(expr)];
+    let mut values = Vec::new();
+
+    while let Some(next) = work.pop()
+    {
+        match next
+        {
+            Work::Visit(Expression::Literal(_, value)) => values.push(value.clone()),
+            Work::Visit(Expression::Atom(tok)) => values.push(lookup(tok, env)?),
+            Work::Visit(Expression::Cons(op, operands)) if matches!(operands.len(), 1 | 2) =>
+            {
+                // Operands are popped off in order, so their values end up in order too
+                work.push(Work::Apply(op, operands.len()));
+                work.extend(operands.iter().rev().map(Work::Visit));
+            }
+            Work::Visit(_) => return None,
+            Work::Apply(op, 1) =>
+            {
+                let operand = values.pop()?;
+                values.push(fold_unary(op, &operand)?);
+            }
+            Work::Apply(op, _) =>
+            {
+                let rhs = values.pop()?;
+                let lhs = values.pop()?;
+                values.push(fold_binary(op, &lhs, &rhs)?);
+            }
+        }
+    }
+
+    values.pop()
+}
+
+// Value bound to the name `tok` in `env`
+fn lookup(tok: &Token, env: &Env) -> Option<LitValue> {
+    if !tok.is_a(TokenKind::Ident)
+    {
+        return None;
+    }
+
+    env.iter()
+        .rev()
+        .find(|(name, _)| name == tok.get_raw_content())
+        .map(|(_, value)| value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use lexer::lexer::Lexer;
+    use symbol_table::SymbolTable;
+
+    use crate::ast_parser::Parser;
+
+    // Parse `expr` as the body of `f x y`
+    fn parse(expr: &str) -> Expression {
+        let source = format!("f :: (int, int) -> int\nf x y = {{ {expr} }}\n");
+        let tokens = Lexer::new("test.az", &source).lex(false).unwrap();
+        let program = Parser::new(tokens, Path::new("test.az"), &source)
+            .parse(false, &mut SymbolTable::new())
+            .unwrap();
+
+        let declarations = program.declarations.unwrap();
+        let (_, definition) = declarations[0].as_function().unwrap();
+        definition.block().expression().unwrap().clone()
+    }
+
+    #[test]
+    fn iterative_matches_recursive() {
+        let env = [("x".to_string(), LitValue::Int(3)), ("y".to_string(), LitValue::Int(-2))];
+
+        // Nested a few dozen levels deep
+        let mut deep = "x".to_string();
+        for i in 0..30
+        {
+            deep = format!("({deep} + {i}) * 2 - -y");
+        }
+
+        for source in [deep.as_str(), "x * (y - 1) < 0 and true", "x + z", "x / (y + 2)", "f(x)"]
+        {
+            let expr = parse(source);
+
+            assert_eq!(eval_iterative(&expr, &env), eval(&expr, &env), "{source}");
+        }
+        assert_eq!(eval(&parse("x * (y - 1) < 0 and true"), &env), Some(LitValue::Bool(true)));
+    }
+
+    #[test]
+    fn iterative_handles_very_deep_trees() {
+        let Expression::Cons(minus, _) = parse("-1")
+        else
+        {
+            panic!("expected a negation");
+        };
+
+        // Far deeper than the parser allows, and too deep to recurse through
+        let mut expr = Expression::Literal(minus.clone(), LitValue::Int(1));
+        for _ in 0..100_000
+        {
+            expr = Expression::Cons(minus.clone(), vec![expr]);
+        }
+
+        assert_eq!(eval_iterative(&expr, &[]), Some(LitValue::Int(1)));
+
+        // Dropping the tree in one go recurses too, so take it apart a node at a time
+        while let Expression::Cons(_, mut operands) = expr
+        {
+            expr = operands.pop().unwrap();
+        }
+    }
+}
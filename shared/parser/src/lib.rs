@@ -6,11 +6,67 @@
 pub mod errors;
 pub mod ast;
 pub mod ast_parser;
+pub mod eval;
+pub mod opt;
+pub mod pretty;
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use ast::{Block, Expression, FuncDefinition, FuncSignature, ParamTy, Statement};
+    use lexer::token::{Token, TokenKind};
+
     #[test]
     fn it_works() {}
+
+    #[test]
+    fn empty_block() {
+        let block = Block::new(None, None);
+        assert!(block.is_empty());
+        assert!(block.statements().is_none());
+        assert!(block.expression().is_none());
+
+        let block = Block::new(Some(Vec::new()), None);
+        assert!(block.is_empty());
+    }
+
+    #[test]
+    fn block_accessors() {
+        let stmt = Statement::new_var_binding_mut(
+            Token::dummy("x", TokenKind::Ident),
+            Expression::new_atom(Token::dummy("1", TokenKind::NumLit)),
+        );
+        let expr = Expression::new_atom(Token::dummy("x", TokenKind::Ident));
+        let block = Block::new(Some(vec![stmt]), Some(expr));
+
+        assert!(!block.is_empty());
+        assert_eq!(block.statements().unwrap().len(), 1);
+        assert_eq!(block.expression().unwrap().to_string(), "x");
+    }
+
+    #[test]
+    fn func_accessors() {
+        let sig = FuncSignature::new(
+            Token::dummy("add_two", TokenKind::Ident),
+            Some(vec![
+                ParamTy::Prim(Token::dummy("int", TokenKind::IntTy)),
+                ParamTy::Prim(Token::dummy("int", TokenKind::IntTy)),
+            ]),
+            Some(Token::dummy("int", TokenKind::IntTy)),
+        );
+        assert_eq!(sig.name().get_raw_content(), "add_two");
+        assert_eq!(sig.ty_list().unwrap().len(), 2);
+        assert!(sig.ty_ret().unwrap().is_a(TokenKind::IntTy));
+
+        let def = FuncDefinition::new(
+            Token::dummy("add_two", TokenKind::Ident),
+            Some(vec![Token::dummy("n1", TokenKind::Ident), Token::dummy("n2", TokenKind::Ident)]),
+            Block::new(None, Some(Expression::new_atom(Token::dummy("n1", TokenKind::Ident)))),
+        );
+        assert_eq!(def.name().get_raw_content(), "add_two");
+        let params: Vec<_> = def.params().unwrap().iter().map(|p| p.get_raw_content()).collect();
+        assert_eq!(params, ["n1", "n2"]);
+        assert!(!def.block().is_empty());
+    }
 }
@@ -0,0 +1,260 @@
+//! AST optimization passes for the Morehead Azalea Compiler
+//!
+//! These passes run after parsing and before codegen. Each one takes ownership of part of the
+//! AST and hands back an equivalent, simpler version of it.
+
+use lexer::token::{Token, TokenKind};
+
+use crate::ast::{Block, Expression, LitValue, RValue, Statement};
+
+/// Fold every subtree whose operands are all literals into a single literal
+///
+/// Arithmetic, comparison and boolean operators are folded (e.g `x + 2 * 3` becomes `x + 6`), and
+/// so are block expressions that only bind literals (e.g `{ let t <- 1; t + 1 }` becomes `2`).
+/// Subtrees containing identifiers are left alone, and so is any operation that would fail at
+/// runtime (e.g division by zero or `int` overflow) so its error surfaces later instead of here.
+pub fn const_fold(expr: Expression) -> Expression {
+    let (head, rest) = match expr
+    {
+        Expression::Cons(head, rest) => (head, rest),
+        Expression::Block(l_bracket, block) => return fold_block(l_bracket, *block),
+        _ => return expr,
+    };
+
+    // Fold from the leaves up
+    let rest = rest.into_iter().map(const_fold).collect::<Vec<_>>();
+
+    let folded = match rest.as_slice()
+    {
+        [Expression::Literal(_, value)] => fold_unary(&head, value),
+        [Expression::Literal(_, lhs), Expression::Literal(_, rhs)] => fold_binary(&head, lhs, rhs),
+        _ => None,
+    };
+
+    let expr = Expression::Cons(head, rest);
+    match folded
+    {
+        Some(value) => Expression::Literal(literal_token(&expr, &value), value),
+        None => expr,
+    }
+}
+
+// Evaluate a block expression down to its value, if every statement in it binds a literal.
+// Otherwise the block is left alone, since dropping its statements would change what it does.
+fn fold_block(l_bracket: Token, block: Block) -> Expression {
+    let mut values: Vec<(String, LitValue)> = Vec::new();
+
+    for stmt in block.statements().unwrap_or_default()
+    {
+        let Statement::VarBindingInit { bind_name, rhs: RValue::Expr(Some(rhs)), .. } = stmt
+        else
+        {
+            return Expression::Block(l_bracket, Box::new(block));
+        };
+
+        let Expression::Literal(_, value) = const_fold(substitute(rhs.clone(), &values))
+        else
+        {
+            return Expression::Block(l_bracket, Box::new(block));
+        };
+        values.push((bind_name.get_raw_content().to_string(), value));
+    }
+
+    let folded = block.expression().map(|expr| const_fold(substitute(expr.clone(), &values)));
+    match folded
+    {
+        Some(literal @ Expression::Literal(..)) => literal,
+        _ => Expression::Block(l_bracket, Box::new(block)),
+    }
+}
+
+// Replace every identifier bound in `values` with its literal. Later bindings shadow earlier
+// ones. Nested blocks are left alone, since they may bind the same names again.
+fn substitute(expr: Expression, values: &[(String, LitValue)]) -> Expression {
+    match expr
+    {
+        Expression::Atom(tok) if tok.is_a(TokenKind::Ident) =>
+        {
+            let bound = values.iter().rev().find(|(name, _)| name == tok.get_raw_content());
+            match bound
+            {
+                Some((_, value)) =>
+                {
+                    let atom = Expression::Atom(tok);
+                    Expression::Literal(literal_token(&atom, value), value.clone())
+                }
+                None => Expression::Atom(tok),
+            }
+        }
+        Expression::Cons(head, rest) =>
+        {
+            let rest = rest.into_iter().map(|operand| substitute(operand, values)).collect();
+            Expression::Cons(head, rest)
+        }
+        _ => expr,
+    }
+}
+
+// Evaluate a prefix operator applied to a literal (e.g `-5`)
+pub(crate) fn fold_unary(op: &Token, value: &LitValue) -> Option<LitValue> {
+    use LitValue::*;
+
+    match (op.get_token_kind(), value)
+    {
+        (TokenKind::Minus, Int(value)) => value.checked_neg().map(Int),
+        (TokenKind::Minus, Float(value)) => Some(Float(-value)),
+        _ => None,
+    }
+}
+
+// Evaluate an infix operator applied to two literals (e.g `2 * 3`)
+pub(crate) fn fold_binary(op: &Token, lhs: &LitValue, rhs: &LitValue) -> Option<LitValue> {
+    use LitValue::*;
+    use TokenKind::*;
+
+    let value = match (op.get_token_kind(), lhs, rhs)
+    {
+        // Arithmetic
+        (Plus, Int(lhs), Int(rhs)) => Int(lhs.checked_add(*rhs)?),
+        (Minus, Int(lhs), Int(rhs)) => Int(lhs.checked_sub(*rhs)?),
+        (Mul, Int(lhs), Int(rhs)) => Int(lhs.checked_mul(*rhs)?),
+        (Div, Int(lhs), Int(rhs)) => Int(lhs.checked_div(*rhs)?),
+        (Plus, Float(lhs), Float(rhs)) => Float(lhs + rhs),
+        (Minus, Float(lhs), Float(rhs)) => Float(lhs - rhs),
+        (Mul, Float(lhs), Float(rhs)) => Float(lhs * rhs),
+        (Div, Float(_), Float(rhs)) if *rhs == 0.0 => return None,
+        (Div, Float(lhs), Float(rhs)) => Float(lhs / rhs),
+
+        // Comparison
+        (Eq, lhs, rhs) if same_type(lhs, rhs) => Bool(lhs == rhs),
+        (Lt, lhs, rhs) if same_type(lhs, rhs) => Bool(lhs.partial_cmp(rhs)?.is_lt()),
+        (Lte, lhs, rhs) if same_type(lhs, rhs) => Bool(lhs.partial_cmp(rhs)?.is_le()),
+        (Gt, lhs, rhs) if same_type(lhs, rhs) => Bool(lhs.partial_cmp(rhs)?.is_gt()),
+        (Gte, lhs, rhs) if same_type(lhs, rhs) => Bool(lhs.partial_cmp(rhs)?.is_ge()),
+
+        // Boolean
+        (AndKw, Bool(lhs), Bool(rhs)) => Bool(*lhs && *rhs),
+        (OrKw, Bool(lhs), Bool(rhs)) => Bool(*lhs || *rhs),
+
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+// Literals of different types are never compared
+fn same_type(lhs: &LitValue, rhs: &LitValue) -> bool {
+    std::mem::discriminant(lhs) == std::mem::discriminant(rhs)
+}
+
+// Leftmost and rightmost `Token`s of an expression
+fn outer_tokens(expr: &Expression) -> (&Token, &Token) {
+    match expr
+    {
+        Expression::Atom(tok) | Expression::Literal(tok, _) => (tok, tok),
+        Expression::Cons(head, rest) =>
+        {
+            rest.iter().map(outer_tokens).fold((head, head), |(first, last), (left, right)| {
+                (
+                    std::cmp::min_by_key(first, left, |tok| tok.get_file_index()),
+                    std::cmp::max_by_key(last, right, |tok| tok.get_file_index()),
+                )
+            })
+        }
+        // Blocks do not keep their `}`, so these end at their last value
+        Expression::Block(l_bracket, block) => match block.expression()
+        {
+            Some(expr) => (l_bracket, outer_tokens(expr).1),
+            None => (l_bracket, l_bracket),
+        },
+        Expression::If { if_kw, cond, then_block, else_block } =>
+        {
+            let branch_values = [then_block, else_block]
+                .into_iter()
+                .filter_map(|block| block.expression())
+                .map(outer_tokens);
+
+            branch_values.fold((if_kw, outer_tokens(cond).1), |(first, last), (_, right)| {
+                (first, std::cmp::max_by_key(last, right, |tok| tok.get_file_index()))
+            })
+        }
+    }
+}
+
+// Build the `Token` of a folded literal, covering the expression it replaces
+fn literal_token(expr: &Expression, value: &LitValue) -> Token {
+    let (raw_content, kind) = match value
+    {
+        LitValue::Int(value) => (value.to_string(), TokenKind::NumLit),
+        LitValue::Float(value) => (format!("{value:?}"), TokenKind::FloatLit),
+        LitValue::Bool(value) => (value.to_string(), TokenKind::BoolLit),
+        LitValue::Char(value) => (format!("{value:?}"), TokenKind::CharLit),
+    };
+    let (first, last) = outer_tokens(expr);
+
+    Token::new(
+        raw_content,
+        kind,
+        *first.span_start_ref(),
+        *last.span_end_ref(),
+        first.get_file_index(),
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use lexer::lexer::Lexer;
+    use symbol_table::SymbolTable;
+
+    use crate::ast_parser::Parser;
+
+    // Parse `expr` as the body of `f x y` and fold it
+    fn fold(expr: &str) -> String {
+        let source = format!("f :: (int, int) -> int\nf x y = {{ {expr} }}\n");
+        let tokens = Lexer::new("test.az", &source).lex(false).unwrap();
+        let program = Parser::new(tokens, Path::new("test.az"), &source)
+            .parse(false, &mut SymbolTable::new())
+            .unwrap();
+
+        let declarations = program.declarations.unwrap();
+        let (_, definition) = declarations[0].as_function().unwrap();
+        let expr = definition.block().expression().unwrap().clone();
+
+        const_fold(expr).to_string()
+    }
+
+    #[test]
+    fn literal_subtrees_fold() {
+        assert_eq!(fold("1 + 2 * 3"), "7");
+        assert_eq!(fold("-(2 - 5)"), "3");
+        assert_eq!(fold("1.5 * 2.0"), "3.0");
+        assert_eq!(fold("1 < 2 and 3 == 4"), "false");
+    }
+
+    #[test]
+    fn identifiers_are_not_folded() {
+        assert_eq!(fold("x + 2 * 3"), "(+ x 6)");
+        assert_eq!(fold("x * (1 + 1) - y"), "(- (* x 2) y)");
+        assert_eq!(fold("0 + f(1 + 1, y)"), "(+ 0 (f 2 y))");
+    }
+
+    #[test]
+    fn block_expressions_fold() {
+        assert_eq!(fold("{ let t <- 1; t + 1 } * 3"), "6");
+        assert_eq!(fold("x + { let t <- 2; let t <- t * t; t - 1 }"), "(+ x 3)");
+
+        // Statements that do not bind a literal are kept
+        assert_eq!(fold("{ let t <- x; t + 1 }"), "{ ... (+ t 1) }");
+    }
+
+    #[test]
+    fn division_by_zero_is_not_folded() {
+        assert_eq!(fold("1 / 0"), "(/ 1 0)");
+        assert_eq!(fold("1 + 4 / (2 - 2)"), "(+ 1 (/ 4 0))");
+    }
+}
@@ -0,0 +1,442 @@
+//! AST pretty-printer for the Morehead Azalea Compiler
+//!
+//! Prints a `Program` back out as Azalea source with one statement per line, four spaces of
+//! indentation per block and a blank line between declarations. Parsing the output gives back
+//! the same AST, so printing it again changes nothing.
+//!
+//! NOTE: Comments are removed by the preprocessor before parsing, so they are not printed.
+
+use std::fmt;
+
+use lexer::token::{Token, TokenKind};
+
+use crate::ast::{Block, Declaration, Expression, ParamTy, Program, RValue, Statement};
+use crate::ast_parser::Parser;
+
+const INDENT: &str = "    ";
+
+/// Print `program` as formatted Azalea source
+pub fn pretty_print(program: &Program) -> String {
+    program
+        .declarations
+        .iter()
+        .flatten()
+        .map(fmt_declaration)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn fmt_declaration(decl: &Declaration) -> String {
+    match decl
+    {
+        Declaration::Function { signature, definition, .. } =>
+        {
+            let tys = fmt_param_tys(signature.ty_list());
+            let ret = fmt_ret_ty(signature.ty_ret());
+            let name = signature.name().get_raw_content();
+
+            // Parameters typed inline keep their parentheses (e.g `(x :: int, y)`)
+            let typed = definition.typed_params().any(|(_param, ty)| ty.is_some());
+            let params = if typed
+            {
+                let params = definition.typed_params().map(|(param, ty)| match ty
+                {
+                    Some(ty) => format!("{param} :: {ty}"),
+                    None => param.to_string(),
+                });
+
+                format!(" ({})", params.collect::<Vec<_>>().join(", "))
+            }
+            else
+            {
+                definition
+                    .typed_params()
+                    .map(|(param, _ty)| format!(" {param}"))
+                    .collect::<String>()
+            };
+            let block = fmt_block(definition.block(), 0);
+
+            format!("{name} :: ({tys}){ret}\n{name}{params} = {block}\n")
+        }
+        Declaration::Choice { name, variants, .. } =>
+        {
+            let variants = variants.iter().flatten().map(Token::get_raw_content);
+
+            format!("{name} :: choice {}\n", fmt_braced_list(variants))
+        }
+        Declaration::Struct { name, typed_fields, .. } =>
+        {
+            let fields = typed_fields
+                .iter()
+                .flatten()
+                .map(|(field, ty)| format!("{field} :: {ty}"));
+
+            format!("{name} :: structure {}\n", fmt_braced_list(fields))
+        }
+    }
+}
+
+// `{ a, b }`, or `{}` if there is nothing in it
+fn fmt_braced_list<S: AsRef<str>>(items: impl Iterator<Item = S>) -> String {
+    let items = items.map(|item| item.as_ref().to_string()).collect::<Vec<_>>();
+
+    if items.is_empty()
+    {
+        "{}".to_string()
+    }
+    else
+    {
+        format!("{{ {} }}", items.join(", "))
+    }
+}
+
+// Input types of a function (e.g `int, (int) -> int`)
+fn fmt_param_tys(tys: Option<&[ParamTy]>) -> String {
+    tys.unwrap_or_default()
+        .iter()
+        .map(|ty| match ty
+        {
+            ParamTy::Prim(ty) => ty.to_string(),
+            ParamTy::Func(func_ty) =>
+            {
+                format!("({}){}", fmt_param_tys(func_ty.ty_list()), fmt_ret_ty(func_ty.ty_ret()))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn fmt_ret_ty(ret: Option<&Token>) -> String {
+    ret.map(|ret| format!(" -> {ret}")).unwrap_or_default()
+}
+
+// A block whose lines are indented one level deeper than `indent`
+fn fmt_block(block: &Block, indent: usize) -> String {
+    if block.is_empty()
+    {
+        return "{}".to_string();
+    }
+
+    let inner = INDENT.repeat(indent + 1);
+    let mut lines = block
+        .statements()
+        .unwrap_or_default()
+        .iter()
+        .map(|stmt| format!("{inner}{}\n", fmt_statement(stmt, indent + 1)))
+        .collect::<String>();
+
+    if let Some(expr) = block.expression()
+    {
+        lines.push_str(&format!("{inner}{}\n", fmt_expr(expr, indent + 1)));
+    }
+
+    format!("{{\n{lines}{}}}", INDENT.repeat(indent))
+}
+
+fn fmt_statement(stmt: &Statement, indent: usize) -> String {
+    match stmt
+    {
+        Statement::VarBindingInit { bind_name, ty_hint, rhs } =>
+        {
+            let hint = ty_hint.as_ref().map(|hint| format!(" :: {}", hint.0)).unwrap_or_default();
+
+            format!("let {bind_name}{hint} <- {};", fmt_rvalue(rhs, indent))
+        }
+        Statement::VarBindingMut { bind_name, expr } =>
+        {
+            format!("{bind_name} <- {};", fmt_expr(expr, indent))
+        }
+        Statement::Selection { if_comp, elif_comp, else_comp } =>
+        {
+            let mut selection = format!(
+                "if {} {}",
+                fmt_expr(if_comp.bool_expr(), indent),
+                fmt_block(if_comp.block(), indent)
+            );
+            if let Some(elif) = elif_comp
+            {
+                selection.push_str(&format!(
+                    " elif {} {}",
+                    fmt_expr(elif.bool_expr(), indent),
+                    fmt_block(elif.block(), indent)
+                ));
+            }
+            if let Some(els) = else_comp
+            {
+                selection.push_str(&format!(" else {}", fmt_block(els.block(), indent)));
+            }
+
+            selection
+        }
+        Statement::IndefiniteLoop { expr, block } =>
+        {
+            format!("while {} {}", fmt_expr(expr, indent), fmt_block(block, indent))
+        }
+        Statement::DefiniteLoop { index_name, low_bound, high_bound, block } =>
+        {
+            let block = fmt_block(block, indent);
+
+            format!("for {index_name} in {low_bound}..{high_bound} {block}")
+        }
+        Statement::FuncCall { name, args } => format!("{name}({});", fmt_args(args, indent)),
+        Statement::Break { .. } => "break;".to_string(),
+        Statement::Continue { .. } => "continue;".to_string(),
+        Statement::Match { scrutinee, arms } =>
+        {
+            let inner = INDENT.repeat(indent + 1);
+            let arms = arms
+                .iter()
+                .map(|(variant, block)| {
+                    format!("{inner}{variant} => {},\n", fmt_block(block, indent + 1))
+                })
+                .collect::<String>();
+
+            format!("match {} {{\n{arms}{}}}", fmt_expr(scrutinee, indent), INDENT.repeat(indent))
+        }
+        Statement::Expr(expr) => format!("{};", fmt_expr(expr, indent)),
+    }
+}
+
+fn fmt_rvalue(rhs: &RValue, indent: usize) -> String {
+    match rhs
+    {
+        RValue::Expr(expr) => expr.iter().map(|expr| fmt_expr(expr, indent)).collect(),
+        RValue::List(elems) => format!("[{}]", fmt_args(elems, indent)),
+        RValue::Struct((name, fields)) => format!("{name} {{ {} }}", fmt_args(fields, indent)),
+        RValue::FuncCall((name, args)) => format!("{name}({})", fmt_args(args, indent)),
+    }
+}
+
+// Comma separated expressions (e.g the arguments of a call)
+fn fmt_args(args: &[Option<Expression>], indent: usize) -> String {
+    args.iter()
+        .flatten()
+        .map(|arg| fmt_expr(arg, indent))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Print an expression with infix operators, adding parentheses only where the operator
+// precedence needs them
+fn fmt_expr(expr: &Expression, indent: usize) -> String {
+    use TokenKind::*;
+
+    match expr
+    {
+        Expression::Atom(tok) | Expression::Literal(tok, _) => tok.to_string(),
+        Expression::Block(_, block) => fmt_block(block, indent),
+        Expression::If { cond, then_block, else_block, .. } => format!(
+            "if {} {} else {}",
+            fmt_expr(cond, indent),
+            fmt_block(then_block, indent),
+            fmt_block(else_block, indent)
+        ),
+        Expression::Cons(head, rest) => match (head.get_token_kind(), rest.as_slice())
+        {
+            // Function call (e.g `f(x, 1)`)
+            (Ident, args) =>
+            {
+                let args = args.iter().map(|arg| fmt_expr(arg, indent)).collect::<Vec<_>>();
+
+                format!("{head}({})", args.join(", "))
+            }
+            // Call through a field access (e.g `m.f(1)`)
+            (LParn, [callee, args @ ..]) =>
+            {
+                let args = args.iter().map(|arg| fmt_expr(arg, indent)).collect::<Vec<_>>();
+
+                format!("{}({})", fmt_expr(callee, indent), args.join(", "))
+            }
+            // Subscript (e.g `xs[0]`)
+            (LSBracket, [list, idx]) =>
+            {
+                let list = fmt_operand(list, u8::MAX, indent);
+
+                format!("{list}[{}]", fmt_expr(idx, indent))
+            }
+            // Prefix operator (e.g `-x`)
+            (_, [operand]) =>
+            {
+                let ((), right_bp) = Parser::get_prefix_bind_power(head);
+
+                format!("{head}{}", fmt_operand(operand, right_bp, indent))
+            }
+            // Infix operator (e.g `a + b`)
+            (_, [lhs, rhs]) =>
+            {
+                let (left_bp, right_bp) = Parser::get_infix_bind_power(head).unwrap();
+
+                // Comparisons cannot be chained, so a compared comparison keeps its parentheses
+                let min_bp = |operand: &Expression, bp: u8| match operand
+                {
+                    Expression::Cons(op, _)
+                        if Parser::is_comparison(op) && Parser::is_comparison(head) =>
+                    {
+                        u8::MAX
+                    }
+                    _ => bp,
+                };
+                let lhs = fmt_operand(lhs, min_bp(lhs, left_bp), indent);
+                let rhs = fmt_operand(rhs, min_bp(rhs, right_bp), indent);
+
+                match head.get_token_kind()
+                {
+                    RecordDot | TQualifer => format!("{lhs}{head}{rhs}"),
+                    _ => format!("{lhs} {head} {rhs}"),
+                }
+            }
+            _ => expr.to_string(),
+        },
+    }
+}
+
+// Print the operand of an operator, in parentheses if it binds less tightly than `min_bp`. An
+// `if` or block used as an operand is always parenthesized, since a statement could not start
+// with it otherwise (e.g `(if a { 1 } else { 2 }) + 1`).
+fn fmt_operand(operand: &Expression, min_bp: u8, indent: usize) -> String {
+    let binds_less_tightly = match operand
+    {
+        Expression::If { .. } | Expression::Block(..) => true,
+        Expression::Cons(op, rest) if op.is_a(TokenKind::Ident) || op.is_a(TokenKind::LParn)
+            || op.is_a(TokenKind::LSBracket) || rest.len() > 2 =>
+        {
+            false
+        }
+        // Prefix operator (e.g `(-x)[0]` or `(-1) as float`)
+        Expression::Cons(op, rest) if rest.len() == 1 =>
+        {
+            let ((), right_bp) = Parser::get_prefix_bind_power(op);
+
+            right_bp < min_bp
+        }
+        Expression::Cons(op, _) =>
+        {
+            Parser::get_infix_bind_power(op).is_some_and(|(left_bp, _)| left_bp < min_bp)
+        }
+        _ => false,
+    };
+
+    let operand = fmt_expr(operand, indent);
+    if binds_less_tightly
+    {
+        format!("({operand})")
+    }
+    else
+    {
+        operand
+    }
+}
+
+// The `Display` of a node is its formatted source, as if it were not indented (e.g `{stmt}` for
+// a `while` loop prints the whole loop). Handy for dumping the AST while debugging.
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", fmt_statement(self, 0))
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", fmt_block(self, 0))
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", fmt_declaration(self).trim_end())
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", pretty_print(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use lexer::lexer::Lexer;
+    use symbol_table::SymbolTable;
+
+    fn parse(source: &str) -> Program {
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+
+        Parser::new(tokens, Path::new("test.az"), source)
+            .parse(false, &mut SymbolTable::new())
+            .unwrap()
+    }
+
+    fn format(source: &str) -> String {
+        pretty_print(&parse(source))
+    }
+
+    #[test]
+    fn messy_source_is_laid_out() {
+        let source = "Color::choice{Red,Blue}\n\
+                      add_two :: (int,int)->int\n\
+                      add_two a b={let c<-(a+b)*2; if c>1{c<-c-1;}else{break;} c}\n";
+
+        assert_eq!(
+            format(source),
+            "Color :: choice { Red, Blue }\n\
+             \n\
+             add_two :: (int, int) -> int\n\
+             add_two a b = {\n    \
+                 let c <- (a + b) * 2;\n    \
+                 if c > 1 {\n        \
+                     c <- c - 1;\n    \
+                 } else {\n        \
+                     break;\n    \
+                 }\n    \
+                 c\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        let source = "P :: structure { a :: int, b :: Color }\n\
+                      f :: (int, (int) -> int) -> int\n\
+                      f x g = { let xs <- [1, 2]; let p <- P { 1, 2 }; \
+                      for i in 0..3 { x <- x - -(i * 2) / (1 - 2); } \
+                      while x > 0 and (x < 3) == true { x <- xs[x - 1]; } \
+                      let y <- if x < 2 { 1 } else { { 2 } }; \
+                      let z <- (if x < 2 { 1 } else { 2 }) + ({ 3 }) * 2; \
+                      let w <- (-xs)[0] + -(-x) + -xs[0]; let v <- (-1) as float; \
+                      0 + g(y, p.a) }\n";
+        let formatted = format(source);
+
+        assert_eq!(format(&formatted), formatted);
+        assert_eq!(parse(&formatted), parse(source));
+        assert!(formatted.contains("x <- x - -(i * 2) / (1 - 2);"));
+        assert!(formatted.contains("(x < 3) == true"));
+        assert!(formatted.contains("(if x < 2 {"));
+        assert!(formatted.contains("(-xs)[0]"));
+        assert!(formatted.contains("(-1) as float"));
+    }
+
+    #[test]
+    fn statements_display_as_source() {
+        let source = "main :: ()\nmain = { let x :: int <- 1 + 2; while x < 10 { x <- x * 2; } }\n";
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let program = Parser::new(tokens, Path::new("test.az"), source)
+            .parse(false, &mut SymbolTable::new())
+            .unwrap();
+
+        let decls = program.declarations.as_deref().unwrap();
+        let stmts = decls[0].as_function().unwrap().1.block().statements().unwrap();
+
+        assert_eq!(stmts[0].to_string(), "let x :: int <- 1 + 2;");
+
+        let while_loop = stmts[1].to_string();
+        assert!(while_loop.starts_with("while x < 10 {"));
+        assert!(while_loop.contains("x <- x * 2;"));
+
+        assert!(decls[0].to_string().starts_with("main :: ()\nmain = {"));
+        assert_eq!(program.to_string(), pretty_print(&program));
+    }
+}
@@ -4,8 +4,7 @@
 //! The preprocessor strips multi-line and single-line C-style comments from
 //! the source file.
 
-use ariadne::{Cache, Config, FileCache, Label, Report, ReportKind, Source};
-use std::ops::Range;
+use lexer::diagnostics::{report_error, report_warning};
 use thiserror::Error;
 
 pub struct ErrorReporter;
@@ -15,51 +14,56 @@ impl ErrorReporter {
     /// missing its terminator.
     pub fn missing_terminater(path: &str, source: &str, offset: usize) {
         let note = "`/*` should close with `*/`";
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Multi-line comment unclosed")
-            .with_label(
-                Label::new((path, (offset.saturating_sub(2))..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(
+            0,
+            "Multi-line comment unclosed",
+            note,
+            path,
+            source,
+            (offset.saturating_sub(2))..offset,
+        );
     }
 
     /// Fancy compiler error that is printed when source files have 
     /// incorrect file extensions
     pub fn incorrect_file_ext(path: &str, source: &str, offset: usize) {
-        let note = format!("file `{0:?}` must have `.az` as a file extension.", path);
-        Report::build(ReportKind::Error, path, 0)
-            .with_code(0)
-            .with_message("Incorrect file extension")
-            .with_note(note)
-            .with_config(Config::default())
-            .with_label(Label::new((path, 0..0)))
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        let note = format!("file `{0}` must have `.az` as a file extension.", path);
+        report_error(0, "Incorrect file extension", &note, path, source, 0..0);
     }
 
     /// Fancy compiler error that is printed when a bad character is detected
     /// in source file.
     pub fn bad_character(bad_ch: char, path: &str, source: &str, offset: usize) {
         let note = format!("Erroneous character, `{bad_ch:?}`, found in source file.");
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(0)
-            .with_message("Bad/Unsupported character found in source file")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(
+            0,
+            "Bad/Unsupported character found in source file",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    /// Fancy compiler error that is printed when a `#line` directive is malformed
+    pub fn bad_line_directive(path: &str, source: &str, offset: usize) {
+        let note = "`#line` directives look like `#line 12` or `#line 12 \"file.az\"`";
+        report_error(0, "Malformed `#line` directive", note, path, source, offset..offset);
+    }
+
+    /// Fancy compiler warning that is printed when the indentation of a line mixes tabs and
+    /// spaces
+    pub fn mixed_indentation(path: &str, source: &str, offset: usize) {
+        let note = "Indent with only tabs or only spaces. Column numbers after a mix depend on \
+                    how wide a tab is.";
+        report_warning(
+            0,
+            "Mixed tabs and spaces in indentation",
+            note,
+            path,
+            source,
+            offset..offset,
+        );
     }
 }
 
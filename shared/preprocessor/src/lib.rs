@@ -3,9 +3,181 @@ pub mod preprocessor;
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use lexer::lexer::Lexer;
+    use preprocessor::Preprocessor;
+
+    fn preprocess(source: &str) -> Preprocessor {
+        Preprocessor::new(source.to_string(), "test.az")
+            .unwrap()
+            .normalize_to_ascii()
+            .unwrap()
+            .remove_multiline_comment()
+            .unwrap()
+            .remove_singleline_comments()
+    }
 
     #[test]
     fn it_works() -> std::io::Result<()> {
         Ok(())
     }
+
+    #[test]
+    fn source_map_without_comments_is_identity() {
+        let source = "let x <- 5;\n";
+        let preprocessor = preprocess(source);
+        let source_map = preprocessor.get_source_map();
+
+        for offset in 0..source.len()
+        {
+            assert_eq!(source_map.original_offset(offset), offset);
+        }
+    }
+
+    #[test]
+    fn token_after_comment_maps_to_original_coordinates() {
+        let source = "/* a comment\n   that spans lines */ // and a line comment\nlet x <- 5;\n";
+        let preprocessor = preprocess(source);
+        let source_map = preprocessor.get_source_map();
+        let cleaned_source = preprocessor.get_cleaned_sources();
+
+        let tokens = Lexer::new("test.az", &cleaned_source).lex(false).unwrap();
+        let x = tokens.iter().find(|tok| tok.get_raw_content() == "x").unwrap();
+
+        // `x` drifted in the cleaned source, but still maps back to where the user wrote it
+        let original_offset = source_map.original_offset(x.get_file_index());
+        assert_ne!(x.get_file_index(), original_offset);
+        assert_eq!(original_offset, source.find('x').unwrap());
+
+        let point = source_map.original_point(x.get_file_index()).unwrap();
+        assert_eq!((point.get_line_num(), point.get_col_num()), (3, 5));
+    }
+
+    #[test]
+    fn line_directives_are_stripped() {
+        use lexer::source_map::LineDirective;
+
+        let source = "let x <- 5;\n  #line 7 \"gen.az\"\nlet y <- 6;\n#line 20\nlet z <- x;\n";
+        let preprocessor = preprocess(source).line_directive().unwrap();
+
+        assert_eq!(
+            preprocessor.get_line_directives(),
+            [
+                LineDirective { line: 2, target_line: 7, target_file: Some("gen.az".to_string()) },
+                LineDirective { line: 4, target_line: 20, target_file: None },
+            ]
+        );
+
+        // Newlines are kept, so every line stays where it was
+        let source_map = preprocessor.get_source_map();
+        let cleaned_source = preprocessor.get_cleaned_sources();
+        assert_eq!(cleaned_source, "let x <- 5;\n\nlet y <- 6;\n\nlet z <- x;\n");
+
+        let z = cleaned_source.find("z <-").unwrap();
+        assert_eq!(source_map.original_offset(z), source.find("z <-").unwrap());
+        assert_eq!(source_map.original_point(z).unwrap().get_line_num(), 5);
+    }
+
+    #[test]
+    fn malformed_line_directives_are_rejected() {
+        for directive in ["#line", "#line x", "#line 0", "#line 3 gen.az", "#line3", "#line 3\"a\""]
+        {
+            let source = format!("{directive}\nlet x <- 5;\n");
+
+            assert!(preprocess(&source).line_directive().is_err(), "{directive}");
+        }
+    }
+
+    #[test]
+    fn clean_all_matches_the_manual_chain() {
+        let source = "/* header */\n#line 3\nlet x <- 5; // five\nlet y <- x;\n";
+
+        let manual = preprocess(source).line_directive().unwrap().get_cleaned_sources();
+        let cleaned = Preprocessor::new(source.to_string(), "test.az").unwrap().clean_all();
+
+        assert_eq!(cleaned.unwrap(), manual);
+        assert_eq!(Preprocessor::passes().first(), Some(&"strip_bom"));
+    }
+
+    #[test]
+    fn crlf_source_is_cleaned_like_lf() {
+        let lf = "// a comment\nlet x <- 5; // five\n  let y <- x;\n";
+        let crlf = lf.replace('\n', "\r\n");
+        let lone_cr = lf.replace('\n', "\r");
+
+        let clean = |source: &str| {
+            let preprocessor = Preprocessor::new(source.to_string(), "test.az")
+                .unwrap()
+                .run_passes()
+                .unwrap();
+            (preprocessor.get_source_map(), preprocessor.get_cleaned_sources())
+        };
+        let (lf_map, lf_cleaned) = clean(lf);
+
+        for source in [crlf.as_str(), lone_cr.as_str()]
+        {
+            let (map, cleaned) = clean(source);
+
+            // No stray `\r` is left behind, even after a line comment
+            assert_eq!(cleaned, lf_cleaned);
+
+            // `y` is on the same line and column as in the LF file
+            let y = cleaned.find('y').unwrap();
+            let point = map.original_point(y).unwrap();
+            let lf_point = lf_map.original_point(y).unwrap();
+            assert_eq!(
+                (point.get_line_num(), point.get_col_num()),
+                (lf_point.get_line_num(), lf_point.get_col_num())
+            );
+            assert_eq!((point.get_line_num(), point.get_col_num()), (3, 7));
+        }
+    }
+
+    #[test]
+    fn trailing_whitespace_is_trimmed() {
+        let source = "let x <- 5;  \t\n\nlet y <- x; // y \n   ";
+        let preprocessor = preprocess(source).trim_trailing_whitespace();
+        let source_map = preprocessor.get_source_map();
+        let cleaned_source = preprocessor.get_cleaned_sources();
+
+        // Newlines are kept, so every line stays where it was
+        assert_eq!(cleaned_source, "let x <- 5;\n\nlet y <- x;\n");
+
+        let y = cleaned_source.find('y').unwrap();
+        assert_eq!(source_map.original_offset(y), source.find('y').unwrap());
+    }
+
+    #[test]
+    fn mixed_indentation_is_warned() {
+        use lexer::diagnostics;
+
+        let source = "main = {\n\t let x <- 1;\n\tx\n    }\n";
+        let preprocessor = Preprocessor::new(source.to_string(), "test.az").unwrap();
+
+        let (warnings, reported) = diagnostics::collected(|| preprocessor.warn_mixed_indentation());
+
+        // Only the tab-then-space line is mixed
+        assert_eq!(warnings, 1);
+        assert_eq!((reported[0].line, reported[0].col), (2, 2));
+    }
+
+    #[test]
+    fn bad_character_after_multi_byte_char_is_located() {
+        use lexer::diagnostics::{self, MessageFormat};
+
+        // `é` and `ß` are two bytes each, so the byte offset of `$` is past its char offset
+        let source = "let x <- 1;\nlet éß <- $;\n";
+
+        diagnostics::set_message_format(MessageFormat::Json);
+        let result = Preprocessor::new(source.to_string(), "test.az").unwrap().normalize_to_ascii();
+        let reported = diagnostics::take_diagnostics();
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert!(result.is_err());
+        assert_eq!((reported[0].line, reported[0].col), (2, 11));
+
+        // Offsets count chars, so `$` is at 22 rather than at its byte position, 24
+        assert_eq!(reported[0].offset, 22);
+        assert_eq!(source.find('$'), Some(24));
+    }
 }
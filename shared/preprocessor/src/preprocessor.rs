@@ -4,6 +4,8 @@
 //! sending the source file off to the lexer to be tokenized.
 
 use crate::errors::{ErrorReporter, PreprocessorError};
+use lexer::source_map::{LineDirective, SourceMap};
+use lexer::span::BOM;
 
 // NOTE: In the future, `content` should be Vec<String> to process many files?
 // NOTE: In the future, `path` should be Vec<String> to process many files paths?
@@ -13,6 +15,11 @@ use crate::errors::{ErrorReporter, PreprocessorError};
 pub struct Preprocessor {
     content: String,
     path: String,
+    original: String,
+    // Original char offset of each char in `content`
+    offsets: Vec<usize>,
+    // `#line` directives stripped out of `content`
+    line_directives: Vec<LineDirective>,
 }
 
 /// CTOR for the `Preprocessor`
@@ -26,27 +33,188 @@ impl Preprocessor {
             return Err(PreprocessorError::Failed(format!("{path}")));
         }
 
+        let offsets = (0..content.chars().count()).collect();
+        let line_directives = find_line_directives(&content);
+
         Ok(Self {
+            original: content.clone(),
             content,
             path: path.to_owned(),
+            offsets,
+            line_directives,
         })
     }
 }
 
+/// Every pass, run in the order the compiler needs
+impl Preprocessor {
+    /// Names of the passes `run_passes` runs, in the order it runs them. A leading BOM is
+    /// stripped before anything else, since `normalize_to_ascii` would reject it as a bad
+    /// character. Line endings are made `\n` next and bad characters are rejected before comments
+    /// are removed, and `#line` directives are only stripped once comments are gone. `new`
+    /// already looks for the directives, so errors from every pass can be remapped.
+    ///
+    /// NOTE: `trim_trailing_whitespace` is not run. Callers that want it can chain it themselves.
+    pub fn passes() -> &'static [&'static str] {
+        &[
+            "strip_bom",
+            "normalize_line_endings",
+            "normalize_to_ascii",
+            "remove_multiline_comment",
+            "remove_singleline_comments",
+            "line_directive",
+        ]
+    }
+
+    /// Run every pass in `passes` in order, keeping the source map and `#line` directives
+    pub fn run_passes(self) -> Result<Self, PreprocessorError> {
+        self.strip_bom()
+            .normalize_line_endings()
+            .normalize_to_ascii()?
+            .remove_multiline_comment()?
+            .remove_singleline_comments()
+            .line_directive()
+    }
+
+    /// Run every pass in order and return the cleaned source
+    pub fn clean_all(self) -> Result<String, PreprocessorError> {
+        Ok(self.run_passes()?.get_cleaned_sources())
+    }
+}
+
+/// Style lints over the original source file
+impl Preprocessor {
+    /// Warn about every line whose leading whitespace mixes tabs and spaces. The warning points
+    /// at the first indentation char that differs from the one the line starts with.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of lines that were warned about
+    pub fn warn_mixed_indentation(&self) -> usize {
+        let mut warnings = 0;
+
+        let mut offset_in_file = 0;
+        for line in self.original.split_inclusive('\n')
+        {
+            let mut indent = line.chars().take_while(|ch| *ch == ' ' || *ch == '\t');
+            let first = indent.next();
+
+            if let Some(mixed_at) = indent.position(|ch| Some(ch) != first)
+            {
+                // Print pretty compiler warning
+                ErrorReporter::mixed_indentation(
+                    self.path.as_ref(),
+                    self.original.as_ref(),
+                    offset_in_file + mixed_at + 1,
+                );
+                warnings += 1;
+            }
+
+            offset_in_file += line.chars().count();
+        }
+
+        warnings
+    }
+}
+
 /// Handlers to remove single-line and multi-line comments from source file
 impl Preprocessor {
+    /// Strip a UTF-8 byte order mark from the start of the file. This has to happen before
+    /// `normalize_to_ascii`, which would otherwise reject the BOM as a bad character.
+    pub fn strip_bom(mut self) -> Self {
+        if self.content.starts_with(BOM)
+        {
+            self.content.remove(0);
+            self.offsets.remove(0);
+        }
+
+        // Return Self to allow method chaining
+        self
+    }
+
+    /// Remove spaces and tabs at the end of every line, keeping the newlines so lines do not
+    /// move
+    pub fn trim_trailing_whitespace(mut self) -> Self {
+        let mut result = String::with_capacity(self.content.len());
+        let mut offsets = Vec::with_capacity(self.offsets.len());
+
+        let mut offset_in_file = 0;
+        for line in self.content.split_inclusive('\n')
+        {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            let kept = text.trim_end_matches([' ', '\t']).chars().count();
+            let line_len = line.chars().count();
+
+            for (idx, ch) in line.chars().take(kept).enumerate()
+            {
+                result.push(ch);
+                offsets.push(self.original_offset(offset_in_file + idx));
+            }
+
+            // Maintain the newline
+            if line.ends_with('\n')
+            {
+                result.push('\n');
+                offsets.push(self.original_offset(offset_in_file + line_len - 1));
+            }
+
+            offset_in_file += line_len;
+        }
+
+        // Update result
+        self.content = result;
+        self.offsets = offsets;
+
+        // Return Self to allow method chaining
+        self
+    }
+
+    /// Turn Windows (`\r\n`) and old Mac (`\r`) line endings into `\n`, so later passes only
+    /// have to look for `\n`. Each `\n` maps back to where its line ending started.
+    pub fn normalize_line_endings(mut self) -> Self {
+        let mut result = String::with_capacity(self.content.len());
+        let mut offsets = Vec::with_capacity(self.offsets.len());
+
+        let mut chars = self.content.chars().enumerate().peekable();
+        while let Some((offset_in_file, ch)) = chars.next()
+        {
+            if ch == '\r'
+            {
+                // A `\r\n` is a single line ending
+                chars.next_if(|(_, next)| *next == '\n');
+                result.push('\n');
+            }
+            else
+            {
+                result.push(ch);
+            }
+            offsets.push(self.original_offset(offset_in_file));
+        }
+
+        // Update result
+        self.content = result;
+        self.offsets = offsets;
+
+        // Return Self to allow method chaining
+        self
+    }
+
     pub fn remove_singleline_comments(mut self) -> Self {
         // Binding for readability
         let content = &self.content;
 
         // Create `result` which will be the fixed-up String with no single-line comments
         let mut result = String::with_capacity(content.len());
+        let mut offsets = Vec::with_capacity(self.offsets.len());
 
         // Loop to remove single line comments by appending non-comments to `result` and skipping
         // commented characters
-        let mut chars = content.chars().peekable();
+        let mut chars          = content.chars().peekable();
+        let mut offset_in_file = 0;
         while let Some(ch) = chars.next()
         {
+            offset_in_file += 1;
+
             // Then we are in a single-line comment, so skip passed characters until we are not
             if ch == '/' && chars.peek() == Some(&'/')
             {
@@ -54,20 +222,25 @@ impl Preprocessor {
                 {
                     // skip character
                     chars.next();
+                    offset_in_file += 1;
                 }
 
                 // Maintain the newline
                 result.push('\n');
+                offsets.push(self.original_offset(offset_in_file));
                 chars.next();
+                offset_in_file += 1;
             }
             else
             {
                 result.push(ch);
+                offsets.push(self.original_offset(offset_in_file - 1));
             }
         }
 
         // Update result
         self.content = result;
+        self.offsets = offsets;
 
         // Return Self to allow method chaining
         self
@@ -79,6 +252,7 @@ impl Preprocessor {
 
         // Create `result` which will be the fixed-up String with no multi-line comments
         let mut result = String::with_capacity(content.len());
+        let mut offsets = Vec::with_capacity(self.offsets.len());
 
         // Loop to remove multi-line comments by appending non-comments to `result` and skipping
         // commented characters
@@ -134,6 +308,7 @@ impl Preprocessor {
                     if temp_chr.is_some_and(char::is_whitespace)
                     {
                         result.push(temp_chr.unwrap());
+                        offsets.push(self.original_offset(offset_in_file - 1));
                     }
 
 
@@ -141,17 +316,85 @@ impl Preprocessor {
                     temp_chr = chars.next();
                 }
 
+                // Skip the closing `*/`
 		chars.next();
-                offset_in_file += 1;
+                offset_in_file += 2;
             }
             else
             {
                 result.push(ch);
+                offsets.push(self.original_offset(offset_in_file - 1));
             }
         }
 
         // Update result
         self.content = result;
+        self.offsets = offsets;
+
+        // Return Self to allow method chaining
+        Ok(self)
+    }
+
+    /// Strip `#line N "file"` directives (the file is optional), keeping their newlines so
+    /// spans do not move. The directives are kept so diagnostics can be reported at the file and
+    /// line they name (see `get_line_directives`).
+    pub fn line_directive(mut self) -> Result<Self, PreprocessorError> {
+        let mut result = String::with_capacity(self.content.len());
+        let mut offsets = Vec::with_capacity(self.offsets.len());
+        let mut directives = Vec::new();
+
+        let mut offset_in_file = 0;
+        for line in self.content.split_inclusive('\n')
+        {
+            let line_len = line.chars().count();
+
+            if line.trim_start().starts_with("#line")
+            {
+                let indent = line.chars().take_while(|ch| ch.is_whitespace()).count();
+                let original_offset = self.original_offset(offset_in_file + indent);
+
+                let Some((target_line, target_file)) = parse_line_directive(line.trim())
+                else
+                {
+                    // Print pretty compiler error
+                    ErrorReporter::bad_line_directive(
+                        self.path.as_ref(),
+                        self.original.as_ref(),
+                        original_offset,
+                    );
+
+                    return Err(PreprocessorError::Failed(self.path.clone()));
+                };
+
+                directives.push(LineDirective {
+                    line: self.original_line(original_offset),
+                    target_line,
+                    target_file,
+                });
+
+                // Maintain the newline
+                if line.ends_with('\n')
+                {
+                    result.push('\n');
+                    offsets.push(self.original_offset(offset_in_file + line_len - 1));
+                }
+            }
+            else
+            {
+                for (idx, ch) in line.chars().enumerate()
+                {
+                    result.push(ch);
+                    offsets.push(self.original_offset(offset_in_file + idx));
+                }
+            }
+
+            offset_in_file += line_len;
+        }
+
+        // Update result
+        self.content = result;
+        self.offsets = offsets;
+        self.line_directives = directives;
 
         // Return Self to allow method chaining
         Ok(self)
@@ -163,12 +406,15 @@ impl Preprocessor {
 
         const VALID_PUNC: &[&str] = &[
             ";", ":", "_", ",", "(", ")", "{", "}", "+", "-", "*", "/", "%", "&", "|", "=", "<",
-            ">", "!", ".", "[", "]",
+            ">", "!", ".", "[", "]", "'", "\\", "#", "\"",
         ];
         const VALID_CONTROL: &[&str] = &["\n", "\t", "\r"];
 
         // Loop through chars to see if any bad characters are in the source
         // file.
+        //
+        // NOTE: `file_offset` counts chars, like every offset given to reporters (see the docs of
+        // the `lexer` crate)
         let chars = content.chars().peekable();
         for (file_offset, ch) in chars.enumerate()
         {
@@ -196,6 +442,40 @@ impl Preprocessor {
         Ok(self)
     }
 
+    /// Build a `SourceMap` from the cleaned source back to the original source file. Reporters
+    /// can use it to point at what the user actually wrote.
+    ///
+    /// Warning: Make sure you actually preprocess the file(s) first!
+    pub fn get_source_map(&self) -> SourceMap {
+        SourceMap::new(self.original.clone(), self.offsets.clone())
+    }
+
+    /// `#line` directives in the order they appear. Before `line_directive` runs, these are
+    /// every well formed directive in the original source (even ones inside comments), so
+    /// diagnostics from the earlier passes can be remapped too.
+    pub fn get_line_directives(&self) -> &[LineDirective] {
+        &self.line_directives
+    }
+
+    /// Line (starting at 1) of the char at `original_offset` in the original source file
+    fn original_line(&self, original_offset: usize) -> usize {
+        1 + self
+            .original
+            .chars()
+            .take(original_offset)
+            .filter(|ch| *ch == '\n')
+            .count()
+    }
+
+    /// Original char offset of the char at `offset` in the current content
+    fn original_offset(&self, offset: usize) -> usize {
+        match self.offsets.get(offset)
+        {
+            Some(original) => *original,
+            None => self.original.chars().count(),
+        }
+    }
+
     /// A Rust "move" of the preprocessed version of the
     /// source file out of the `Preprocessor`, leaving an
     /// empty String in its place.
@@ -205,3 +485,45 @@ impl Preprocessor {
         std::mem::replace(&mut self.content, String::new())
     }
 }
+
+// Every well formed `#line` directive in `source`. Malformed ones are left for `line_directive`
+// to report.
+fn find_line_directives(source: &str) -> Vec<LineDirective> {
+    source
+        .split('\n')
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let (target_line, target_file) = parse_line_directive(line.trim())?;
+
+            Some(LineDirective { line: idx + 1, target_line, target_file })
+        })
+        .collect()
+}
+
+// Target line and (optional) file of a `#line N "file"` directive, or `None` if it is malformed
+fn parse_line_directive(directive: &str) -> Option<(usize, Option<String>)> {
+    let rest = directive.strip_prefix("#line")?;
+    if !rest.starts_with(char::is_whitespace)
+    {
+        return None;
+    }
+
+    let rest = rest.trim_start();
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    let target_line = rest[..digits].parse().ok().filter(|line| *line > 0)?;
+
+    let file = rest[digits..].trim();
+    if file.is_empty()
+    {
+        return Some((target_line, None));
+    }
+
+    // The file must be quoted, and there must be whitespace between it and the line
+    let name = file.strip_prefix('"')?.strip_suffix('"')?;
+    if name.is_empty() || name.contains('"') || !rest[digits..].starts_with(char::is_whitespace)
+    {
+        return None;
+    }
+
+    Some((target_line, Some(name.to_string())))
+}
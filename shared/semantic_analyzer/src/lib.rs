@@ -1,15 +1,18 @@
 
 
-use symbol_table::SymbolTable;
-use symbol_table::SemanticError;
+use std::path::Path;
+
+use lexer::diagnostics::source_of;
+use lexer::token::{NumSuffix, Token, TokenKind};
+use symbol_table::{Primitve, SymbolKind, SymbolTable, Type};
+use symbol_table::{SemanticError, SemanticErrorReporter};
+use symbol_table::index::ReferenceIndex;
 use parser::ast::*;
 
 
 
 pub fn check_for_missing_varbind<'semantic>(st: &SymbolTable, ast: &Program) -> Result<(), SemanticError> {
 
-    println!("{ast:#?}");
-    
     if ast.declarations.is_none() {
         return Ok(());
     }
@@ -20,14 +23,1862 @@ pub fn check_for_missing_varbind<'semantic>(st: &SymbolTable, ast: &Program) ->
     Ok(())
 }
 
+// `break` and `continue` only make sense inside of a loop body
+pub fn check_for_loop_control_outside_loop<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        let in_loop = false;
+        check_block_loop_control(definition.block(), in_loop, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+fn check_block_loop_control(
+    block: &Block,
+    in_loop: bool,
+    path: &Path,
+    cleaned_source: &str,
+) -> Result<(), SemanticError> {
+    for stmt in block.statements().unwrap_or_default()
+    {
+        match stmt
+        {
+            Statement::Break { break_kw: control_kw } | Statement::Continue { continue_kw: control_kw }
+                if !in_loop =>
+            {
+                let (path, source) =
+                    source_of(control_kw.source_id(), path.to_str().unwrap(), cleaned_source);
+                SemanticErrorReporter::break_outside_loop(
+                    control_kw,
+                    &path,
+                    &source,
+                    control_kw.get_file_index(),
+                );
+
+                return Err(SemanticError::SemanticFail);
+            }
+            _ =>
+            {
+                // Everything nested in a loop body is inside of the loop, but its condition is
+                // not. `if` blocks are only inside a loop if the `if` itself is.
+                let loop_body = match stmt
+                {
+                    Statement::IndefiniteLoop { block, .. } => Some(block),
+                    Statement::DefiniteLoop { block, .. } => Some(block),
+                    _ => None,
+                };
+
+                for nested in stmt.blocks()
+                {
+                    let in_body = loop_body.is_some_and(|body| std::ptr::eq(body, nested));
+                    let in_loop = in_loop || in_body;
+                    check_block_loop_control(nested, in_loop, path, cleaned_source)?;
+                }
+            }
+        }
+    }
+
+    for nested in block.expression_blocks()
+    {
+        check_block_loop_control(nested, in_loop, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+// For-loop bounds must be `int` literals, and the low bound cannot be above the high one (e.g
+// `for i in 5..1`)
+pub fn check_for_bounds<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        check_block_for_bounds(definition.block(), path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+fn check_block_for_bounds(
+    block: &Block,
+    path: &Path,
+    cleaned_source: &str,
+) -> Result<(), SemanticError> {
+    for stmt in block.statements().unwrap_or_default()
+    {
+        if let Statement::DefiniteLoop { low_bound, high_bound, .. } = stmt
+        {
+            let low = int_bound(low_bound, path, cleaned_source)?;
+            let high = int_bound(high_bound, path, cleaned_source)?;
+
+            if low > high
+            {
+                let (path, source) =
+                    source_of(low_bound.source_id(), path.to_str().unwrap(), cleaned_source);
+                SemanticErrorReporter::empty_range(
+                    low_bound,
+                    high_bound,
+                    &path,
+                    &source,
+                    low_bound.get_file_index(),
+                );
+
+                return Err(SemanticError::SemanticFail);
+            }
+        }
+
+        for nested in stmt.blocks()
+        {
+            check_block_for_bounds(nested, path, cleaned_source)?;
+        }
+    }
+
+    for nested in block.expression_blocks()
+    {
+        check_block_for_bounds(nested, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+// Value of a for-loop bound, which must be an `int` literal
+fn int_bound(bound: &Token, path: &Path, cleaned_source: &str) -> Result<i64, SemanticError> {
+    match (bound.num_suffix(), LitValue::from_token(bound))
+    {
+        (None | Some(NumSuffix::Int), Some(LitValue::Int(value))) => Ok(value),
+        _ =>
+        {
+            let (path, source) =
+                source_of(bound.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::non_integer_bound(
+                bound,
+                &path,
+                &source,
+                bound.get_file_index(),
+            );
+
+            Err(SemanticError::SemanticFail)
+        }
+    }
+}
+
+// Every `match` needs an arm for each variant of the choice it matches on. The choice is the one
+// that declares the variant of the first arm.
+pub fn check_match_exhaustiveness<'semantic>(
+    st: &SymbolTable,
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    let matches = MatchContext { choices: choices_of(decls), st, index: index_references(ast) };
+
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        check_block_match_exhaustiveness(definition.block(), &matches, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+// Every choice declared in the program. Tuple is `(choice_name, variants)`.
+fn choices_of(decls: &[Declaration]) -> Vec<(&Token, &[Token])> {
+    decls
+        .iter()
+        .filter_map(|decl| match decl
+        {
+            Declaration::Choice { name, variants, .. } =>
+            {
+                Some((name, variants.as_deref().unwrap_or_default()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// What is needed to find the choice a `match` is over
+struct MatchContext<'ast, 'st> {
+    // Tuple is `(choice_name, variants)`
+    choices: Vec<(&'ast Token, &'ast [Token])>,
+    st: &'st SymbolTable,
+    index: ReferenceIndex,
+}
+
+impl<'ast> MatchContext<'ast, '_> {
+    // The choice that `scrutinee` holds a value of, going by the type the symbol table gives the
+    // binding it names. Without one (e.g a parameter), it is the choice declaring the first arm
+    // that names a variant of any choice.
+    fn choice_of(
+        &self,
+        scrutinee: &Expression,
+        arms: &[(Token, Block)],
+    ) -> Option<(&'ast Token, &'ast [Token])> {
+        let by_name = |name: &str| {
+            self.choices.iter().find(|(choice_name, _)| choice_name.get_raw_content() == name)
+        };
+
+        let scrutinee_ty = match scrutinee
+        {
+            Expression::Atom(used) =>
+            {
+                let used_at = used.file_range();
+                self.st
+                    .iter()
+                    .filter(|node| node.ty_name().is_some())
+                    .find(|node| self.index.references_of(node.name()).contains(&used_at))
+                    .and_then(|node| by_name(node.ty_name()?.get_raw_content()))
+            }
+            _ => None,
+        };
+
+        let by_arms = || {
+            arms.iter().find_map(|(arm, _)| {
+                self.choices.iter().find(|(_, variants)| variants.iter().any(|v| v == arm))
+            })
+        };
+
+        scrutinee_ty.or_else(by_arms).copied()
+    }
+}
+
+fn check_block_match_exhaustiveness(
+    block: &Block,
+    matches: &MatchContext,
+    path: &Path,
+    cleaned_source: &str,
+) -> Result<(), SemanticError> {
+    for stmt in block.statements().unwrap_or_default()
+    {
+        if let Statement::Match { scrutinee, arms } = stmt
+        {
+            let arm_names = arms
+                .iter()
+                .map(|(variant, _)| variant.get_raw_content())
+                .collect::<Vec<_>>();
+
+            if let Some((choice_name, variants)) = matches.choice_of(scrutinee, arms)
+            {
+                // Every arm names a variant of the choice
+                let unknown = arms
+                    .iter()
+                    .map(|(arm, _)| arm)
+                    .find(|arm| !variants.contains(arm));
+
+                if let Some(arm) = unknown
+                {
+                    let (path, source) =
+                        source_of(arm.source_id(), path.to_str().unwrap(), cleaned_source);
+                    SemanticErrorReporter::unknown_variant(
+                        arm,
+                        choice_name,
+                        &path,
+                        &source,
+                        arm.get_file_index(),
+                    );
+
+                    return Err(SemanticError::SemanticFail);
+                }
+
+                let missing_variants = variants
+                    .iter()
+                    .map(Token::get_raw_content)
+                    .filter(|variant| !arm_names.contains(variant))
+                    .collect::<Vec<_>>();
+
+                if !missing_variants.is_empty()
+                {
+                    let source_id = scrutinee.head().source_id();
+                    let (path, source) =
+                        source_of(source_id, path.to_str().unwrap(), cleaned_source);
+                    SemanticErrorReporter::non_exhaustive_match(
+                        choice_name,
+                        &missing_variants,
+                        &path,
+                        &source,
+                        scrutinee.span().start,
+                    );
+
+                    return Err(SemanticError::SemanticFail);
+                }
+            }
+        }
+
+        for nested in stmt.blocks()
+        {
+            check_block_match_exhaustiveness(nested, matches, path, cleaned_source)?;
+        }
+    }
+
+    for nested in block.expression_blocks()
+    {
+        check_block_match_exhaustiveness(nested, matches, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+// A function that returns a value may end its body with an `if` instead of an expression. That
+// `if` gives the function its value, so every path through it needs a branch (e.g an `else`).
+// An `if` anywhere else is only run for its effects and may leave out the `else`.
+pub fn check_selection_exhaustiveness<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    for (signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        if signature.ty_ret().is_some()
+        {
+            check_value_block_selection(definition.block(), path, cleaned_source)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_value_block_selection(
+    block: &Block,
+    path: &Path,
+    cleaned_source: &str,
+) -> Result<(), SemanticError> {
+    // A trailing expression gives the value, not the last statement
+    if block.expression().is_some()
+    {
+        return Ok(());
+    }
+
+    let Some(last_stmt) = block.statements().and_then(|stmts| stmts.last())
+    else
+    {
+        return Ok(());
+    };
+    let Statement::Selection { if_comp, else_comp, .. } = last_stmt
+    else
+    {
+        return Ok(());
+    };
+
+    if else_comp.is_none()
+    {
+        let cond = if_comp.bool_expr();
+        let (path, source) =
+            source_of(cond.head().source_id(), path.to_str().unwrap(), cleaned_source);
+        SemanticErrorReporter::non_exhaustive_selection(&path, &source, cond.span().start);
+
+        return Err(SemanticError::SemanticFail);
+    }
+
+    // Each branch gives the value in turn
+    for branch in last_stmt.body_blocks()
+    {
+        check_value_block_selection(branch, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+// Names of every declared structure and choice
+fn user_tys(st: &SymbolTable) -> Vec<&str> {
+    st.iter()
+        .filter(|node| node.kind() == SymbolKind::Global)
+        .filter(|node| matches!(node.ty(), Type::Struct | Type::Choice))
+        .map(|node| node.name().get_raw_content())
+        .collect()
+}
+
+// A structure field is either a primitive or a structure or choice declared somewhere in the
+// program (e.g `friend :: Person`)
+pub fn check_struct_field_types<'semantic>(
+    st: &SymbolTable,
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    let user_tys = user_tys(st);
+    for decl in decls
+    {
+        let Declaration::Struct { typed_fields: Some(fields), .. } = decl
+        else
+        {
+            continue;
+        };
+
+        let unknown = fields
+            .iter()
+            .map(|(_field, ty)| ty)
+            .filter(|ty| ty.is_a(TokenKind::Ident))
+            .find(|ty| !user_tys.contains(&ty.get_raw_content()));
+
+        if let Some(ty) = unknown
+        {
+            let (path, source) = source_of(ty.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::unknown_type(ty, &path, &source, ty.get_file_index());
+
+            return Err(SemanticError::SemanticFail);
+        }
+    }
+
+    Ok(())
+}
+
+// A parameter typed inline (e.g `f (x :: int) = ...`) has the same type as the signature gives it,
+// and a type named inline was declared as a structure or choice
+pub fn check_param_annotations<'semantic>(
+    st: &SymbolTable,
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    let user_tys = user_tys(st);
+    for (signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        let unknown = definition
+            .typed_params()
+            .filter_map(|(_param, inline_ty)| inline_ty)
+            .filter(|ty| ty.is_a(TokenKind::Ident))
+            .find(|ty| !user_tys.contains(&ty.get_raw_content()));
+
+        if let Some(ty) = unknown
+        {
+            let (path, source) = source_of(ty.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::unknown_type(ty, &path, &source, ty.get_file_index());
+
+            return Err(SemanticError::SemanticFail);
+        }
+
+        let sig_tys = signature.ty_list().unwrap_or_default();
+        for ((param, inline_ty), sig_ty) in definition.typed_params().zip(sig_tys)
+        {
+            let Some(inline_ty) = inline_ty
+            else
+            {
+                continue;
+            };
+
+            let sig_ty = match sig_ty
+            {
+                ParamTy::Prim(ty) if ty.get_raw_content() == inline_ty.get_raw_content() =>
+                {
+                    continue
+                }
+                ParamTy::Prim(ty) => ty.get_raw_content().to_string(),
+                ParamTy::Func(_) => "a function type".to_string(),
+            };
+
+            let (path, source) =
+                source_of(inline_ty.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::param_type_conflict(
+                param,
+                inline_ty,
+                &sig_ty,
+                &path,
+                &source,
+                inline_ty.get_file_index(),
+            );
+
+            return Err(SemanticError::SemanticFail);
+        }
+    }
+
+    Ok(())
+}
+
+// A structure that contains itself, directly (`A :: structure { a :: A }`) or through other
+// structures, would take up infinite space
+pub fn check_recursive_structs<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    // Every structure with the types of its fields
+    let structs = decls
+        .iter()
+        .filter_map(|decl| match decl
+        {
+            Declaration::Struct { name, typed_fields, .. } =>
+            {
+                let field_tys = typed_fields.iter().flatten().map(|(_field, ty)| ty).collect();
+                Some((name, field_tys))
+            }
+            _ => None,
+        })
+        .collect::<Vec<(&Token, Vec<&Token>)>>();
+
+    for (name, _) in &structs
+    {
+        let mut stack = vec![*name];
+        if let Some(start) = find_struct_cycle(&structs, &mut stack)
+        {
+            // Only report the cycle once, from the first structure in it
+            if start != 0
+            {
+                continue;
+            }
+            let cycle = &stack[start..];
+
+            let (path, source) =
+                source_of(name.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::recursive_struct(cycle, &path, &source, name.get_file_index());
+
+            return Err(SemanticError::SemanticFail);
+        }
+    }
+
+    Ok(())
+}
+
+// Depth first search through the structure fields of the last structure on `stack`
+//
+// Returns where the cycle starts in `stack` if one is reachable, leaving the structures that
+// lead to it on `stack`
+fn find_struct_cycle<'ast>(
+    structs: &[(&'ast Token, Vec<&'ast Token>)],
+    stack: &mut Vec<&'ast Token>,
+) -> Option<usize> {
+    let current = stack.last()?.get_raw_content();
+    let (_, field_tys) = structs.iter().find(|(name, _)| name.get_raw_content() == current)?;
+
+    for field_ty in field_tys
+    {
+        // Fields of primitive or choice types do not lead anywhere
+        let Some((next, _)) = structs
+            .iter()
+            .find(|(name, _)| name.symbol() == field_ty.symbol())
+        else
+        {
+            continue;
+        };
+
+        let seen = stack.iter().position(|name| name.symbol() == next.symbol());
+        if seen.is_some()
+        {
+            return seen;
+        }
+
+        stack.push(next);
+        if let Some(start) = find_struct_cycle(structs, stack)
+        {
+            return Some(start);
+        }
+        stack.pop();
+    }
+
+    None
+}
+
+// `let x <- x + 1;` uses `x` before it exists, unless a parameter or an earlier binding named `x`
+// is in scope (which the new `x` then shadows). Updating a binding (e.g `x <- x + 1;`) is fine.
+pub fn check_self_referential_bindings<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return Ok(());
+    };
+
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        let mut bound = definition
+            .params()
+            .unwrap_or_default()
+            .iter()
+            .map(Token::get_raw_content)
+            .collect::<Vec<_>>();
+
+        check_block_self_reference(definition.block(), &mut bound, path, cleaned_source)?;
+    }
+
+    Ok(())
+}
+
+fn check_block_self_reference<'ast>(
+    block: &'ast Block,
+    bound: &mut Vec<&'ast str>,
+    path: &Path,
+    cleaned_source: &str,
+) -> Result<(), SemanticError> {
+    // Bindings made in this block go out of scope at its end
+    let outer_len = bound.len();
+
+    for stmt in block.statements().unwrap_or_default()
+    {
+        if let Statement::VarBindingInit { bind_name, rhs, .. } = stmt
+        {
+            let name = bind_name.get_raw_content();
+
+            let mut used = Vec::new();
+            rvalue_idents(rhs, &mut used);
+
+            let self_ref = used.into_iter().find(|tok| tok.get_raw_content() == name);
+            if let Some(self_ref) = self_ref.filter(|_| !bound.contains(&name))
+            {
+                let (path, source) =
+                    source_of(self_ref.source_id(), path.to_str().unwrap(), cleaned_source);
+                SemanticErrorReporter::self_reference_in_binding(
+                    bind_name,
+                    &path,
+                    &source,
+                    self_ref.get_file_index(),
+                );
+
+                return Err(SemanticError::SemanticFail);
+            }
+
+            bound.push(name);
+        }
+
+        // The index of a `for` loop is bound inside of its body
+        let loop_index = match stmt
+        {
+            Statement::DefiniteLoop { index_name, .. } => Some(index_name.get_raw_content()),
+            _ => None,
+        };
+
+        for nested in stmt.blocks()
+        {
+            let nested_len = bound.len();
+            bound.extend(loop_index);
+
+            check_block_self_reference(nested, bound, path, cleaned_source)?;
+            bound.truncate(nested_len);
+        }
+    }
+
+    for nested in block.expression_blocks()
+    {
+        check_block_self_reference(nested, bound, path, cleaned_source)?;
+    }
+
+    bound.truncate(outer_len);
+
+    Ok(())
+}
+
+// Identifiers used as values on the right hand side of a binding
+fn rvalue_idents<'ast>(rhs: &'ast RValue, idents: &mut Vec<&'ast Token>) {
+    let exprs = match rhs
+    {
+        RValue::Expr(expr) => std::slice::from_ref(expr),
+        RValue::List(elems) => elems.as_slice(),
+        RValue::Struct((_, fields)) => fields.as_slice(),
+        RValue::FuncCall((_, args)) => args.as_slice(),
+    };
+
+    for expr in exprs.iter().flatten()
+    {
+        expr_idents(expr, idents);
+    }
+}
+
+fn expr_idents<'ast>(expr: &'ast Expression, idents: &mut Vec<&'ast Token>) {
+    match expr
+    {
+        Expression::Atom(tok) if tok.is_a(TokenKind::Ident) => idents.push(tok),
+        Expression::Atom(_) | Expression::Literal(..) => {}
+        Expression::Cons(head, rest) => match head.get_token_kind()
+        {
+            // Field names, cast types and choice variants are not values
+            TokenKind::RecordDot | TokenKind::AsKw => expr_idents(&rest[0], idents),
+            TokenKind::TQualifer => {}
+            _ => rest.iter().for_each(|operand| expr_idents(operand, idents)),
+        },
+        Expression::Block(_, block) => block_idents(block, idents),
+        Expression::If { cond, then_block, else_block, .. } =>
+        {
+            expr_idents(cond, idents);
+            block_idents(then_block, idents);
+            block_idents(else_block, idents);
+        }
+    }
+}
+
+// Identifiers used as values inside of a block. A block has bindings of its own, so uses of
+// a name bound earlier in the block (or in a block nested in it) are left out.
+fn block_idents<'ast>(block: &'ast Block, idents: &mut Vec<&'ast Token>) {
+    let mut bound = Vec::new();
+
+    for stmt in block.statements().unwrap_or_default()
+    {
+        let mut used = Vec::new();
+        for expr in stmt.expressions()
+        {
+            expr_idents(expr, &mut used);
+        }
+        for nested in stmt.body_blocks()
+        {
+            block_idents(nested, &mut used);
+        }
+
+        // The index of a `for` loop is bound inside of its body
+        if let Statement::DefiniteLoop { index_name, .. } = stmt
+        {
+            used.retain(|tok| tok.get_raw_content() != index_name.get_raw_content());
+        }
+        idents.extend(used.into_iter().filter(|tok| !bound.contains(&tok.get_raw_content())));
+
+        if let Statement::VarBindingInit { bind_name, .. } = stmt
+        {
+            bound.push(bind_name.get_raw_content());
+        }
+    }
+
+    if let Some(expr) = block.expression()
+    {
+        let mut used = Vec::new();
+        expr_idents(expr, &mut used);
+        idents.extend(used.into_iter().filter(|tok| !bound.contains(&tok.get_raw_content())));
+    }
+}
+
+// A `let` in an inner scope may reuse the name of a parameter or outer binding, but that is often
+// a mistake. Warn about each one.
+//
+// Returns how many warnings were reported
+pub fn check_shadowing<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> usize {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return 0;
+    };
+
+    let mut warnings = 0;
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        let params = definition.params().unwrap_or_default().iter().collect();
+        let mut scopes = vec![params];
+
+        warnings += check_block_shadowing(definition.block(), &mut scopes, path, cleaned_source);
+    }
+
+    warnings
+}
+
+fn check_block_shadowing<'ast>(
+    block: &'ast Block,
+    scopes: &mut Vec<Vec<&'ast Token>>,
+    path: &Path,
+    cleaned_source: &str,
+) -> usize {
+    let mut warnings = 0;
+    scopes.push(Vec::new());
+
+    for stmt in block.statements().unwrap_or_default()
+    {
+        if let Statement::VarBindingInit { bind_name, .. } = stmt
+        {
+            let Some((_current_scope, outer_scopes)) = scopes.split_last()
+            else
+            {
+                unreachable!("the scope of this block was pushed above");
+            };
+
+            let shadows_outer = outer_scopes
+                .iter()
+                .flatten()
+                .any(|outer| outer.symbol() == bind_name.symbol());
+
+            if shadows_outer
+            {
+                let (path, source) =
+                    source_of(bind_name.source_id(), path.to_str().unwrap(), cleaned_source);
+                SemanticErrorReporter::variable_shadows_outer(
+                    bind_name,
+                    &path,
+                    &source,
+                    bind_name.get_file_index(),
+                );
+                warnings += 1;
+            }
+        }
+
+        // The index of a `for` loop is bound in a scope around its body
+        let loop_index = match stmt
+        {
+            Statement::DefiniteLoop { index_name, .. } => Some(index_name),
+            _ => None,
+        };
+
+        for nested in stmt.blocks()
+        {
+            scopes.push(loop_index.into_iter().collect());
+            warnings += check_block_shadowing(nested, scopes, path, cleaned_source);
+            scopes.pop();
+        }
+
+        // A new binding is only in scope after its right hand side
+        if let Statement::VarBindingInit { bind_name, .. } = stmt
+        {
+            scopes.last_mut().expect("the scope of this block was pushed above").push(bind_name);
+        }
+    }
+
+    for nested in block.expression_blocks()
+    {
+        warnings += check_block_shadowing(nested, scopes, path, cleaned_source);
+    }
+
+    scopes.pop();
+
+    warnings
+}
+
+// A `let` binding that is never used afterwards is probably dead code. Warn about each one, unless
+// its name starts with `_` to say that is on purpose.
+//
+// Returns how many warnings were reported
+pub fn check_unused_bindings<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> usize {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return 0;
+    };
+    let (index, bindings) = index_references_and_bindings(decls);
+
+    let mut warnings = 0;
+    for bind_name in bindings
+    {
+        // The only reference is the binding itself
+        let unused = index.references_of(bind_name).len() <= 1;
+
+        if unused && !bind_name.get_raw_content().starts_with('_')
+        {
+            let (path, source) =
+                source_of(bind_name.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::unused_binding(
+                bind_name,
+                &path,
+                &source,
+                bind_name.get_file_index(),
+            );
+            warnings += 1;
+        }
+    }
+
+    warnings
+}
+
+// A function other than `main` that is never called is probably dead code. Warn about each one.
+//
+// NOTE: There is no way to export a function yet, so every uncalled one is warned about.
+//
+// Returns how many warnings were reported
+pub fn check_unused_functions<'semantic>(
+    ast: &Program,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> usize {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return 0;
+    };
+    let index = index_references(ast);
+
+    let mut warnings = 0;
+    for (signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        let func_name = signature.name();
+        if func_name.is_a(TokenKind::MainKw)
+        {
+            continue;
+        }
+
+        // The name of the signature and of the definition are not calls
+        let declared_at = [func_name.file_range(), definition.name().file_range()];
+        let called = index
+            .references_of(func_name)
+            .iter()
+            .any(|reference| !declared_at.contains(reference));
+
+        if !called
+        {
+            let (path, source) =
+                source_of(func_name.source_id(), path.to_str().unwrap(), cleaned_source);
+            SemanticErrorReporter::unused_function(
+                func_name,
+                &path,
+                &source,
+                func_name.get_file_index(),
+            );
+            warnings += 1;
+        }
+    }
+
+    warnings
+}
+
+// Record where every symbol is declared and used, resolving each use to the innermost binding of
+// its name in scope. Top level declarations are in scope everywhere.
+pub fn index_references(ast: &Program) -> ReferenceIndex {
+    ast.declarations
+        .as_ref()
+        .map_or_else(ReferenceIndex::new, |decls| index_references_and_bindings(decls).0)
+}
+
+// Builds the `ReferenceIndex` of `decls`, along with every name bound by `let` in source order.
+// Both come from one walk, so a binding is found wherever its uses can be.
+fn index_references_and_bindings(decls: &[Declaration]) -> (ReferenceIndex, Vec<&Token>) {
+    let mut index = ReferenceIndex::new();
+    let mut bindings = Vec::new();
+
+    let globals = decls.iter().map(Declaration::name).collect::<Vec<_>>();
+    for global in &globals
+    {
+        index.insert_definition(global);
+    }
+    let mut scopes = vec![globals];
+
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        let mut refs =
+            References { index: &mut index, scopes: &mut scopes, bindings: &mut bindings };
+        refs.use_name(definition.name());
+
+        let params = definition.params().unwrap_or_default();
+        refs.scopes.push(Vec::new());
+        params.iter().for_each(|param| refs.declare(param));
+
+        refs.block(definition.block());
+        refs.scopes.pop();
+    }
+
+    (index, bindings)
+}
+
+// Walks a function body, filling in a `ReferenceIndex`
+struct References<'ast, 'walk> {
+    index: &'walk mut ReferenceIndex,
+    // Bindings in scope, innermost scope last
+    scopes: &'walk mut Vec<Vec<&'ast Token>>,
+    // Names bound by `let`, in source order
+    bindings: &'walk mut Vec<&'ast Token>,
+}
+
+impl<'ast> References<'ast, '_> {
+    fn declare(&mut self, name: &'ast Token) {
+        self.index.insert_definition(name);
+        self.scopes.last_mut().expect("a scope is always open").push(name);
+    }
+
+    // Names that do not resolve (e.g misspelled ones) are not recorded
+    fn use_name(&mut self, used: &Token) {
+        let declared_by = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|declared| declared.get_raw_content() == used.get_raw_content());
+
+        if let Some(declared_by) = declared_by
+        {
+            self.index.insert_reference(declared_by, used);
+        }
+    }
+
+    fn block(&mut self, block: &'ast Block) {
+        self.scopes.push(Vec::new());
+
+        for stmt in block.statements().unwrap_or_default()
+        {
+            self.statement(stmt);
+        }
+        if let Some(expr) = block.expression()
+        {
+            self.expression(expr);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stmt: &'ast Statement) {
+        match stmt
+        {
+            // The new binding is not in scope on its own right hand side
+            Statement::VarBindingInit { bind_name, rhs, .. } =>
+            {
+                let exprs = match rhs
+                {
+                    RValue::Expr(expr) => std::slice::from_ref(expr),
+                    RValue::List(elems) => elems.as_slice(),
+                    RValue::Struct((name, fields)) | RValue::FuncCall((name, fields)) =>
+                    {
+                        self.use_name(name);
+                        fields.as_slice()
+                    }
+                };
+                exprs.iter().flatten().for_each(|expr| self.expression(expr));
+
+                self.declare(bind_name);
+                self.bindings.push(bind_name);
+            }
+            Statement::VarBindingMut { bind_name, expr } =>
+            {
+                self.use_name(bind_name);
+                self.expression(expr);
+            }
+            Statement::Selection { if_comp, elif_comp, .. } =>
+            {
+                self.expression(if_comp.bool_expr());
+                if let Some(elif) = elif_comp
+                {
+                    self.expression(elif.bool_expr());
+                }
+                stmt.body_blocks().into_iter().for_each(|block| self.block(block));
+            }
+            Statement::IndefiniteLoop { expr, block } =>
+            {
+                self.expression(expr);
+                self.block(block);
+            }
+            // The index of a `for` loop is bound inside of its body
+            Statement::DefiniteLoop { index_name, block, .. } =>
+            {
+                self.scopes.push(Vec::new());
+                self.declare(index_name);
+                self.block(block);
+                self.scopes.pop();
+            }
+            Statement::FuncCall { name, args } =>
+            {
+                self.use_name(name);
+                args.iter().flatten().for_each(|arg| self.expression(arg));
+            }
+            Statement::Match { scrutinee, arms } =>
+            {
+                self.expression(scrutinee);
+                arms.iter().for_each(|(_variant, block)| self.block(block));
+            }
+            Statement::Expr(expr) => self.expression(expr),
+            Statement::Break { .. } | Statement::Continue { .. } => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &'ast Expression) {
+        match expr
+        {
+            Expression::Atom(tok) if tok.is_a(TokenKind::Ident) => self.use_name(tok),
+            Expression::Atom(_) | Expression::Literal(..) => {}
+            Expression::Cons(head, rest) => match head.get_token_kind()
+            {
+                // Field names, cast types and choice variants are not symbols in scope
+                TokenKind::RecordDot | TokenKind::AsKw => self.expression(&rest[0]),
+                TokenKind::TQualifer => {}
+                kind =>
+                {
+                    // Call of a function by name (e.g `f(x)`)
+                    if kind == TokenKind::Ident
+                    {
+                        self.use_name(head);
+                    }
+                    rest.iter().for_each(|operand| self.expression(operand));
+                }
+            },
+            Expression::Block(_, block) => self.block(block),
+            Expression::If { cond, then_block, else_block, .. } =>
+            {
+                self.expression(cond);
+                self.block(then_block);
+                self.block(else_block);
+            }
+        }
+    }
+}
+
+// Bindings without a type hint start out as `Type::Undetermined`. Give each one the type of its
+// right hand side where that type follows from literals, casts, lists, structures or the return
+// type of a called function.
+pub fn resolve_undetermined_types(st: &mut SymbolTable, ast: &Program) {
+    let Some(decls) = ast.declarations.as_ref()
+    else
+    {
+        return;
+    };
+
+    // Tuple is `(func_name, ret_ty)`
+    let ret_tys = decls
+        .iter()
+        .filter_map(Declaration::as_function)
+        .filter_map(|(signature, _)| {
+            let ret_ty = signature.ty_ret().and_then(prim_of_type_tok)?;
+            Some((signature.name().get_raw_content(), Type::Prim(ret_ty)))
+        })
+        .collect::<Vec<_>>();
+
+    let choices = choices_of(decls);
+
+    for (_signature, definition) in decls.iter().filter_map(Declaration::as_function)
+    {
+        resolve_block_types(definition.block(), &ret_tys, &choices, st);
+    }
+}
+
+fn resolve_block_types(
+    block: &Block,
+    ret_tys: &[(&str, Type)],
+    choices: &[(&Token, &[Token])],
+    st: &mut SymbolTable,
+) {
+    for stmt in block.statements().unwrap_or_default()
+    {
+        if let Statement::VarBindingInit { bind_name, ty_hint, rhs } = stmt
+        {
+            // A binding holding a choice also records which choice it is
+            let choice = choice_of_binding(ty_hint.as_ref(), rhs, choices);
+            let node = st
+                .declared_by_mut(bind_name)
+                .filter(|node| *node.ty() == Type::Undetermined);
+
+            if let Some((node, choice_name)) = node.zip(choice)
+            {
+                node.refine_sym_kind_to(SymbolKind::ChoiceVar);
+                node.refine_sym_ty_to(Type::Choice);
+                node.refine_sym_ty_name_to(choice_name.clone());
+            }
+
+            let hint = ty_hint.as_ref().and_then(|TypeTok(tok)| prim_of_type_tok(tok));
+            let ty = infer_rvalue_type(rhs, hint, ret_tys);
+
+            let node = st
+                .declared_by_mut(bind_name)
+                .filter(|node| *node.ty() == Type::Undetermined);
+
+            if let Some(node) = node.filter(|_| ty != Type::Undetermined)
+            {
+                node.refine_sym_kind_to(match ty
+                {
+                    Type::List(_) => SymbolKind::ListVar,
+                    Type::Struct => SymbolKind::StructVar,
+                    _ => SymbolKind::PrimVar,
+                });
+                node.refine_sym_ty_to(ty);
+            }
+        }
+
+        for nested in stmt.blocks()
+        {
+            resolve_block_types(nested, ret_tys, choices, st);
+        }
+    }
+
+    for nested in block.expression_blocks()
+    {
+        resolve_block_types(nested, ret_tys, choices, st);
+    }
+}
+
+// The choice a binding holds a value of, going by its type hint (e.g `c :: Color`) or else its
+// right hand side (e.g `Red` or `Color::Red`)
+fn choice_of_binding<'ast>(
+    ty_hint: Option<&TypeTok>,
+    rhs: &RValue,
+    choices: &[(&'ast Token, &[Token])],
+) -> Option<&'ast Token> {
+    let named = |name: &Token| {
+        choices.iter().map(|(choice_name, _)| *choice_name).find(|choice_name| *choice_name == name)
+    };
 
+    if let Some(TypeTok(hint)) = ty_hint
+    {
+        return named(hint);
+    }
+
+    match rhs
+    {
+        RValue::Expr(Some(Expression::Atom(variant))) => choices
+            .iter()
+            .find(|(_, variants)| variants.contains(variant))
+            .map(|(choice_name, _)| *choice_name),
+        RValue::Expr(Some(Expression::Cons(head, rest))) if head.is_a(TokenKind::TQualifer) =>
+        {
+            match rest.first()
+            {
+                Some(Expression::Atom(choice_name)) => named(choice_name),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn infer_rvalue_type(rhs: &RValue, hint: Option<Primitve>, ret_tys: &[(&str, Type)]) -> Type {
+    match (rhs, hint)
+    {
+        // The type hint of a list names the type of its elements
+        (RValue::List(_), Some(hint)) => Type::List(hint),
+        (RValue::List(elems), None) =>
+        {
+            let first_elem = elems.first().and_then(Option::as_ref);
+
+            match first_elem.map(|expr| infer_expr_type(expr, ret_tys))
+            {
+                Some(Type::Prim(prim)) => Type::List(prim),
+                _ => Type::Undetermined,
+            }
+        }
+        (RValue::Struct(_), _) => Type::Struct,
+        (_, Some(hint)) => Type::Prim(hint),
+        (RValue::Expr(Some(expr)), None) => infer_expr_type(expr, ret_tys),
+        (RValue::FuncCall((name, _)), None) => ret_ty_of(name, ret_tys),
+        (RValue::Expr(None), None) => Type::Undetermined,
+    }
+}
+
+fn infer_expr_type(expr: &Expression, ret_tys: &[(&str, Type)]) -> Type {
+    use TokenKind::*;
+
+    match expr
+    {
+        // A type suffix (e.g `5f`) says what the literal is meant to be
+        Expression::Literal(tok, value) => match (tok.num_suffix(), value)
+        {
+            (Some(NumSuffix::Int), _) | (None, LitValue::Int(_)) => Type::Prim(Primitve::U32),
+            (Some(NumSuffix::Float), _) | (None, LitValue::Float(_)) => Type::Prim(Primitve::F32),
+            (_, LitValue::Bool(_)) => Type::Prim(Primitve::Bool),
+            (_, LitValue::Char(_)) => Type::Prim(Primitve::Text),
+        },
+        Expression::Atom(_) => Type::Undetermined,
+        Expression::Cons(head, rest) => match head.get_token_kind()
+        {
+            Eq | NEq | Lt | Lte | Gt | Gte | AndKw | OrKw | Not => Type::Prim(Primitve::Bool),
+            AsKw => match rest.get(1)
+            {
+                Some(Expression::Atom(ty_tok)) =>
+                {
+                    prim_of_type_tok(ty_tok).map_or(Type::Undetermined, Type::Prim)
+                }
+                _ => Type::Undetermined,
+            },
+            // Arithmetic keeps the type of its operands, if they agree
+            Plus | Minus | Mul | Div =>
+            {
+                let mut operand_tys = rest.iter().map(|operand| infer_expr_type(operand, ret_tys));
+                let first_ty = operand_tys.next().unwrap_or(Type::Undetermined);
+
+                if operand_tys.all(|ty| ty == first_ty)
+                {
+                    first_ty
+                }
+                else
+                {
+                    Type::Undetermined
+                }
+            }
+            Ident => ret_ty_of(head, ret_tys),
+            _ => Type::Undetermined,
+        },
+        Expression::Block(_, block) => block_value_type(block, ret_tys),
+        // Both branches have to agree
+        Expression::If { then_block, else_block, .. } =>
+        {
+            let branch_ty = |block: &Block| block_value_type(block, ret_tys);
+            let then_ty = branch_ty(then_block);
+
+            if then_ty == branch_ty(else_block)
+            {
+                then_ty
+            }
+            else
+            {
+                Type::Undetermined
+            }
+        }
+    }
+}
+
+// Type of the value of a block, which is the type of its trailing expression
+fn block_value_type(block: &Block, ret_tys: &[(&str, Type)]) -> Type {
+    block.expression().map_or(Type::Undetermined, |expr| infer_expr_type(expr, ret_tys))
+}
+
+// Return type of the function called `name`
+fn ret_ty_of(name: &Token, ret_tys: &[(&str, Type)]) -> Type {
+    ret_tys
+        .iter()
+        .find(|(func_name, _)| *func_name == name.get_raw_content())
+        .map_or(Type::Undetermined, |(_, ret_ty)| ret_ty.clone())
+}
+
+// Primitive named by a type `Token` (e.g `int`)
+fn prim_of_type_tok(tok: &Token) -> Option<Primitve> {
+    match Type::from_type_token(tok)
+    {
+        Type::Prim(prim) => Some(prim),
+        _ => None,
+    }
+}
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use lexer::diagnostics::{self, Diagnostic};
+    use lexer::lexer::Lexer;
+    use parser::ast_parser::Parser;
+    use std::ops::Range;
+    use std::path::Path;
+
+    fn parse_program(source: &str) -> Program {
+        parse_with_symbols(source).1
+    }
+
+    // Same as `parse_program`, along with the `SymbolTable` the parser filled in
+    fn parse_with_symbols(source: &str) -> (SymbolTable, Program) {
+        let tokens = Lexer::new("test.az", source).lex(false).unwrap();
+        let mut st = SymbolTable::new();
+        let ast = Parser::new(tokens, Path::new("test.az"), source).parse(false, &mut st).unwrap();
+
+        (st, ast)
+    }
+
     #[test]
     fn it_works() {
     }
+
+    #[test]
+    fn read_function_name() {
+        let ast = parse_program("add_two :: (int, int) -> int\nadd_two n1 n2 = { n1 + n2 }\n");
+        let decls = ast.declarations.as_ref().unwrap();
+        let (signature, definition) = decls[0].as_function().unwrap();
+
+        assert_eq!(decls[0].name().get_raw_content(), "add_two");
+        assert_eq!(signature.name().get_raw_content(), "add_two");
+        assert_eq!(signature.ty_list().unwrap().len(), 2);
+        assert_eq!(signature.ty_ret().unwrap().get_raw_content(), "int");
+        assert_eq!(definition.params().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn read_statement_bind_names() {
+        let ast = parse_program("main :: ()\nmain = { let x <- 5; while x > 0 { x <- x - 1; } }\n");
+        let decls = ast.declarations.as_ref().unwrap();
+        let (_, definition) = decls[0].as_function().unwrap();
+        let stmts = definition.block().statements().unwrap();
+
+        assert_eq!(stmts[0].bind_name().unwrap().get_raw_content(), "x");
+        assert!(stmts[1].bind_name().is_none());
+
+        let loop_body = stmts[1].blocks()[0].statements().unwrap();
+        assert_eq!(loop_body[0].bind_name().unwrap().get_raw_content(), "x");
+    }
+
+    #[test]
+    fn break_inside_loop() {
+        let source = "main :: ()\nmain = { while true { if true { break; } continue; } }\n";
+        let ast = parse_program(source);
+
+        assert!(check_for_loop_control_outside_loop(&ast, Path::new("test.az"), source).is_ok());
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        let source = "main :: ()\nmain = { let x <- 1; break; }\n";
+        let ast = parse_program(source);
+
+        assert!(check_for_loop_control_outside_loop(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "main :: ()\nmain = { if true { continue; } }\n";
+        let ast = parse_program(source);
+
+        assert!(check_for_loop_control_outside_loop(&ast, Path::new("test.az"), source).is_err());
+
+        // Inside of a block used as a value, or the condition of a loop
+        let source = "main :: ()\nmain = { let x <- { break; 1 }; }\n";
+        let ast = parse_program(source);
+
+        assert!(check_for_loop_control_outside_loop(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "main :: ()\nmain = { while { continue; true } { } }\n";
+        let ast = parse_program(source);
+
+        assert!(check_for_loop_control_outside_loop(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "main :: ()\nmain = { while true { let x <- { break; 1 }; } }\n";
+        let ast = parse_program(source);
+
+        assert!(check_for_loop_control_outside_loop(&ast, Path::new("test.az"), source).is_ok());
+    }
+
+    // Run `check_match_exhaustiveness` over `source`, once the types of its bindings are known
+    fn check_matches(source: &str) -> (Result<(), SemanticError>, Vec<Diagnostic>) {
+        let (mut st, ast) = parse_with_symbols(source);
+        resolve_undetermined_types(&mut st, &ast);
+
+        let path = Path::new("test.az");
+        diagnostics::collected(|| check_match_exhaustiveness(&st, &ast, path, source))
+    }
+
+    #[test]
+    fn exhaustive_match() {
+        let source = "Color :: choice { Red, Blue }\n\
+                      main :: ()\n\
+                      main = { match c { Red => { x <- 1; }, Blue => { x <- 2; } } }\n";
+
+        assert!(check_matches(source).0.is_ok());
+    }
+
+    #[test]
+    fn match_missing_variant() {
+        let source = "Color :: choice { Red, Green, Blue }\n\
+                      main :: ()\n\
+                      main = { while true { match c { Red => { x <- 1; }, Blue => { x <- 2; } } } }\n";
+
+        let (result, reported) = check_matches(source);
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 6);
+    }
+
+    #[test]
+    fn match_reported_against_its_own_file() {
+        let source = "Color :: choice { Red, Blue }\n\
+                      main :: ()\n\
+                      main = { let c <- Red; match c { Red => { } } }\n";
+
+        // Merged in from `b.az` while compiling `a.az`
+        let source_id = diagnostics::register_source("b.az", source);
+        let tokens = Lexer::new("b.az", source).with_source_id(source_id).lex(false).unwrap();
+        let mut st = SymbolTable::new();
+        let ast = Parser::new(tokens, Path::new("b.az"), source).parse(false, &mut st).unwrap();
+        resolve_undetermined_types(&mut st, &ast);
+
+        let path = Path::new("a.az");
+        let (result, reported) =
+            diagnostics::collected(|| check_match_exhaustiveness(&st, &ast, path, "main :: ()\n"));
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 6);
+        assert_eq!((reported[0].file.as_str(), reported[0].line), ("b.az", 3));
+    }
+
+    #[test]
+    fn match_over_choice_of_scrutinee() {
+        // The first arm names a variant of both choices, but `l` holds a `Light`
+        let source = "Color :: choice { Red, Blue }\n\
+                      Light :: choice { Red, Green }\n\
+                      main :: ()\n\
+                      main = { let l :: Light <- Green; match l { Red => { }, Green => { } } }\n";
+        assert!(check_matches(source).0.is_ok());
+
+        let source = "Color :: choice { Red, Blue }\n\
+                      Light :: choice { Red, Green }\n\
+                      main :: ()\n\
+                      main = { let l <- Light::Green; match l { Red => { }, Blue => { } } }\n";
+        let (result, reported) = check_matches(source);
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 15);
+        assert!(reported[0].message.contains("`Blue` is not a variant of the choice `Light`"));
+    }
+
+    #[test]
+    fn match_arm_with_unknown_variant() {
+        let source = "Color :: choice { Red, Blue }\n\
+                      main :: ()\n\
+                      main = { let c <- Red; match c { Bleu => { }, Red => { }, Blue => { } } }\n";
+
+        let (result, reported) = check_matches(source);
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 15);
+
+        // Without knowing the type of the scrutinee, the other arms still say which choice it is
+        let source = "Color :: choice { Red, Blue }\n\
+                      f :: (int)\n\
+                      f c = { match c { Bleu => { }, Red => { }, Blue => { } } }\n";
+
+        let (result, reported) = check_matches(source);
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 15);
+    }
+
+    #[test]
+    fn value_selection_needs_else() {
+        let source = "sign :: (int) -> int\nsign n = { if n < 0 { x <- 1; } }\n";
+        let ast = parse_program(source);
+        assert!(check_selection_exhaustiveness(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "sign :: (int) -> int\nsign n = { if n < 0 { x <- 1; } else { x <- 2; } }\n";
+        let ast = parse_program(source);
+        assert!(check_selection_exhaustiveness(&ast, Path::new("test.az"), source).is_ok());
+    }
+
+    #[test]
+    fn selection_reported_against_its_own_file() {
+        let source = "sign :: (int) -> int\nsign n = { if n < 0 { x <- 1; } }\n";
+
+        // Merged in from `b.az` while compiling `a.az`
+        let source_id = diagnostics::register_source("b.az", source);
+        let tokens = Lexer::new("b.az", source).with_source_id(source_id).lex(false).unwrap();
+        let ast = Parser::new(tokens, Path::new("b.az"), source)
+            .parse(false, &mut SymbolTable::new())
+            .unwrap();
+
+        let path = Path::new("a.az");
+        let (result, reported) =
+            diagnostics::collected(|| check_selection_exhaustiveness(&ast, path, "main :: ()\n"));
+        assert!(result.is_err());
+        assert_eq!((reported[0].file.as_str(), reported[0].line), ("b.az", 2));
+    }
+
+    #[test]
+    fn statement_selection_may_skip_else() {
+        // Followed by the block's value
+        let source = "sign :: (int) -> int\nsign n = { if n < 0 { x <- 1; } 0 }\n";
+        let ast = parse_program(source);
+        assert!(check_selection_exhaustiveness(&ast, Path::new("test.az"), source).is_ok());
+
+        // Function does not return a value
+        let source = "main :: ()\nmain = { if true { x <- 1; } }\n";
+        let ast = parse_program(source);
+        assert!(check_selection_exhaustiveness(&ast, Path::new("test.az"), source).is_ok());
+    }
+
+    #[test]
+    fn resolve_binding_types() {
+        let source = "one :: () -> float\none = { 1.0 }\n\
+                      main :: ()\n\
+                      main = { let x <- 5; let xs <- [1]; let f <- one(); let b <- x < 2; \
+                      let c <- 2 as float; let y <- x; let hinted :: bool <- y; \
+                      let suffixed <- 5f; let int_suffixed <- 5i; }\n";
+        let (mut st, ast) = parse_with_symbols(source);
+
+        resolve_undetermined_types(&mut st, &ast);
+
+        let ty_of = |name: &str| {
+            let node = st.iter().find(|node| node.name().get_raw_content() == name).unwrap();
+            (node.ty().clone(), node.kind())
+        };
+        assert_eq!(ty_of("x"), (Type::Prim(Primitve::U32), SymbolKind::PrimVar));
+        assert_eq!(ty_of("xs"), (Type::List(Primitve::U32), SymbolKind::ListVar));
+        assert_eq!(ty_of("f"), (Type::Prim(Primitve::F32), SymbolKind::PrimVar));
+        assert_eq!(ty_of("b"), (Type::Prim(Primitve::Bool), SymbolKind::PrimVar));
+        assert_eq!(ty_of("c"), (Type::Prim(Primitve::F32), SymbolKind::PrimVar));
+        assert_eq!(ty_of("hinted"), (Type::Prim(Primitve::Bool), SymbolKind::PrimVar));
+        assert_eq!(ty_of("suffixed").0, Type::Prim(Primitve::F32));
+        assert_eq!(ty_of("int_suffixed").0, Type::Prim(Primitve::U32));
+
+        // Variables are not looked up yet
+        assert_eq!(ty_of("y").0, Type::Undetermined);
+    }
+
+    #[test]
+    fn binding_refers_to_itself() {
+        let source = "main :: ()\nmain = { let x <- x + 1; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "main :: ()\nmain = { let xs <- [1, 0 + xs]; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+
+        // Bound in a block that already ended
+        let source = "main :: ()\nmain = { if true { let x <- 1; } let x <- x; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+
+        // Used inside of a block that gives the value
+        let source = "main :: ()\nmain = { let x <- { x + 1 }; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "main :: ()\nmain = { let x <- { let y <- 1; while true { y <- x; } y }; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+
+        // Used in a branch of an `if` that gives the value
+        let source = "main :: ()\nmain = { let x <- if true { x } else { 1 }; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+
+        let source = "main :: ()\nmain = { let x <- if true { 1 } else { x * 2 }; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_err());
+    }
+
+    #[test]
+    fn binding_refers_to_earlier_binding() {
+        // Updating an existing binding
+        let source = "main :: ()\nmain = { let x <- 1; x <- x + 1; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_ok());
+
+        // Shadowing a parameter, an outer binding or a loop index
+        let source = "f :: (int) -> int\n\
+                      f x = { let x <- x + 1; while true { let x <- x * 2; } \
+                      for i in 0..3 { let i <- i; } x }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_ok());
+
+        // A field that happens to share the name
+        let source = "main :: ()\nmain = { let p <- 1; let x <- p.x; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_ok());
+
+        // Bound inside of the block that gives the value
+        let source = "main :: ()\nmain = { let x <- { let x <- 1; x + 1 }; }\n";
+        let ast = parse_program(source);
+        assert!(check_self_referential_bindings(&ast, Path::new("test.az"), source).is_ok());
+    }
+
+    #[test]
+    fn for_bounds() {
+        let check = |body: &str| {
+            let source = format!("main :: ()\nmain = {{ {body} }}\n");
+            let ast = parse_program(&source);
+
+            diagnostics::collected(|| check_for_bounds(&ast, Path::new("test.az"), &source))
+        };
+
+        let (result, reported) = check("for i in 5..1 { }");
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 12);
+
+        let (result, reported) = check("while true { for i in 0..2.5 { } }");
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 13);
+
+        assert!(check("for i in 0..5 { for j in 3..3 { } }").0.is_ok());
+    }
+
+    #[test]
+    fn unused_bindings() {
+        let source = "main :: () -> int\n\
+                      main = { let unused <- 1; let _ignored <- 2; let used <- 3; used }\n";
+        let ast = parse_program(source);
+
+        let (warnings, reported) = diagnostics::collected(|| {
+            check_unused_bindings(&ast, Path::new("test.az"), source)
+        });
+
+        assert_eq!(warnings, 1);
+        assert!(reported[0].message.contains("`unused` is bound but never used"));
+    }
+
+    #[test]
+    fn unused_bindings_in_value_blocks() {
+        let source = "main :: () -> int\n\
+                      main = { let z <- { let dead <- 1; 2 }; \
+                      let w <- if true { let gone <- 3; 4 } else { 5 }; z + w }\n";
+        let ast = parse_program(source);
+
+        let (warnings, reported) = diagnostics::collected(|| {
+            check_unused_bindings(&ast, Path::new("test.az"), source)
+        });
+
+        assert_eq!(warnings, 2);
+        assert!(reported[0].message.contains("`dead` is bound but never used"));
+        assert!(reported[1].message.contains("`gone` is bound but never used"));
+    }
+
+    #[test]
+    fn unused_functions() {
+        let source = "used :: () -> int\nused = { 1 }\n\
+                      unused :: (int) -> int\nunused x = { x }\n\
+                      main :: () -> int { used() }\n";
+        let ast = parse_program(source);
+
+        let (warnings, reported) = diagnostics::collected(|| {
+            check_unused_functions(&ast, Path::new("test.az"), source)
+        });
+
+        assert_eq!(warnings, 1);
+        assert!(reported[0].message.contains("`unused` is declared but never called"));
+    }
+
+    #[test]
+    fn references_resolve_to_their_binding() {
+        let source = "main :: () -> int\n\
+                      main = { let x <- 1; while true { let x <- 2; } let y <- x; x + y }\n";
+        let ast = parse_program(source);
+        let index = index_references(&ast);
+
+        let decl = &ast.declarations.as_ref().unwrap()[0];
+        let stmts = decl.as_function().unwrap().1.block().statements().unwrap();
+        let outer_x = stmts[0].bind_name().unwrap();
+        let inner_x = stmts[1].blocks()[0].statements().unwrap()[0].bind_name().unwrap();
+
+        // The outer `x` is defined once and used twice. The `x` bound inside of the loop is a
+        // symbol of its own.
+        assert_eq!(index.references_of(outer_x), [31..32, 75..76, 78..79]);
+        assert_eq!(index.references_of(inner_x), vec![Range { start: 56, end: 57 }]);
+
+        // The `main` of the definition refers to the one of the signature
+        assert_eq!(index.references_of(decl.name()), [0..4, 18..22]);
+    }
+
+    #[test]
+    fn inner_binding_shadows_outer() {
+        let source = "f :: (int) -> int\n\
+                      f n = { let x <- 1; while true { let x <- 2; let n <- 3; } \
+                      for i in 0..3 { let i <- 4; } x }\n";
+        let ast = parse_program(source);
+
+        assert_eq!(check_shadowing(&ast, Path::new("test.az"), source), 3);
+
+        // Inside of a block used as a value
+        let source = "main :: ()\nmain = { let x <- 1; let y <- { let x <- 2; x }; }\n";
+        let ast = parse_program(source);
+
+        assert_eq!(check_shadowing(&ast, Path::new("test.az"), source), 1);
+
+        // The binding being made is not in scope yet on its right hand side
+        let source = "main :: ()\nmain = { let y <- { let y <- 2; y }; }\n";
+        let ast = parse_program(source);
+
+        assert_eq!(check_shadowing(&ast, Path::new("test.az"), source), 0);
+    }
+
+    #[test]
+    fn sibling_bindings_do_not_shadow() {
+        // Each block only sees its own `x`
+        let source = "main :: ()\n\
+                      main = { if true { let x <- 1; } else { let x <- 2; } let x <- 3; }\n";
+        let ast = parse_program(source);
+
+        assert_eq!(check_shadowing(&ast, Path::new("test.az"), source), 0);
+    }
+
+    #[test]
+    fn binding_repeated_in_scope() {
+        use symbol_table::check_for_dup_bindings_in_scope;
+
+        let check = |source: &str| {
+            let (st, _) = parse_with_symbols(source);
+
+            check_for_dup_bindings_in_scope(&st, Path::new("test.az"), source)
+        };
+
+        assert!(check("main :: ()\nmain = { let x <- 1; let x <- 2; }\n").is_err());
+        assert!(check("main :: ()\nmain = { while true { let x <- 1; let x <- [2]; } }\n")
+            .is_err());
+
+        // Nested and sibling blocks, or other functions
+        assert!(check("main :: ()\nmain = { let x <- 1; while true { let x <- 2; } }\n").is_ok());
+        assert!(check("main :: ()\nmain = { if true { let x <- 1; } else { let x <- 2; } }\n")
+            .is_ok());
+        assert!(check("f :: ()\nf = { let x <- 1; }\ng :: ()\ng = { let x <- 1; }\n").is_ok());
+    }
+
+    #[test]
+    fn recursive_structs() {
+        let check = |source: &str| {
+            check_recursive_structs(&parse_program(source), Path::new("test.az"), source)
+        };
+
+        assert!(check("A :: structure { x :: int, a :: A }\n").is_err());
+        assert!(check("A :: structure { b :: B }\nB :: structure { a :: A }\n").is_err());
+        assert!(check("A :: structure { b :: B }\n\
+                       B :: structure { c :: C }\n\
+                       C :: structure { b :: B }\n")
+            .is_err());
+
+        // A chain that ends, and a structure used by two others
+        assert!(check("A :: structure { b :: B, c :: C }\n\
+                       B :: structure { c :: C, k :: Kind }\n\
+                       C :: structure { x :: int }\n\
+                       Kind :: choice { One, Two }\n")
+            .is_ok());
+    }
+
+    #[test]
+    fn param_annotations() {
+        let check = |source: &str| {
+            let (st, ast) = parse_with_symbols(source);
+
+            diagnostics::collected(|| {
+                check_param_annotations(&st, &ast, Path::new("test.az"), source)
+            })
+        };
+
+        // Typed inline to match the signature, or only partly typed
+        assert!(check("f :: (int, bool) -> int\nf (x :: int, y :: bool) = { x }\n").0.is_ok());
+        assert!(check("f :: (int, bool) -> int\nf (x, y :: bool) = { x }\n").0.is_ok());
+
+        let (result, reported) = check("f :: (int, bool) -> int\nf (x :: int, y :: int) = { x }\n");
+        assert!(result.is_err());
+        assert_eq!(reported[0].code, 14);
+        assert!(reported[0].message.contains("`y` is given type `int`"));
+
+        // A function input can't be typed as a primitive
+        let (result, _) = check("f :: ((int) -> int)\nf (g :: int) = { }\n");
+        assert!(result.is_err());
+
+        // A type named inline must be declared, even where the signature does not type it
+        let (result, reported) = check("f :: ()\nf (p :: Persn) = { }\n");
+        assert!(result.is_err());
+        assert!(reported[0].message.contains("Unknown Type"));
+    }
+
+    #[test]
+    fn struct_field_types() {
+        let check = |source: &str| {
+            let (st, ast) = parse_with_symbols(source);
+
+            check_struct_field_types(&st, &ast, Path::new("test.az"), source)
+        };
+
+        // Declared before or after the structure using it
+        assert!(check("Person :: structure { age :: int, pet :: Animal }\n\
+                       Animal :: choice { Cat, Dog }\n\
+                       Pair :: structure { first :: Person, second :: Person }\n")
+            .is_ok());
+
+        assert!(check("Person :: structure { age :: int, friend :: Persn }\n").is_err());
+    }
 }
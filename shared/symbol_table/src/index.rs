@@ -0,0 +1,107 @@
+//! Indexes over the symbols of a program for tooling (e.g jump-to-definition and rename)
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use lexer::token::Token;
+
+/// Maps the name of each function, structure and choice to the `Token` that declared it
+#[derive(Debug, Default)]
+pub struct DefinitionIndex {
+    definitions: HashMap<String, Token>,
+}
+
+impl DefinitionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `name` declared a function, structure or choice. A name declared more than
+    /// once keeps its first declaration, since the rest are reported as duplicates.
+    pub fn insert(&mut self, name: &Token) {
+        self.definitions
+            .entry(name.get_raw_content().to_string())
+            .or_insert_with(|| name.clone());
+    }
+
+    /// `Token` that declared `name`, if anything did
+    pub fn definition_of(&self, name: &str) -> Option<&Token> {
+        self.definitions.get(name)
+    }
+
+    /// Char offsets into the source file of the name that declared `name`
+    pub fn location_of(&self, name: &str) -> Option<Range<usize>> {
+        self.definition_of(name).map(Token::file_range)
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
+
+/// Identifies a symbol by the `Token` that declared it, so two bindings with the same name in
+/// different scopes are different symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolId {
+    source_id: usize,
+    file_index: usize,
+}
+
+impl SymbolId {
+    /// Id of the symbol that `declared_by` declared
+    pub fn of(declared_by: &Token) -> Self {
+        Self {
+            source_id: declared_by.source_id(),
+            file_index: declared_by.get_file_index(),
+        }
+    }
+}
+
+/// Maps each symbol to every place its name is written: where it was declared first, then
+/// where it is used, in source order
+#[derive(Debug, Default)]
+pub struct ReferenceIndex {
+    references: HashMap<SymbolId, Vec<Range<usize>>>,
+}
+
+impl ReferenceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `name` declares a symbol
+    pub fn insert_definition(&mut self, name: &Token) {
+        self.references.entry(SymbolId::of(name)).or_insert_with(|| vec![name.file_range()]);
+    }
+
+    /// Record that `used` refers to the symbol `declared_by` declared
+    pub fn insert_reference(&mut self, declared_by: &Token, used: &Token) {
+        self.insert_definition(declared_by);
+
+        if let Some(references) = self.references.get_mut(&SymbolId::of(declared_by))
+        {
+            references.push(used.file_range());
+        }
+    }
+
+    /// Char offsets of the declaration and every use of the symbol `declared_by` declared
+    pub fn references_of(&self, declared_by: &Token) -> &[Range<usize>] {
+        self.references.get(&SymbolId::of(declared_by)).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&SymbolId, &Vec<Range<usize>>)> {
+        self.references.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.references.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+}
@@ -1,12 +1,16 @@
 #![allow(dead_code)]
 
-use ariadne::{Label, Report, ReportKind, Source};
+pub mod index;
+
+use lexer::diagnostics::{report_error, report_warning, source_of};
 use thiserror::Error;
 
-use std::{cell::Cell, path::Path};
+use std::{cell::Cell, fmt, path::Path};
 
 use lexer::token::{Token, TokenKind};
 
+use crate::index::DefinitionIndex;
+
 
 // `SemanticError` represents a general failure of Azalea program's semantics
 #[derive(Debug, Error)]
@@ -25,18 +29,14 @@ impl SemanticErrorReporter {
             "funcion def `{0:?}` was found more than once.",
             duplicated_func.get_raw_content()
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(4)
-            .with_message("Function Def. Name Repeated (semantic error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(
+            4,
+            "Function Def. Name Repeated (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
     }
 
     pub fn dup_choice_def<'a>(duplicated_choice: &Token, path: &str, source: &str, offset: usize) {
@@ -44,18 +44,14 @@ impl SemanticErrorReporter {
             "choice def `{0:?}` was found more than once.",
             duplicated_choice.get_raw_content()
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(4)
-            .with_message("Choice Def. Name Repeated (semantic error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(
+            4,
+            "Choice Def. Name Repeated (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
     }
 
     pub fn dup_structure_def<'a>(
@@ -68,18 +64,264 @@ impl SemanticErrorReporter {
             "structure def `{0:?}` was found more than once.",
             duplicated_structure.get_raw_content()
         );
-        Report::build(ReportKind::Error, path, offset)
-            .with_code(4)
-            .with_message("Structure Def. Name Repeated (semantic error)")
-            .with_label(
-                Label::new((path, offset..offset))
-                    .with_message("Here")
-                    .with_color(ariadne::Color::Red),
-            )
-            .with_note(note)
-            .finish()
-            .print((path, Source::from(source)))
-            .unwrap();
+        report_error(
+            4,
+            "Structure Def. Name Repeated (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `main = { break; }`
+    pub fn break_outside_loop(control_kw: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` can only be used inside a `while` or `for` loop.",
+            control_kw.get_raw_content()
+        );
+        report_error(
+            5,
+            "Loop Control Outside Loop (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `Color :: choice { Red, Blue }` with `match c { Red => {} }`
+    pub fn non_exhaustive_match(
+        choice_name: &Token,
+        missing_variants: &[&str],
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let missing = missing_variants
+            .iter()
+            .map(|variant| format!("`{variant}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let note = format!(
+            "match over choice `{0}` is missing arms for: {1}",
+            choice_name.get_raw_content(),
+            missing
+        );
+        report_error(
+            6,
+            "Non-Exhaustive Match (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `f :: () -> int` with `f = { if x { 1 } }`
+    pub fn non_exhaustive_selection(path: &str, source: &str, offset: usize) {
+        let note = "This `if` gives the value of the block, so it needs an `else` branch for when \
+                    no condition holds.";
+        report_error(
+            7,
+            "Non-Exhaustive Selection (semantic error)",
+            note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `let x <- x + 1;` where no `x` was bound before
+    pub fn self_reference_in_binding(
+        bind_name: &Token,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!(
+            "`{0}` is used to initialize itself before it exists. Bind `{0}` first, or use \
+             `{0} <- ...` to update an existing binding.",
+            bind_name.get_raw_content()
+        );
+        report_error(
+            8,
+            "Binding Refers To Itself (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `let x <- 1; let x <- 2;`
+    pub fn duplicate_binding_in_scope(
+        bind_name: &Token,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!(
+            "`{0}` is already bound in this scope. Use `{0} <- ...` to update it, or pick \
+             another name.",
+            bind_name.get_raw_content()
+        );
+        report_error(
+            9,
+            "Binding Repeated In Scope (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `Person :: structure { friend :: Persn }`
+    pub fn unknown_type(ty: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` is not a primitive type or a declared structure or choice.",
+            ty.get_raw_content()
+        );
+        report_error(10, "Unknown Type (semantic error)", &note, path, source, offset..offset);
+    }
+
+    // Error example: `A :: structure { b :: B }` with `B :: structure { a :: A }`
+    pub fn recursive_struct(cycle: &[&Token], path: &str, source: &str, offset: usize) {
+        let names = cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|name| format!("`{}`", name.get_raw_content()))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        let note = format!(
+            "These structures contain each other ({names}), so they would take up infinite space."
+        );
+        report_error(
+            11,
+            "Recursive Structure (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `for i in 5..1 {}`
+    pub fn empty_range(low: &Token, high: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}..{1}` counts up from `{0}`, so a loop over it never runs. Swap the bounds or \
+             remove the loop.",
+            low.get_raw_content(),
+            high.get_raw_content()
+        );
+        report_error(12, "Empty Range (semantic error)", &note, path, source, offset..offset);
+    }
+
+    // Error example: `for i in 0..2.5 {}`
+    pub fn non_integer_bound(bound: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` is not an `int`. For-loop bounds must be `int` literals.",
+            bound.get_raw_content()
+        );
+        report_error(
+            13,
+            "Non-Integer Loop Bound (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `f :: (int)` with `f (x :: bool) = {}`
+    pub fn param_type_conflict(
+        param: &Token,
+        inline_ty: &Token,
+        sig_ty: &str,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!(
+            "`{0}` is given type `{1}` here, but the signature says `{2}`. Make them agree or \
+             drop the inline type.",
+            param.get_raw_content(),
+            inline_ty.get_raw_content(),
+            sig_ty
+        );
+        report_error(
+            14,
+            "Conflicting Parameter Type (semantic error)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Error example: `match color { Red => {}, Bleu => {} }` where `Color :: choice { Red, Blue }`
+    pub fn unknown_variant(
+        variant: &Token,
+        choice_name: &Token,
+        path: &str,
+        source: &str,
+        offset: usize,
+    ) {
+        let note = format!(
+            "`{0}` is not a variant of the choice `{1}` being matched on.",
+            variant.get_raw_content(),
+            choice_name.get_raw_content()
+        );
+        report_error(15, "Unknown Variant (semantic error)", &note, path, source, offset..offset);
+    }
+
+    // Warning example: `let x <- 1;` with `while true { let x <- 2; }`
+    pub fn variable_shadows_outer(bind_name: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` shadows a binding of the same name from an outer scope. Rename one of them if \
+             both are needed.",
+            bind_name.get_raw_content()
+        );
+        report_warning(
+            0,
+            "Variable Shadows Outer Binding (semantic warning)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Warning example: `main = { let x <- 1; }`
+    pub fn unused_binding(bind_name: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` is bound but never used. Remove it, or name it `_{0}` if that is on purpose.",
+            bind_name.get_raw_content()
+        );
+        report_warning(
+            1,
+            "Unused Binding (semantic warning)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
+    }
+
+    // Warning example: `helper :: ()` with no `helper()` anywhere
+    pub fn unused_function(func_name: &Token, path: &str, source: &str, offset: usize) {
+        let note = format!(
+            "`{0}` is declared but never called. Remove it if it is not needed.",
+            func_name.get_raw_content()
+        );
+        report_warning(
+            2,
+            "Unused Function (semantic warning)",
+            &note,
+            path,
+            source,
+            offset..offset,
+        );
     }
 }
 
@@ -95,6 +337,25 @@ pub enum SymbolKind {
     FuncParm,
 }
 
+// Way to print a `SymbolKind` using println!()
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self
+        {
+            SymbolKind::FuncCall => "function",
+            SymbolKind::Global => "global",
+            SymbolKind::PrimVar => "variable",
+            SymbolKind::StructVar => "structure variable",
+            SymbolKind::ListVar => "list variable",
+            SymbolKind::ChoiceVar => "choice variable",
+            SymbolKind::ForLoopIndex => "loop index",
+            SymbolKind::FuncParm => "parameter",
+        };
+
+        write!(f, "{kind}")
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Primitve {
     U32,
@@ -113,10 +374,57 @@ pub enum Type {
     Undetermined,
 }
 
+impl Type {
+    /// Type written as `ty_tok` (e.g `int`). Names of structures and choices are only known to be
+    /// one or the other once every declaration is parsed, so they are `Type::Undetermined`.
+    pub fn from_type_token(ty_tok: &Token) -> Self {
+        match ty_tok.get_token_kind()
+        {
+            TokenKind::IntTy => Type::Prim(Primitve::U32),
+            TokenKind::FloatTy => Type::Prim(Primitve::F32),
+            TokenKind::BoolTy => Type::Prim(Primitve::Bool),
+            TokenKind::TextTy => Type::Prim(Primitve::Text),
+            _ => Type::Undetermined,
+        }
+    }
+}
+
+// Way to print a `Primitve` as it is written in source (e.g `int`)
+impl fmt::Display for Primitve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prim = match self
+        {
+            Primitve::U32 => "int",
+            Primitve::F32 => "float",
+            Primitve::Bool => "bool",
+            Primitve::Text => "text",
+        };
+
+        write!(f, "{prim}")
+    }
+}
+
+// Way to print a `Type` using println!()
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self
+        {
+            Type::Prim(prim) => write!(f, "{prim}"),
+            Type::Struct => write!(f, "structure"),
+            Type::Choice => write!(f, "choice"),
+            Type::Func => write!(f, "function"),
+            Type::List(prim) => write!(f, "[{prim}]"),
+            Type::Undetermined => write!(f, "undetermined"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SymbolNode {
     sym_name: Token,
     sym_ty: Type,
+    // Name of the structure or choice the symbol holds a value of (e.g `Color`), once known
+    sym_ty_name: Option<Token>,
     sym_kind: Cell<SymbolKind>,
     sym_scope_depth: usize,
     sym_scope_breath: usize,
@@ -132,12 +440,34 @@ impl SymbolNode {
         SymbolNode {
             sym_name,
             sym_ty: sym_ty.clone(),
+            sym_ty_name: None,
             sym_kind: Cell::new(Self::determine_sym_kind(sym_ty)),
             sym_scope_depth,
             sym_scope_breath,
         }
     }
 
+    /// Node for a binding called `name` whose type is written as `ty` (e.g `int`). See
+    /// `Type::from_type_token` for which types are known up front.
+    pub fn from_binding(name: &Token, ty: &Token, depth: usize, breadth: usize) -> Self {
+        SymbolNode::new(name.clone(), Type::from_type_token(ty), depth, breadth)
+    }
+
+    /// Node for a function called `name` (e.g the name of a `FuncSignature`)
+    ///
+    /// NOTE: Takes the name instead of the `FuncSignature` itself, since the parser crate (where
+    /// the AST lives) depends on this one.
+    pub fn from_function(name: &Token, depth: usize, breadth: usize) -> Self {
+        SymbolNode::new(name.clone(), Type::Func, depth, breadth)
+    }
+
+    // The symbol was declared by the token `name`, and not just another one with the same name
+    fn is_declared_by(&self, name: &Token) -> bool {
+        self.sym_name.get_file_index() == name.get_file_index()
+            && self.sym_name.source_id() == name.source_id()
+            && self.sym_name.symbol() == name.symbol()
+    }
+
     fn determine_sym_kind(sym_ty: Type) -> SymbolKind {
         match sym_ty {
             Type::Prim(_) => SymbolKind::PrimVar,
@@ -152,21 +482,123 @@ impl SymbolNode {
     pub fn refine_sym_kind_to(&self, sym_kind: SymbolKind) {
         self.sym_kind.set(sym_kind);
     }
+
+    /// Replace the type of the symbol once it is known (e.g a `Type::Undetermined` binding)
+    pub fn refine_sym_ty_to(&mut self, sym_ty: Type) {
+        self.sym_ty = sym_ty;
+    }
+
+    /// Record which structure or choice the symbol holds a value of (e.g `Color`)
+    pub fn refine_sym_ty_name_to(&mut self, ty_name: Token) {
+        self.sym_ty_name = Some(ty_name);
+    }
+
+    pub fn name(&self) -> &Token {
+        &self.sym_name
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.sym_ty
+    }
+
+    /// Name of the structure or choice the symbol holds a value of, if known
+    pub fn ty_name(&self) -> Option<&Token> {
+        self.sym_ty_name.as_ref()
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        self.sym_kind.get()
+    }
+
+    pub fn scope_depth(&self) -> usize {
+        self.sym_scope_depth
+    }
+
+    /// One line description of the symbol (e.g `add_two: function (function) @0`)
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {} ({}) @{}",
+            self.sym_name.get_raw_content(),
+            self.sym_ty,
+            self.kind(),
+            self.sym_scope_depth
+        )
+    }
 }
 
 #[derive(Debug)]
 pub struct SymbolTable {
     nodes: Vec<SymbolNode>,
+
+    /// Where each function, structure and choice was declared, filled in as nodes are pushed
+    definitions: DefinitionIndex,
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
-        Self { nodes: Vec::new() }
+        Self { nodes: Vec::new(), definitions: DefinitionIndex::new() }
     }
 
     pub fn push(&mut self, node: SymbolNode) {
+        // Functions, structures and choices are declared at the top level
+        if node.kind() == SymbolKind::Global
+        {
+            self.definitions.insert(node.name());
+        }
+
         self.nodes.push(node)
     }
+
+    /// Move every `SymbolNode` of `other` to the end of this table, leaving `other` empty
+    pub fn append(&mut self, other: &mut SymbolTable) {
+        for node in other.nodes.drain(..)
+        {
+            self.push(node);
+        }
+        other.definitions = DefinitionIndex::new();
+    }
+
+    /// Where each function, structure and choice in the table was declared
+    pub fn definitions(&self) -> &DefinitionIndex {
+        &self.definitions
+    }
+
+    /// Every `SymbolNode` in the order it was pushed
+    pub fn iter(&self) -> impl Iterator<Item = &SymbolNode> {
+        self.nodes.iter()
+    }
+
+    /// Every `SymbolNode` of type `ty`, in the order it was pushed
+    pub fn by_kind<'a>(&'a self, ty: &'a Type) -> impl Iterator<Item = &'a SymbolNode> {
+        self.nodes.iter().filter(move |node| node.sym_ty == *ty)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Find the `SymbolNode` that `name` declared, if any
+    pub fn declared_by(&self, name: &Token) -> Option<&SymbolNode> {
+        self.nodes.iter().find(|node| node.is_declared_by(name))
+    }
+
+    /// Find the `SymbolNode` that `name` declared, if any
+    pub fn declared_by_mut(&mut self, name: &Token) -> Option<&mut SymbolNode> {
+        self.nodes.iter_mut().find(|node| node.is_declared_by(name))
+    }
+}
+
+impl<'a> IntoIterator for &'a SymbolTable {
+    type Item = &'a SymbolNode;
+    type IntoIter = std::slice::Iter<'a, SymbolNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.iter()
+    }
 }
 
 pub fn check_for_dup_funcs_syms<'semantic>(
@@ -174,38 +606,13 @@ pub fn check_for_dup_funcs_syms<'semantic>(
     path: &'semantic Path,
     cleaned_source: &'semantic str,
 ) -> Result<(), SemanticError> {
-    for (idx, symbol_node) in st
-        .nodes
-        .iter()
-        .filter(|sn| sn.sym_ty == Type::Func)
-        .enumerate()
-    {
-        println!("{symbol_node:#?}");
-
-        let curr_node = symbol_node;
-
-        for (jdx, next_node) in st
-            .nodes
-            .iter()
-            .filter(|sn| sn.sym_ty == Type::Func)
-            .enumerate()
-        {
-            if curr_node.sym_name.get_raw_content() == next_node.sym_name.get_raw_content()
-                && idx != jdx
-            {
-                SemanticErrorReporter::dup_function_def(
-                    &curr_node.sym_name,
-                    path.to_str().unwrap(),
-                    cleaned_source,
-                    curr_node.sym_name.get_file_index(),
-                );
-
-                return Err(SemanticError::SemanticFail);
-            }
-        }
-    }
-
-    Ok(())
+    check_for_dup_syms(
+        st,
+        &Type::Func,
+        path,
+        cleaned_source,
+        SemanticErrorReporter::dup_function_def,
+    )
 }
 
 pub fn check_for_dup_choice_syms<'semantic>(
@@ -213,84 +620,211 @@ pub fn check_for_dup_choice_syms<'semantic>(
     path: &'semantic Path,
     cleaned_source: &'semantic str,
 ) -> Result<(), SemanticError> {
-    for (idx, symbol_node) in st
-        .nodes
-        .iter()
-        .filter(|sn| sn.sym_ty == Type::Choice)
-        .enumerate()
-    {
-        println!("{symbol_node:#?}");
+    check_for_dup_syms(
+        st,
+        &Type::Choice,
+        path,
+        cleaned_source,
+        SemanticErrorReporter::dup_choice_def,
+    )
+}
 
-        let curr_node = symbol_node;
+pub fn check_for_dup_structs_syms<'semantic>(
+    st: &SymbolTable,
+    path: &'semantic Path,
+    cleaned_source: &'semantic str,
+) -> Result<(), SemanticError> {
+    check_for_dup_syms(
+        st,
+        &Type::Struct,
+        path,
+        cleaned_source,
+        SemanticErrorReporter::dup_structure_def,
+    )
+}
 
-        for (jdx, next_node) in st
-            .nodes
-            .iter()
-            .filter(|sn| sn.sym_ty == Type::Choice)
-            .enumerate()
+// Report the first symbol of type `ty` whose name is declared again with `report_dup`
+fn check_for_dup_syms(
+    st: &SymbolTable,
+    ty: &Type,
+    path: &Path,
+    cleaned_source: &str,
+    report_dup: fn(&Token, &str, &str, usize),
+) -> Result<(), SemanticError> {
+    // Bindings holding a value of a choice have its type too, but are not declarations of one
+    let declared = || st.by_kind(ty).filter(|node| node.ty_name().is_none());
+
+    for (idx, curr_node) in declared().enumerate()
+    {
+        let is_dup = declared().enumerate().any(|(jdx, next_node)| {
+            curr_node.sym_name.symbol() == next_node.sym_name.symbol() && idx != jdx
+        });
+
+        if is_dup
         {
-            if curr_node.sym_name.get_raw_content() == next_node.sym_name.get_raw_content()
-                && idx != jdx
-            {
-                SemanticErrorReporter::dup_choice_def(
-                    &curr_node.sym_name,
-                    path.to_str().unwrap(),
-                    cleaned_source,
-                    curr_node.sym_name.get_file_index(),
-                );
-
-                return Err(SemanticError::SemanticFail);
-            }
+            // The duplicate may come from another file than the one being compiled
+            let (path, source) = source_of(
+                curr_node.sym_name.source_id(),
+                path.to_str().unwrap(),
+                cleaned_source,
+            );
+            report_dup(&curr_node.sym_name, &path, &source, curr_node.sym_name.get_file_index());
+
+            return Err(SemanticError::SemanticFail);
         }
     }
 
     Ok(())
 }
 
-pub fn check_for_dup_structs_syms<'semantic>(
+// Binding a name twice in the same block is an error. Binding it again in a nested block
+// shadows it instead.
+pub fn check_for_dup_bindings_in_scope<'semantic>(
     st: &SymbolTable,
     path: &'semantic Path,
     cleaned_source: &'semantic str,
 ) -> Result<(), SemanticError> {
-    for (idx, symbol_node) in st
+    let bindings = st
         .nodes
         .iter()
-        .filter(|sn| sn.sym_ty == Type::Struct)
-        .enumerate()
-    {
-        println!("{symbol_node:#?}");
+        .filter(|sn| sn.kind() != SymbolKind::Global)
+        .collect::<Vec<_>>();
 
-        let curr_node = symbol_node;
+    for (jdx, next_node) in bindings.iter().enumerate()
+    {
+        let bound_before = bindings[..jdx].iter().any(|curr_node| {
+            curr_node.sym_name.symbol() == next_node.sym_name.symbol()
+                && curr_node.sym_scope_depth == next_node.sym_scope_depth
+                && curr_node.sym_scope_breath == next_node.sym_scope_breath
+        });
 
-        for (jdx, next_node) in st
-            .nodes
-            .iter()
-            .filter(|sn| sn.sym_ty == Type::Struct)
-            .enumerate()
+        if bound_before
         {
-            if curr_node.sym_name.get_raw_content() == next_node.sym_name.get_raw_content()
-                && idx != jdx
-            {
-                SemanticErrorReporter::dup_structure_def(
-                    &curr_node.sym_name,
-                    path.to_str().unwrap(),
-                    cleaned_source,
-                    curr_node.sym_name.get_file_index(),
-                );
-
-                return Err(SemanticError::SemanticFail);
-            }
+            let (path, source) = source_of(
+                next_node.sym_name.source_id(),
+                path.to_str().unwrap(),
+                cleaned_source,
+            );
+            SemanticErrorReporter::duplicate_binding_in_scope(
+                &next_node.sym_name,
+                &path,
+                &source,
+                next_node.sym_name.get_file_index(),
+            );
+
+            return Err(SemanticError::SemanticFail);
         }
     }
 
     Ok(())
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use lexer::span::SpanPoint;
+
+    fn ident(raw: &str) -> Token {
+        Token::dummy(raw, TokenKind::Ident)
+    }
+
     #[test]
     fn it_works() {}
+
+    #[test]
+    fn symbol_node_accessors() {
+        let node = SymbolNode::new(ident("count"), Type::Prim(Primitve::U32), 2, 0);
+
+        assert_eq!(node.name().get_raw_content(), "count");
+        assert_eq!(node.ty(), &Type::Prim(Primitve::U32));
+        assert_eq!(node.kind(), SymbolKind::PrimVar);
+        assert_eq!(node.scope_depth(), 2);
+
+        node.refine_sym_kind_to(SymbolKind::FuncParm);
+        assert_eq!(node.kind(), SymbolKind::FuncParm);
+    }
+
+    #[test]
+    fn symbol_node_from_tokens() {
+        let float_ty = Token::new(
+            "float".to_string(),
+            TokenKind::FloatTy,
+            SpanPoint::default(),
+            SpanPoint::default(),
+            0,
+            true,
+        );
+
+        let node = SymbolNode::from_binding(&ident("ratio"), &float_ty, 1, 0);
+        assert_eq!(node.ty(), &Type::Prim(Primitve::F32));
+        assert_eq!(node.kind(), SymbolKind::PrimVar);
+
+        // A structure or choice name is not known to be either yet
+        let node = SymbolNode::from_binding(&ident("p"), &ident("Point"), 1, 0);
+        assert_eq!(node.ty(), &Type::Undetermined);
+
+        let node = SymbolNode::from_function(&ident("add"), 0, 0);
+        assert_eq!(node.name().get_raw_content(), "add");
+        assert_eq!(node.ty(), &Type::Func);
+        assert_eq!(node.kind(), SymbolKind::FuncCall);
+    }
+
+    #[test]
+    fn iterate_and_filter_table() {
+        let mut st = SymbolTable::new();
+        assert!(st.is_empty());
+
+        st.push(SymbolNode::from_function(&ident("add"), 0, 0));
+        st.push(SymbolNode::new(ident("x"), Type::Prim(Primitve::U32), 1, 0));
+        st.push(SymbolNode::from_function(&ident("main"), 0, 0));
+        assert_eq!(st.len(), 3);
+
+        let names = st.iter().map(|node| node.name().get_raw_content()).collect::<Vec<_>>();
+        assert_eq!(names, ["add", "x", "main"]);
+        assert_eq!((&st).into_iter().count(), 3);
+
+        let funcs = st.by_kind(&Type::Func).map(|node| node.name().get_raw_content());
+        assert_eq!(funcs.collect::<Vec<_>>(), ["add", "main"]);
+        assert_eq!(st.by_kind(&Type::Struct).count(), 0);
+    }
+
+    #[test]
+    fn symbol_node_summary() {
+        let node = SymbolNode::new(ident("count"), Type::Prim(Primitve::U32), 2, 0);
+        assert_eq!(node.summary(), "count: int (variable) @2");
+
+        let node = SymbolNode::new(ident("values"), Type::List(Primitve::F32), 1, 0);
+        assert_eq!(node.summary(), "values: [float] (list variable) @1");
+    }
+
+    #[test]
+    fn dup_reported_against_its_own_file() {
+        use lexer::diagnostics::{
+            register_source, set_message_format, take_diagnostics, MessageFormat,
+        };
+        use lexer::lexer::Lexer;
+
+        let source_a = "add :: (int, int) -> int\n";
+        let source_b = "main :: () -> int\nadd :: (int, int) -> int\n";
+
+        let source_id = register_source("b.az", source_b);
+        let tokens = Lexer::new("b.az", source_b).with_source_id(source_id).lex(false).unwrap();
+        let add_in_b = tokens.iter().find(|tok| tok.get_raw_content() == "add").unwrap();
+        assert_eq!(add_in_b.source_id(), source_id);
+
+        let mut st = SymbolTable::new();
+        st.push(SymbolNode::new(add_in_b.clone(), Type::Func, 0, 0));
+        st.push(SymbolNode::new(ident("add"), Type::Func, 0, 0));
+
+        set_message_format(MessageFormat::Json);
+        let result = check_for_dup_funcs_syms(&st, Path::new("a.az"), source_a);
+        set_message_format(MessageFormat::Human);
+
+        assert!(result.is_err());
+        let diagnostics = take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file, "b.az");
+        assert_eq!((diagnostics[0].line, diagnostics[0].col), (2, 1));
+    }
 }
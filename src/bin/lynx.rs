@@ -0,0 +1,209 @@
+//! Lynx, Azalea's build tool
+
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use clap::{Parser as ClapParser, Subcommand};
+use lexer::diagnostics;
+use mac::CompileOptions;
+
+/// Azalea's build tool
+#[derive(ClapParser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Format `.az` files in place. Comments are removed by the preprocessor before parsing, so
+    /// formatting a file drops them.
+    Fmt {
+        /// A `.az` file, or a directory to search for `.az` files
+        path: String,
+
+        /// Do not write anything, and fail if any file is not formatted
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Check `.az` files for errors without building them, reporting every semantic error
+    /// instead of stopping at the first
+    Check {
+        /// A `.az` file, or a directory to search for `.az` files
+        path: String,
+    },
+}
+
+// Every `.az` file at `path`, searching directories recursively
+fn az_files<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if !path.is_dir()
+    {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)?
+    {
+        let entry_path = entry?.path();
+
+        if entry_path.is_dir()
+        {
+            files.extend(az_files(&entry_path)?);
+        }
+        else if entry_path.extension().is_some_and(|ext| ext == "az")
+        {
+            files.push(entry_path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+// Format every `.az` file at `path`, or with `check` only look for files that need it
+//
+// Returns the files that were not formatted
+fn run_fmt(path: &str, check: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let mut unformatted = Vec::new();
+
+    for file in az_files(path)?
+    {
+        let source    = std::fs::read_to_string(&file)?;
+        let formatted = mac::format(&source, &CompileOptions::new().with_path(&file))?;
+
+        if formatted == source
+        {
+            continue;
+        }
+
+        if !check
+        {
+            std::fs::write(&file, &formatted)?;
+        }
+        unformatted.push(file);
+    }
+
+    Ok(unformatted)
+}
+
+// Run every `.az` file at `path` through semantic analysis, with `opts` for everything but the
+// path
+//
+// Returns the number of errors reported
+fn run_check(path: &str, opts: &CompileOptions) -> anyhow::Result<usize> {
+    let reported_before = diagnostics::errors_reported();
+
+    for file in az_files(path)?
+    {
+        let source = std::fs::read_to_string(&file)?;
+        let opts   = opts.clone().with_path(&file).with_keep_going(true);
+
+        // Errors were reported as they were found, so only the count matters here
+        let _ = mac::compile(&source, &opts);
+    }
+
+    Ok(diagnostics::errors_reported() - reported_before)
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command
+    {
+        Command::Fmt { path, check } =>
+        {
+            let unformatted = run_fmt(&path, check)?;
+
+            for file in &unformatted
+            {
+                let verb = if check { "Not formatted" } else { "Formatted" };
+                println!("{verb}: {}", file.display());
+            }
+
+            if check && !unformatted.is_empty()
+            {
+                bail!("{} files are not formatted", unformatted.len());
+            }
+        }
+        Command::Check { path } =>
+        {
+            let errors = run_check(&path, &CompileOptions::new())?;
+
+            if errors > 0
+            {
+                bail!("{errors} errors found");
+            }
+            println!("No errors found");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fmt_subcommand() {
+        let cli = Cli::try_parse_from(["lynx", "fmt", "src", "--check"]).unwrap();
+
+        assert!(matches!(cli.command, Command::Fmt { check: true, .. }));
+    }
+
+    // The `fuzz` feature mutates the source before compiling it
+    #[cfg(not(feature = "fuzz"))]
+    #[test]
+    fn check_reports_every_semantic_error() {
+        use lexer::diagnostics::MessageFormat;
+
+        // Named after this process, so runs at the same time do not share it
+        let dir = std::env::temp_dir().join(format!("lynx_check_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("main.az");
+        let source = "f :: ()\nf = { break; }\nf :: ()\nf = {}\n";
+        std::fs::write(&path, source).unwrap();
+
+        // Collect the reports instead of printing them
+        let opts = CompileOptions::new().with_message_format(MessageFormat::Json);
+        let errors = run_check(path.to_str().unwrap(), &opts).unwrap();
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        // `f` is defined twice, and `break` is outside of a loop
+        assert_eq!(errors, 2);
+        let reported = diagnostics::take_diagnostics();
+        let codes = reported.iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>();
+        assert_eq!(codes, [4, 5]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn formatted_file_is_left_alone() {
+        // Named after this process, so runs at the same time do not share it
+        let scratch = std::env::temp_dir().join(format!("lynx_fmt_{}", std::process::id()));
+        std::fs::create_dir_all(&scratch).unwrap();
+
+        let path = scratch.join("main.az");
+        std::fs::write(&path, "main :: () -> int\nmain = { let x<-1;x }\n").unwrap();
+        let dir = scratch.to_str().unwrap();
+
+        // Needs formatting, so `--check` finds it without touching it
+        assert_eq!(run_fmt(dir, true).unwrap(), vec![path.clone()]);
+        assert_eq!(run_fmt(dir, false).unwrap(), vec![path.clone()]);
+
+        let formatted = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(formatted, "main :: () -> int\nmain = {\n    let x <- 1;\n    x\n}\n");
+
+        // Formatting again is a no-op, and `--check` passes
+        assert!(run_fmt(dir, false).unwrap().is_empty());
+        assert!(run_fmt(dir, true).unwrap().is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), formatted);
+
+        std::fs::remove_dir_all(&scratch).unwrap();
+    }
+}
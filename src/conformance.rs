@@ -0,0 +1,148 @@
+//! Grammar conformance checks for the Morehead Azalea Compiler
+//!
+//! The formal pest grammar in `grammar/azalea_grammar` and the hand-written lexer and parser
+//! describe the same language. These checks run a source file through both and report when one
+//! accepts it and the other does not, so the two do not drift apart.
+
+use std::path::Path;
+
+use lexer::diagnostics;
+use lexer::lexer::Lexer;
+use parser::ast_parser::Parser;
+use pest::Parser as _;
+use pest_derive::Parser as PestParser;
+use symbol_table::SymbolTable;
+
+/// Parser generated from the formal grammar
+#[derive(PestParser)]
+#[grammar = "../grammar/azalea_grammar/formal_grammar.pest"]
+pub struct AzaleaParser;
+
+/// Which side accepted a source file that the other rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// Only the pest grammar accepts the source
+    GrammarOnly,
+
+    /// Only the hand-written parser accepts the source
+    ParserOnly,
+}
+
+/// Does the pest grammar accept `source` as a whole source file?
+pub fn grammar_accepts(source: &str) -> bool {
+    AzaleaParser::parse(Rule::source_file, source).is_ok()
+}
+
+/// Do the hand-written lexer and parser accept `source`? Nothing is reported to the user.
+///
+/// NOTE: `source` is not preprocessed, so it should not have comments in it.
+pub fn parser_accepts(source: &str) -> bool {
+    let path = Path::new("conformance.az");
+
+    diagnostics::silenced(|| {
+        let Ok(tokens) = Lexer::new(path, source).lex(false)
+        else
+        {
+            return false;
+        };
+
+        Parser::new(tokens, path, source)
+            .parse(false, &mut SymbolTable::new())
+            .is_ok()
+    })
+}
+
+/// Run `source` through the pest grammar and the hand-written parser
+///
+/// # Returns
+///
+/// Returns `None` if both accept or both reject `source`, else which one accepted it.
+pub fn divergence(source: &str) -> Option<Divergence> {
+    match (grammar_accepts(source), parser_accepts(source))
+    {
+        (true, false) => Some(Divergence::GrammarOnly),
+        (false, true) => Some(Divergence::ParserOnly),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Small programs covering each construct. Every one ends in whitespace, since the lexer
+    // needs it to finish the last token.
+    const ACCEPTED: &[(&str, &str)] = &[
+        ("empty file", ""),
+        ("function", "add :: (int, int) -> int\nadd a b = { a + b * 2 }\n"),
+        ("inline main", "main :: () -> int { 0 }\n"),
+        ("choice", "Color :: choice { Red, Green, Blue, }\n"),
+        ("structure", "Point :: structure { x :: int, y :: float, tag :: Color }\n"),
+        ("bindings", "main :: ()\nmain = { let x :: int <- 2; let y <- -x; x <- y + 1; }\n"),
+        ("list and struct literals", "main :: ()\nmain = { let xs <- [1, 2]; let p <- P { 1, 2 }; }\n"),
+        ("calls", "main :: ()\nmain = { print(add(1, 2), 3); let y <- add(1, 2); }\n"),
+        (
+            "selection",
+            "main :: ()\nmain = { if x < 1 { y <- 1; } elif x == 2 { y <- 2; } else { y <- 3; } }\n",
+        ),
+        ("loops", "main :: ()\nmain = { while a and b { break; } for i in 0..3 { continue; } }\n"),
+        ("match", "main :: ()\nmain = { match c { Red => { x <- 1; }, Blue => {} } }\n"),
+        (
+            "value blocks",
+            "f :: () -> int\nf = { let y <- if a { 1 } else { { let t <- 2; t } }; y }\n",
+        ),
+        ("function typed input", "apply :: ((int) -> int, int) -> int\napply g x = { 0 + g(x) }\n"),
+        (
+            "operators",
+            "f :: () -> float\nf = { let z <- (a < b) == c or d; -p.x as float + xs[i - 1] }\n",
+        ),
+        ("literals", "f :: ()\nf = { let a <- 'a'; let b <- '\\n'; let c <- true; let d <- 1.5f; }\n"),
+        ("raw ident", "f :: ()\nf = { let r#for <- 5i; r#for }\n"),
+        ("expression statements", "f :: () -> int\nf = { compute(x) + 1; -y; g(x) }\n"),
+    ];
+
+    const REJECTED: &[(&str, &str)] = &[
+        ("missing semicolon", "main :: ()\nmain = { let x <- 1 }\n"),
+        ("chained comparison", "f :: () -> bool\nf = { a < b < c }\n"),
+        ("if value without else", "main :: ()\nmain = { let y <- if x { 1 }; }\n"),
+        ("unclosed paren", "main :: ()\nmain = { let y <- (1 + 2; }\n"),
+        ("double comma", "Color :: choice { Red,, Blue }\n"),
+        ("match arm without arrow", "main :: ()\nmain = { match c { Red { } } }\n"),
+        ("call as value", "main :: ()\nmain = { let y <- f(1) + 2; }\n"),
+        ("keyword binding", "main :: ()\nmain = { let for <- 1; }\n"),
+        ("missing definition", "main :: ()\n"),
+    ];
+
+    #[test]
+    fn grammar_and_parser_agree() {
+        let divergences = ACCEPTED
+            .iter()
+            .chain(REJECTED)
+            .filter_map(|(name, source)| divergence(source).map(|side| (*name, side)))
+            .collect::<Vec<_>>();
+
+        assert!(divergences.is_empty(), "{divergences:?}");
+    }
+
+    #[test]
+    fn corpus_is_accepted_or_rejected_as_expected() {
+        for (name, source) in ACCEPTED
+        {
+            assert!(grammar_accepts(source) && parser_accepts(source), "{name} was rejected");
+        }
+
+        for (name, source) in REJECTED
+        {
+            assert!(!grammar_accepts(source) && !parser_accepts(source), "{name} was accepted");
+        }
+    }
+
+    #[test]
+    fn checks_are_silent() {
+        let reported_before = diagnostics::errors_reported();
+
+        assert!(!parser_accepts("main :: ()\nmain = { let x <- 1 }\n"));
+        assert_eq!(diagnostics::errors_reported(), reported_before);
+        assert_eq!(diagnostics::message_format(), diagnostics::MessageFormat::Human);
+    }
+}
@@ -0,0 +1,563 @@
+//! Library interface of the Morehead Azalea Compiler
+//!
+//! `compile` runs the front end (preprocessor, lexer, parser and semantic checks) over a source
+//! file using the settings in a `CompileOptions`, and `compile_from_path` reads the file first.
+//! The `mac` binary parses its command line into the same `CompileOptions` and runs the stages one
+//! at a time, so it can stop after any of them.
+
+pub mod conformance;
+
+// Taken by the `CompileOptions` builders, so callers do not need to depend on `lexer` themselves
+pub use lexer::diagnostics::{ColorChoice, MessageFormat};
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "fuzz")]
+use fuzzer::{Fuzzer, XORShiftState};
+use lexer::diagnostics::{self, ErrorCounter};
+use lexer::errors::LexError;
+use lexer::lexer::Lexer;
+use lexer::source_map::SourceMap;
+use lexer::token::Token;
+use parser::ast::Program;
+use parser::ast_parser::Parser as AstParser;
+use parser::errors::ParserError;
+use parser::pretty::pretty_print;
+use preprocessor::errors::PreprocessorError;
+use preprocessor::preprocessor::Preprocessor;
+use semantic_analyzer::{
+    check_for_bounds, check_for_loop_control_outside_loop, check_for_missing_varbind,
+    check_match_exhaustiveness, check_param_annotations, check_recursive_structs,
+    check_selection_exhaustiveness, check_self_referential_bindings, check_shadowing,
+    check_struct_field_types, check_unused_bindings, check_unused_functions,
+    resolve_undetermined_types,
+};
+use symbol_table::{
+    check_for_dup_bindings_in_scope, check_for_dup_choice_syms, check_for_dup_funcs_syms,
+    check_for_dup_structs_syms, SemanticError, SymbolTable,
+};
+use thiserror::Error;
+
+/// Failure of one of the compiler stages. Its errors were already reported.
+#[derive(Debug, Error)]
+pub enum CompileError {
+    #[error("could not read `{}`", path.display())]
+    Read {
+        path: PathBuf,
+
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error(transparent)]
+    Preprocess(#[from] PreprocessorError),
+
+    #[error(transparent)]
+    Lex(#[from] LexError),
+
+    #[error(transparent)]
+    Parse(#[from] ParserError),
+
+    #[error(transparent)]
+    Semantic(#[from] SemanticError),
+}
+
+/// Settings for one run of the compiler
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// Path of the source file, as shown in diagnostics
+    path: PathBuf,
+
+    /// Print the token stream
+    verbose_lex: bool,
+
+    /// Print the AST
+    verbose_parse: bool,
+
+    /// Stop reporting errors after this many have been reported
+    max_errors: usize,
+
+    message_format: MessageFormat,
+
+    color: ColorChoice,
+
+    /// Warn when a binding in an inner scope reuses the name of an outer one
+    warn_shadow: bool,
+
+    /// Warn when the indentation of a line mixes tabs and spaces
+    warn_mixed_indent: bool,
+
+    /// Warn about `let` bindings that are never used
+    warn_unused: bool,
+
+    /// Warn about functions other than `main` that are never called
+    warn_unused_functions: bool,
+
+    /// Run every semantic check instead of stopping at the first one that fails
+    keep_going: bool,
+
+    /// Seed used to mutate the source before compiling it
+    #[cfg(feature = "fuzz")]
+    fuzz_seed: usize,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("main.az"),
+            verbose_lex: false,
+            verbose_parse: false,
+            max_errors: 20,
+            message_format: MessageFormat::Human,
+            color: ColorChoice::Auto,
+            warn_shadow: false,
+            warn_mixed_indent: false,
+            warn_unused: false,
+            warn_unused_functions: false,
+            keep_going: false,
+            #[cfg(feature = "fuzz")]
+            fuzz_seed: 2,
+        }
+    }
+}
+
+impl CompileOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.path = path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn with_verbose_lex(mut self, verbose_lex: bool) -> Self {
+        self.verbose_lex = verbose_lex;
+        self
+    }
+
+    pub fn with_verbose_parse(mut self, verbose_parse: bool) -> Self {
+        self.verbose_parse = verbose_parse;
+        self
+    }
+
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    pub fn with_message_format(mut self, message_format: MessageFormat) -> Self {
+        self.message_format = message_format;
+        self
+    }
+
+    pub fn with_color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_warn_shadow(mut self, warn_shadow: bool) -> Self {
+        self.warn_shadow = warn_shadow;
+        self
+    }
+
+    pub fn with_warn_mixed_indent(mut self, warn_mixed_indent: bool) -> Self {
+        self.warn_mixed_indent = warn_mixed_indent;
+        self
+    }
+
+    pub fn with_warn_unused(mut self, warn_unused: bool) -> Self {
+        self.warn_unused = warn_unused;
+        self
+    }
+
+    pub fn with_warn_unused_functions(mut self, warn_unused_functions: bool) -> Self {
+        self.warn_unused_functions = warn_unused_functions;
+        self
+    }
+
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    #[cfg(feature = "fuzz")]
+    pub fn with_fuzz_seed(mut self, fuzz_seed: usize) -> Self {
+        self.fuzz_seed = fuzz_seed;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn max_errors(&self) -> usize {
+        self.max_errors
+    }
+
+    pub fn message_format(&self) -> MessageFormat {
+        self.message_format
+    }
+
+    /// Make every following diagnostic use the message format and colors of these options
+    pub fn apply_diagnostic_settings(&self) {
+        diagnostics::set_message_format(self.message_format);
+        diagnostics::set_color_choice(self.color);
+    }
+
+    /// New `ErrorCounter` that stops reporting after `max_errors`, to share between the stages
+    pub fn error_counter(&self) -> Rc<ErrorCounter> {
+        Rc::new(ErrorCounter::new(self.max_errors))
+    }
+
+    fn path_str(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+/// How long each compiler stage took, in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct StageTimings {
+    entries: Vec<(&'static str, Duration)>,
+}
+
+impl StageTimings {
+    /// Run `stage` and record how long it took under `name`, whether it failed or not
+    pub fn time<T, F: FnOnce() -> T>(&mut self, name: &'static str, stage: F) -> T {
+        let start  = Instant::now();
+        let result = stage();
+        self.entries.push((name, start.elapsed()));
+
+        result
+    }
+
+    /// Time taken by the stage called `name`, if it ran
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.entries
+            .iter()
+            .find(|(stage, _)| *stage == name)
+            .map(|(_, elapsed)| *elapsed)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(&'static str, Duration)> {
+        self.entries.iter()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.entries.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
+
+// One stage per line with its time in milliseconds, then the total
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.entries.iter().copied().chain([("total", self.total())]);
+        for (name, elapsed) in rows
+        {
+            writeln!(f, "{name:<10} {:>10.3} ms", elapsed.as_secs_f64() * 1000.0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Result of a successful `compile`
+#[derive(Debug)]
+pub struct Compiled {
+    /// The checked AST
+    pub ast: Program,
+
+    pub timings: StageTimings,
+}
+
+/// Source file with its comments removed
+pub struct Preprocessed {
+    pub cleaned_source: String,
+
+    /// Maps offsets in `cleaned_source` back to the original source file
+    pub source_map: SourceMap,
+}
+
+/// Remove comments from `source` (after fuzzing it, if the `fuzz` feature is enabled)
+pub fn preprocess(source: &str, opts: &CompileOptions) -> Result<Preprocessed, CompileError> {
+    #[cfg(feature = "fuzz")]
+    let source = &Fuzzer::new(source.to_string(), XORShiftState::new(opts.fuzz_seed)).fuzz();
+
+    remove_comments(source, opts)
+}
+
+// `preprocess` without the fuzzing, for tools that must see the source as it is
+fn remove_comments(source: &str, opts: &CompileOptions) -> Result<Preprocessed, CompileError> {
+    let preprocessor = Preprocessor::new(source.to_string(), opts.path_str())?;
+
+    // Errors in lines after a `#line` directive are reported where the directive says, even the
+    // ones found by the passes that run before directives are stripped
+    let line_directives = preprocessor.get_line_directives().to_vec();
+    diagnostics::register_line_directives(opts.path_str(), line_directives);
+
+    if opts.warn_mixed_indent
+    {
+        preprocessor.warn_mixed_indentation();
+    }
+
+    let preprocessor = preprocessor.run_passes()?;
+
+    // Directives inside of comments were stripped with them, so they no longer count
+    let line_directives = preprocessor.get_line_directives().to_vec();
+    diagnostics::register_line_directives(opts.path_str(), line_directives);
+
+    // Keep track of where cleaned source came from so errors point into the original file
+    let source_map     = preprocessor.get_source_map();
+    let cleaned_source = preprocessor.get_cleaned_sources();
+
+    Ok(Preprocessed { cleaned_source, source_map })
+}
+
+/// Tokenize the preprocessed source; fail fast on error
+pub fn lex(
+    preprocessed: &Preprocessed,
+    opts: &CompileOptions,
+    error_counter: &Rc<ErrorCounter>,
+) -> Result<Vec<Token>, CompileError> {
+    let tokens = Lexer::new(opts.path(), &preprocessed.cleaned_source)
+        .with_source_map(preprocessed.source_map.clone())
+        .with_error_counter(Rc::clone(error_counter))
+        .lex(opts.verbose_lex)
+        .inspect_err(|_| error_counter.print_suppressed())?;
+
+    Ok(tokens)
+}
+
+/// Parse `tokens` into the AST, filling the `SymbolTable` along the way
+pub fn parse(
+    tokens: Vec<Token>,
+    preprocessed: &Preprocessed,
+    opts: &CompileOptions,
+    error_counter: &Rc<ErrorCounter>,
+) -> Result<(Program, SymbolTable), CompileError> {
+    let mut sym_table = SymbolTable::new();
+
+    let ast = AstParser::new(tokens, opts.path(), preprocessed.cleaned_source.as_str())
+        .with_source_map(&preprocessed.source_map)
+        .with_error_counter(Rc::clone(error_counter))
+        .parse(opts.verbose_parse, &mut sym_table)
+        .inspect_err(|_| error_counter.print_suppressed())?;
+
+    Ok((ast, sym_table))
+}
+
+/// Resolve the types of bindings and run every semantic check over the AST
+///
+/// Stops at the first check that fails, unless `opts` says to keep going. Then every check runs
+/// and the first failure is returned.
+pub fn check_semantics(
+    ast: &Program,
+    sym_table: &mut SymbolTable,
+    preprocessed: &Preprocessed,
+    opts: &CompileOptions,
+) -> Result<(), CompileError> {
+    let path           = opts.path();
+    let cleaned_source = preprocessed.cleaned_source.as_str();
+
+    // Give bindings without a type hint the type of their right hand side
+    resolve_undetermined_types(sym_table, ast);
+    let sym_table = &*sym_table;
+
+    type Check<'a> = Box<dyn Fn() -> Result<(), SemanticError> + 'a>;
+    let checks: [Check; 13] = [
+        Box::new(|| check_for_dup_funcs_syms(sym_table, path, cleaned_source)),
+        Box::new(|| check_for_dup_choice_syms(sym_table, path, cleaned_source)),
+        Box::new(|| check_for_dup_structs_syms(sym_table, path, cleaned_source)),
+        Box::new(|| check_for_dup_bindings_in_scope(sym_table, path, cleaned_source)),
+        Box::new(|| check_struct_field_types(sym_table, ast, path, cleaned_source)),
+        Box::new(|| check_recursive_structs(ast, path, cleaned_source)),
+        Box::new(|| check_param_annotations(sym_table, ast, path, cleaned_source)),
+        Box::new(|| check_for_missing_varbind(sym_table, ast)),
+        Box::new(|| check_self_referential_bindings(ast, path, cleaned_source)),
+        Box::new(|| check_for_loop_control_outside_loop(ast, path, cleaned_source)),
+        Box::new(|| check_for_bounds(ast, path, cleaned_source)),
+        Box::new(|| check_match_exhaustiveness(sym_table, ast, path, cleaned_source)),
+        Box::new(|| check_selection_exhaustiveness(ast, path, cleaned_source)),
+    ];
+
+    if opts.warn_shadow
+    {
+        check_shadowing(ast, path, cleaned_source);
+    }
+
+    if opts.warn_unused
+    {
+        check_unused_bindings(ast, path, cleaned_source);
+    }
+
+    if opts.warn_unused_functions
+    {
+        check_unused_functions(ast, path, cleaned_source);
+    }
+
+    let mut first_failure = None;
+    for check in checks
+    {
+        if let Err(err) = check()
+        {
+            if !opts.keep_going
+            {
+                return Err(err.into());
+            }
+            first_failure.get_or_insert(err);
+        }
+    }
+
+    first_failure.map_or(Ok(()), |err| Err(err.into()))
+}
+
+/// Run every stage of the compiler over `source`
+///
+/// # Returns
+///
+/// Returns the checked AST and how long each stage took, or the error of the first stage that
+/// failed. Errors are reported as they are found, using the message format and colors of `opts`.
+pub fn compile(source: &str, opts: &CompileOptions) -> Result<Compiled, CompileError> {
+    opts.apply_diagnostic_settings();
+
+    // Shared by every stage so we stop reporting after `max_errors`
+    let error_counter = opts.error_counter();
+    let mut timings   = StageTimings::default();
+
+    let preprocessed = timings.time("preprocess", || preprocess(source, opts))?;
+    let tokens       = timings.time("lex", || lex(&preprocessed, opts, &error_counter))?;
+    let (ast, mut symbols) =
+        timings.time("parse", || parse(tokens, &preprocessed, opts, &error_counter))?;
+    timings.time("semantic", || check_semantics(&ast, &mut symbols, &preprocessed, opts))?;
+
+    Ok(Compiled { ast, timings })
+}
+
+/// Read the source file at `path`
+pub fn read_source(path: &Path) -> Result<String, CompileError> {
+    std::fs::read_to_string(path)
+        .map_err(|source| CompileError::Read { path: path.to_path_buf(), source })
+}
+
+/// Read the source file at `path` and run every stage of the compiler over it (see `compile`)
+///
+/// Diagnostics name `path`, whatever the path in `opts` is.
+pub fn compile_from_path(path: &Path, opts: &CompileOptions) -> Result<Program, CompileError> {
+    let source = read_source(path)?;
+    let opts   = opts.clone().with_path(path);
+
+    Ok(compile(&source, &opts)?.ast)
+}
+
+/// Parse `source` and print it back out formatted (see `parser::pretty`)
+///
+/// Comments are removed by the preprocessor before parsing, so the formatted source has none.
+pub fn format(source: &str, opts: &CompileOptions) -> Result<String, CompileError> {
+    opts.apply_diagnostic_settings();
+
+    let error_counter = opts.error_counter();
+    let preprocessed  = remove_comments(source, opts)?;
+    let tokens        = lex(&preprocessed, opts, &error_counter)?;
+    let (ast, _)      = parse(tokens, &preprocessed, opts, &error_counter)?;
+
+    Ok(pretty_print(&ast))
+}
+
+// The `fuzz` feature mutates the source before compiling it
+#[cfg(all(test, not(feature = "fuzz")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_valid_source() {
+        let compiled = compile("main :: () -> int\nmain = { 0 }\n", &CompileOptions::new());
+
+        assert_eq!(compiled.unwrap().ast.declarations.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn compile_source_with_bom() {
+        let opts = CompileOptions::new().with_message_format(MessageFormat::Json);
+
+        let compiled = compile("\u{feff}main :: () -> int\nmain = { 0 }\n", &opts);
+        assert_eq!(compiled.unwrap().ast.declarations.unwrap().len(), 1);
+
+        // Errors on the first line are not pushed over by the BOM
+        let result = compile("\u{feff}main 1a :: () -> int\nmain = { 0 }\n", &opts);
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert!(matches!(result, Err(CompileError::Lex(_))));
+        let reported = diagnostics::take_diagnostics();
+        assert_eq!((reported[0].line, reported[0].col), (1, 6));
+    }
+
+    #[test]
+    fn compile_times_every_stage() {
+        let compiled = compile("main :: () -> int\nmain = { 0 }\n", &CompileOptions::new());
+        let timings  = compiled.unwrap().timings;
+
+        let stages = timings.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(stages, ["preprocess", "lex", "parse", "semantic"]);
+        assert!(timings.get("parse").unwrap() <= timings.total());
+        assert_eq!(timings.to_string().lines().count(), 5);
+    }
+
+    #[test]
+    fn compile_stops_reporting_at_max_errors() {
+        let opts = CompileOptions::new()
+            .with_path("max_errors.az")
+            .with_max_errors(2)
+            .with_message_format(MessageFormat::Json);
+
+        // Each number runs into a letter
+        let result = compile("main :: () -> int\nmain = { 1a + 2b + 3c + 4d }\n", &opts);
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert!(matches!(result, Err(CompileError::Lex(_))));
+
+        let reported = diagnostics::take_diagnostics();
+        assert_eq!(reported.len(), 2);
+        assert!(reported.iter().all(|diagnostic| diagnostic.file == "max_errors.az"));
+    }
+
+    #[test]
+    fn line_directive_remaps_errors() {
+        let opts = CompileOptions::new()
+            .with_path("generated.az")
+            .with_message_format(MessageFormat::Json);
+
+        let source = "main :: () -> int\n#line 40 \"main.template\"\nmain = {\n  1a }\n";
+        let result = compile(source, &opts);
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert!(matches!(result, Err(CompileError::Lex(_))));
+
+        // `1a` is on line 4, which is the second line after the directive
+        let reported = diagnostics::take_diagnostics();
+        assert_eq!(reported.len(), 1);
+        assert_eq!((reported[0].file.as_str(), reported[0].line), ("main.template", 41));
+        assert_eq!(reported[0].col, 3);
+    }
+
+    #[test]
+    fn line_directive_remaps_preprocessor_errors() {
+        let opts = CompileOptions::new()
+            .with_path("generated.az")
+            .with_message_format(MessageFormat::Json);
+
+        // Bad characters are rejected before directives are stripped
+        let source = "main :: () -> int\n#line 40 \"main.template\"\nmain = {\n  1 @ }\n";
+        let result = compile(source, &opts);
+        diagnostics::set_message_format(MessageFormat::Human);
+
+        assert!(matches!(result, Err(CompileError::Preprocess(_))));
+
+        let reported = diagnostics::take_diagnostics();
+        assert_eq!(reported.len(), 1);
+        assert_eq!((reported[0].file.as_str(), reported[0].line), ("main.template", 41));
+        assert_eq!(reported[0].col, 5);
+    }
+}
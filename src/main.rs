@@ -1,17 +1,14 @@
 use std::io::Write;
-use std::{io::Read, path::Path};
-use std::fs;
+use std::path::Path;
 
+#[cfg(feature = "fuzz")]
 use fuzzer::{Fuzzer, XORShiftState};
-use lexer::lexer::Lexer;
-use parser::ast_parser::Parser as AstParser;
-use parser::ast;
-use preprocessor::preprocessor::Preprocessor;
+use lexer::diagnostics;
+use mac::{ColorChoice, CompileOptions, MessageFormat, StageTimings};
 use symbol_table::SymbolTable;
-use symbol_table::{check_for_dup_funcs_syms, check_for_dup_choice_syms, check_for_dup_structs_syms};
-use semantic_analyzer::check_for_missing_varbind;
 
 use clap::Parser as ClapParser;
+use clap::ValueEnum;
 
 
 /// Azalea compiler (Dalton's capstone)
@@ -22,42 +19,273 @@ struct Args {
     #[arg(short, long)]
     source_path: String,
 
+    /// Print the token stream and keep compiling (see `--stop-after lex`)
     #[arg(long)]
     verbose_lex: bool,
 
+    /// Print the AST and keep compiling (see `--stop-after parse`)
     #[arg(long)]
-    verbose_parse: bool
+    verbose_parse: bool,
+
+    /// Print the symbol table after parsing
+    #[arg(long)]
+    dump_symbols: bool,
+
+    /// Write the source, as cleaned by the preprocessor, to this path (or stdout without one)
+    /// before lexing it
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    dump_cleaned: Option<String>,
+
+    /// Stop after the given stage and print its result
+    #[arg(long, value_enum)]
+    stop_after: Option<Stage>,
+
+    /// Stop reporting errors after this many have been reported
+    #[arg(long, default_value_t = 20)]
+    max_errors: usize,
+
+    /// Warn when a binding in an inner scope reuses the name of an outer one
+    #[arg(long)]
+    warn_shadow: bool,
+
+    /// Warn when the indentation of a line mixes tabs and spaces
+    #[arg(long)]
+    warn_mixed_indent: bool,
+
+    /// Warn about `let` bindings that are never used
+    #[arg(long)]
+    warn_unused: bool,
+
+    /// Warn about functions other than `main` that are never called
+    #[arg(long)]
+    warn_unused_functions: bool,
+
+    /// Print how long each stage took, in milliseconds, once compiling is done
+    #[arg(long)]
+    time_report: bool,
+
+    /// Hide the `[x/4]` progress lines and the symbol table dump
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Print errors as pretty reports (`human`) or as one JSON object per line (`json`)
+    #[arg(long, value_enum, default_value_t = MessageFormatArg::Human)]
+    message_format: MessageFormatArg,
+
+    /// Print pretty reports with colors always, never, or only when stdout is a terminal
+    #[arg(long, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+
+    /// Fuzz the source file this many times instead of compiling it, saving every input that
+    /// crashes the compiler to `crashes/`
+    #[cfg(feature = "fuzz")]
+    #[arg(long)]
+    fuzz_iterations: Option<usize>,
+
+    /// Fuzz the source file this many times, parsing each input with both the formal grammar and
+    /// the compiler, and save every input only one of them accepts to `divergences/`
+    #[cfg(feature = "fuzz")]
+    #[arg(long)]
+    fuzz_differential: Option<usize>,
 }
 
-fn source_file_to_string<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
-    // Open file using with a buffer
-    let file = std::fs::File::open(path)?;
+/// Stages of the compiler, in the order they run
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    /// Print the cleaned source
+    Preprocess,
+    /// Print the token stream
+    Lex,
+    /// Print the AST
+    Parse,
+    /// Print the symbol table
+    Semantic,
+}
 
-    // Create the `BufReader` to read lines
-    let mut reader = std::io::BufReader::new(&file);
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MessageFormatArg {
+    Human,
+    Json,
+}
 
-    // Read file as string
-    let mut content: String = String::with_capacity(reader.capacity());
-    reader.read_to_string(&mut content)?;
+impl From<MessageFormatArg> for MessageFormat {
+    fn from(arg: MessageFormatArg) -> Self {
+        match arg
+        {
+            MessageFormatArg::Human => MessageFormat::Human,
+            MessageFormatArg::Json => MessageFormat::Json,
+        }
+    }
+}
 
-    Ok(content)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorArg {
+    Auto,
+    Always,
+    Never,
 }
 
-fn run_fuzzer(source_content: String) -> String {
-    // Create `Fuzzer` and load it with the source file
-    let seed           = 2;
-    let mut fuzzer     = Fuzzer::new(source_content, XORShiftState::new(seed));
-    let source_content = fuzzer.fuzz();
+impl From<ColorArg> for ColorChoice {
+    fn from(arg: ColorArg) -> Self {
+        match arg
+        {
+            ColorArg::Auto => ColorChoice::Auto,
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+        }
+    }
+}
 
-    source_content
+impl From<&Args> for CompileOptions {
+    fn from(args: &Args) -> Self {
+        CompileOptions::new()
+            .with_path(&args.source_path)
+            .with_verbose_lex(args.verbose_lex)
+            .with_verbose_parse(args.verbose_parse)
+            .with_max_errors(args.max_errors)
+            .with_message_format(args.message_format.into())
+            .with_color(args.color.into())
+            .with_warn_shadow(args.warn_shadow)
+            .with_warn_mixed_indent(args.warn_mixed_indent)
+            .with_warn_unused(args.warn_unused)
+            .with_warn_unused_functions(args.warn_unused_functions)
+    }
 }
 
-fn seralize_ast_to_path<P: AsRef<Path>>(ast: &ast::Program, path: P) -> anyhow::Result<()> {
+// Run the preprocessor, lexer and parser over `input` without reporting any errors
+#[cfg(feature = "fuzz")]
+fn run_front_end_quietly(path: &str, input: &[u8]) -> anyhow::Result<()> {
+    use std::rc::Rc;
+
+    use lexer::diagnostics::ErrorCounter;
+    use lexer::lexer::Lexer;
+    use parser::ast_parser::Parser as AstParser;
+    use preprocessor::preprocessor::Preprocessor;
+
+    // Count errors, but never report them
+    let error_counter = Rc::new(ErrorCounter::new(0));
+
+    // Lex the raw bytes first, since the preprocessor only takes valid UTF-8
+    let _ = Lexer::from_bytes(path, input)
+        .with_error_counter(Rc::clone(&error_counter))
+        .lex(false);
+
+    let source       = String::from_utf8_lossy(input).to_string();
+    let preprocessor = Preprocessor::new(source, path)?
+        .normalize_to_ascii()?
+        .remove_multiline_comment()?
+        .remove_singleline_comments();
+    let cleaned_source = preprocessor.get_cleaned_sources();
+
+    let tokens = Lexer::new(path, &cleaned_source)
+        .with_error_counter(Rc::clone(&error_counter))
+        .lex(false)?;
+
+    AstParser::new(tokens, Path::new(path), cleaned_source.as_str())
+        .with_error_counter(error_counter)
+        .parse(false, &mut SymbolTable::new())?;
+
+    Ok(())
+}
+
+// Fuzz the seed file `iterations` times, each time with a new random seed, and write every input
+// that makes the compiler panic into `crash_dir`
+//
+// Returns the number of crashes found
+#[cfg(feature = "fuzz")]
+fn run_fuzzer_corpus<P: AsRef<Path>>(
+    path: &str,
+    iterations: usize,
+    crash_dir: P,
+) -> anyhow::Result<usize> {
+    let seed_content = mac::read_source(Path::new(path))?;
+    let mut crashes  = 0;
+
+    for seed in 1..=iterations
+    {
+        let mut fuzzer = Fuzzer::new(seed_content.clone(), XORShiftState::new(seed));
+        let input      = fuzzer.fuzz_to_bytes();
+
+        // The preprocessor reports errors without an `ErrorCounter`, so collect and drop them
+        let format = diagnostics::message_format();
+        diagnostics::set_message_format(MessageFormat::Json);
+
+        // Errors are expected on fuzzed input, only panics are crashes
+        let outcome = std::panic::catch_unwind(|| run_front_end_quietly(path, &input));
+
+        diagnostics::take_diagnostics();
+        diagnostics::set_message_format(format);
+
+        // Every token of this input is gone, so its names need not stay interned
+        lexer::interner::clear_interner();
+
+        if outcome.is_err()
+        {
+            std::fs::create_dir_all(&crash_dir)?;
+            std::fs::write(crash_dir.as_ref().join(format!("crash-{seed}.az")), &input)?;
+            crashes += 1;
+        }
+    }
+
+    Ok(crashes)
+}
+
+// Parse the seed file, then `iterations` fuzzed versions of it, with both the pest grammar and the
+// hand-written parser, and write every input that only one of them accepts into `divergence_dir`
+//
+// Returns the iterations that diverged, where iteration 0 is the unmutated seed
+#[cfg(feature = "fuzz")]
+fn run_differential_corpus<P: AsRef<Path>>(
+    path: &str,
+    iterations: usize,
+    divergence_dir: P,
+) -> anyhow::Result<Vec<usize>> {
+    use mac::conformance::{self, Divergence};
+
+    let seed_content = mac::read_source(Path::new(path))?;
+    let mut diverged = Vec::new();
+
+    for seed in 0..=iterations
+    {
+        let input = if seed == 0
+        {
+            seed_content.clone()
+        }
+        else
+        {
+            Fuzzer::new(seed_content.clone(), XORShiftState::new(seed)).fuzz()
+        };
+
+        // The hand-written parser still panics on some inputs, which counts as rejecting them
+        let parser_accepts =
+            std::panic::catch_unwind(|| conformance::parser_accepts(&input)).unwrap_or(false);
+        let grammar_accepts = conformance::grammar_accepts(&input);
+        lexer::interner::clear_interner();
+
+        let side = match (grammar_accepts, parser_accepts)
+        {
+            (true, false) => Divergence::GrammarOnly,
+            (false, true) => Divergence::ParserOnly,
+            _ => continue,
+        };
+
+        std::fs::create_dir_all(&divergence_dir)?;
+        std::fs::write(divergence_dir.as_ref().join(format!("divergence-{seed}.az")), &input)?;
+        println!("divergence-{seed}.az: {side:?}");
+        diverged.push(seed);
+    }
+
+    Ok(diverged)
+}
+
+#[cfg(feature = "serialize")]
+fn seralize_ast_to_path<P: AsRef<Path>>(ast: &parser::ast::Program, path: P) -> anyhow::Result<()> {
     // Seralize AST into JSON 
+
     let serialized = serde_json::to_string_pretty(ast)?;
 
     // Open file for writing 
-    let file = fs::OpenOptions::new()
+    let file = std::fs::OpenOptions::new()
 	.write(true)
 	.truncate(true)
 	.open(path)?;
@@ -71,54 +299,160 @@ fn seralize_ast_to_path<P: AsRef<Path>>(ast: &ast::Program, path: P) -> anyhow::
     Ok(())
 }
 
-fn run_compiler() -> anyhow::Result<()> {
+// Write the cleaned source to `path`, or to `out` if `path` is `-`
+fn dump_cleaned<W: Write>(cleaned_source: &str, path: &str, out: &mut W) -> std::io::Result<()> {
+    if path == "-"
+    {
+        write!(out, "{cleaned_source}")?;
+        return Ok(());
+    }
 
-    let args = Args::parse();
+    std::fs::write(path, cleaned_source)
+}
 
+// Format the symbol table as a table with one symbol per row
+fn format_symbol_table(sym_table: &SymbolTable) -> String {
+    let header = ["name", "type", "kind", "depth"];
+    let rows   = sym_table
+        .iter()
+        .map(|node| {
+            [
+                node.name().get_raw_content().to_string(),
+                node.ty().to_string(),
+                node.kind().to_string(),
+                node.scope_depth().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    // Every column is as wide as its widest cell
+    let mut widths = header.map(str::len);
+    for row in &rows
+    {
+        for (width, cell) in widths.iter_mut().zip(row)
+        {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: [&str; 4]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        format!("{}\n", line.trim_end())
+    };
+
+    let mut table = format_row(header);
+    for row in &rows
+    {
+        table.push_str(&format_row(row.each_ref().map(String::as_str)));
+    }
+
+    table
+}
+
+// Write a progress line to `out` unless `--quiet` was given or stdout is kept clean for tools
+// reading JSON diagnostics
+fn log_progress<W: Write>(args: &Args, out: &mut W, message: &str) -> std::io::Result<()> {
+    if !args.quiet && args.message_format == MessageFormatArg::Human
+    {
+        writeln!(out, "{message}")?;
+    }
+
+    Ok(())
+}
+
+// Compile the file given in `args`, writing progress and dumps to `out` (stdout outside of tests).
+// Diagnostics are still printed on their own.
+fn run_compiler<W: Write>(
+    args: &Args,
+    timings: &mut StageTimings,
+    out: &mut W,
+) -> anyhow::Result<()> {
     // Read source file content as a `String`
     let path: &str     = args.source_path.as_str();
-    let source_content = source_file_to_string(path)?;
-    
+    let source_content = mac::read_source(Path::new(path))?;
 
-    // Fuzz the source code if "fuzz" feature is enabled
     #[cfg(feature = "fuzz")]
-    let source_content = run_fuzzer(source_content);
+    if let Some(iterations) = args.fuzz_iterations
+    {
+        let crashes = run_fuzzer_corpus(path, iterations, "crashes")?;
+        writeln!(out, "{crashes} crashes found in {iterations} iterations")?;
 
-    // Create `Preprocessor` and load it with the source file
-    let preprocessor = Preprocessor::new(source_content, path)?;
-    
-    // Remove comments from source file and return a cleaned version
-    println!("[1/4] Preprocessing source...");
-    let cleaned_source = preprocessor
-        .normalize_to_ascii()?
-        .remove_multiline_comment()?
-        .remove_singleline_comments()
-        .get_cleaned_sources();
+        return Ok(());
+    }
 
-    // Create `Lexer`
-    let mut lexer = Lexer::new(path, &cleaned_source);
+    #[cfg(feature = "fuzz")]
+    if let Some(iterations) = args.fuzz_differential
+    {
+        let diverged = run_differential_corpus(path, iterations, "divergences")?;
+        writeln!(out, "{} divergences found in {iterations} iterations", diverged.len())?;
 
-    // Tokenize the source file; fail fast on error
-    println!("[2/4] Tokenizing source...");
-    let tokens = lexer.lex(args.verbose_lex)?;
+        return Ok(());
+    }
 
-    // Create `Parser` using the tokens
-    let path   = std::path::Path::new(path);
-    let parser = AstParser::new(tokens, path, cleaned_source.as_str());
+    let opts = CompileOptions::from(args);
 
-    // Intialize `SymbolTable`
-    let mut sym_table = SymbolTable::new();
+    // Remove comments from source file and return a cleaned version
+    log_progress(args, out, "[1/4] Preprocessing source...")?;
+    let preprocessed = timings.time("preprocess", || mac::preprocess(&source_content, &opts))?;
 
-    // Parse tokens into the abstract syntax tree with `parser`
-    println!("[3/4] Parsing tokens...");
-    let ast = parser.parse(args.verbose_parse, &mut sym_table)?;
+    if let Some(dump_path) = &args.dump_cleaned
+    {
+        dump_cleaned(&preprocessed.cleaned_source, dump_path, out)?;
+    }
 
-    println!("{sym_table:#?}");
+    if args.stop_after == Some(Stage::Preprocess)
+    {
+        writeln!(out, "{}", preprocessed.cleaned_source)?;
+        return Ok(());
+    }
 
-    check_for_dup_funcs_syms(&sym_table, path, cleaned_source.as_str())?;
-    check_for_dup_choice_syms(&sym_table, path, cleaned_source.as_str())?;
-    check_for_dup_structs_syms(&sym_table, path, cleaned_source.as_str())?;
-    check_for_missing_varbind(&sym_table, &ast)?;
+    // Shared by every stage so we stop reporting after `max_errors`
+    let error_counter = opts.error_counter();
+
+    // Tokenize the source file; fail fast on error
+    log_progress(args, out, "[2/4] Tokenizing source...")?;
+    let tokens = timings.time("lex", || mac::lex(&preprocessed, &opts, &error_counter))?;
+
+    if args.stop_after == Some(Stage::Lex)
+    {
+        writeln!(out, "{tokens:#?}")?;
+        return Ok(());
+    }
+
+    // Parse tokens into the abstract syntax tree
+    log_progress(args, out, "[3/4] Parsing tokens...")?;
+    let (ast, mut sym_table) =
+        timings.time("parse", || mac::parse(tokens, &preprocessed, &opts, &error_counter))?;
+
+    if args.stop_after == Some(Stage::Parse)
+    {
+        writeln!(out, "{ast:#?}")?;
+        return Ok(());
+    }
+
+    let checked = timings.time("semantic", || {
+        mac::check_semantics(&ast, &mut sym_table, &preprocessed, &opts)
+    });
+
+    // Dump the symbols even if a check failed, since they help to find out why. Stopping after
+    // the semantic checks prints them as its output, but only once however it was asked for.
+    let stop_after_semantic = args.stop_after == Some(Stage::Semantic);
+    if (args.dump_symbols && !args.quiet) || (stop_after_semantic && checked.is_ok())
+    {
+        write!(out, "{}", format_symbol_table(&sym_table))?;
+    }
+    checked?;
+
+    if stop_after_semantic
+    {
+        return Ok(());
+    }
 
     // Seralize AST to disk for analysis (can be disabled!)
     #[cfg(feature = "serialize")]
@@ -128,8 +462,191 @@ fn run_compiler() -> anyhow::Result<()> {
 }
 
 fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    CompileOptions::from(&args).apply_diagnostic_settings();
+
     // Run the Morehead Azalea Compiler
-    run_compiler()?;
+    let mut timings = StageTimings::default();
+    let result      = run_compiler(&args, &mut timings, &mut std::io::stdout());
+
+    // On stderr, so it never mixes with JSON diagnostics
+    if args.time_report
+    {
+        eprint!("{timings}");
+    }
+
+    // JSON diagnostics are collected while compiling, so print them whether or not we failed
+    if args.message_format == MessageFormatArg::Json
+    {
+        diagnostics::print_diagnostics();
+    }
+
+    result
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    // Temporary directory for one test, named after the test and this process so runs at the
+    // same time do not share it. It is removed once the test is done with it.
+    struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        fn new(test_name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("mac_{test_name}_{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+
+            ScratchDir(dir)
+        }
+
+        // Write `source` to a file named `name` in the directory and return its path
+        fn write_source(&self, name: &str, source: &str) -> String {
+            let path = self.0.join(name);
+            std::fs::write(&path, source).unwrap();
+
+            path.to_str().unwrap().to_string()
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // The `fuzz` feature mutates the source before compiling it
+    #[cfg(not(feature = "fuzz"))]
+    #[test]
+    fn stop_after_lex_skips_parsing() {
+        // Lexes fine, but `let` is missing its `<-`
+        let scratch = ScratchDir::new("stop_after_lex");
+        let path = scratch.write_source("main.az", "main :: ()\nmain = { let x 5; }\n");
+
+        let args = Args::try_parse_from(["mac", "-s", &path]).unwrap();
+        assert!(run_compiler(&args, &mut StageTimings::default(), &mut Vec::new()).is_err());
+
+        let args = Args::try_parse_from(["mac", "-s", &path, "--stop-after", "lex"]).unwrap();
+        assert!(run_compiler(&args, &mut StageTimings::default(), &mut Vec::new()).is_ok());
+    }
+
+    // The `fuzz` feature mutates the source before compiling it
+    #[cfg(not(feature = "fuzz"))]
+    #[test]
+    fn dump_cleaned_blanks_comments() {
+        let source = "main :: () -> int // entry point\n/* a\n b */ main = { 0 }\n";
+        let scratch = ScratchDir::new("dump_cleaned");
+        let path   = scratch.write_source("main.az", source);
+        let dump   = scratch.0.join("cleaned.txt");
+        let dump   = dump.to_str().unwrap();
+
+        let args = Args::try_parse_from(["mac", "-s", &path, "-q", "--dump-cleaned", dump]);
+        run_compiler(&args.unwrap(), &mut StageTimings::default(), &mut Vec::new()).unwrap();
+
+        // Comments are removed, but every line stays where it was
+        let cleaned = std::fs::read_to_string(dump).unwrap();
+        assert!(!cleaned.contains("//") && !cleaned.contains("/*") && !cleaned.contains("entry"));
+        assert_eq!(cleaned.lines().count(), source.lines().count());
+        assert!(cleaned.lines().nth(2).unwrap().contains("main = { 0 }"));
+
+        // Without a path it goes to stdout
+        let args = Args::try_parse_from(["mac", "-s", &path, "--dump-cleaned"]).unwrap();
+        assert_eq!(args.dump_cleaned.as_deref(), Some("-"));
+    }
+
+    // The `fuzz` feature mutates the source before compiling it
+    #[cfg(not(feature = "fuzz"))]
+    #[test]
+    fn quiet_hides_progress() {
+        let scratch = ScratchDir::new("quiet");
+        let path = scratch.write_source("main.az", "main :: () -> int\nmain = { 0 }\n");
+        let compile = |quiet: &[&str]| {
+            let args = [&["mac", "-s", &path, "--dump-symbols"], quiet].concat();
+            let args = Args::try_parse_from(args);
+
+            let mut out = Vec::new();
+            let _ = run_compiler(&args.unwrap(), &mut StageTimings::default(), &mut out);
+            String::from_utf8(out).unwrap()
+        };
+
+        let loud = compile(&[]);
+        assert!(loud.contains("[1/4]") && loud.contains("depth"));
+
+        assert_eq!(compile(&["--quiet"]), "");
+    }
+
+    // The `fuzz` feature mutates the source before compiling it
+    #[cfg(not(feature = "fuzz"))]
+    #[test]
+    fn symbols_are_printed_once() {
+        let scratch = ScratchDir::new("print_symbols");
+        let path = scratch.write_source("main.az", "main :: () -> int\nmain = { 0 }\n");
+        let args = ["mac", "-s", &path, "--stop-after", "semantic", "--dump-symbols"];
+
+        let mut out = Vec::new();
+        run_compiler(&Args::try_parse_from(args).unwrap(), &mut StageTimings::default(), &mut out)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap().matches("depth").count(), 1);
+    }
+
+    #[test]
+    fn color_arg() {
+        let args = Args::try_parse_from(["mac", "-s", "main.az"]).unwrap();
+        assert_eq!(args.color, ColorArg::Auto);
+
+        let args = Args::try_parse_from(["mac", "-s", "main.az", "--color", "never"]).unwrap();
+        assert_eq!(ColorChoice::from(args.color), ColorChoice::Never);
+
+        assert!(Args::try_parse_from(["mac", "-s", "main.az", "--color", "sometimes"]).is_err());
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    fn clean_seed_records_no_crashes() {
+        let scratch   = ScratchDir::new("fuzz_seed");
+        let path      = scratch.write_source("main.az", "main :: () -> int { 0 }\n");
+        let crash_dir = scratch.0.join("crashes");
+
+        let crashes = run_fuzzer_corpus(&path, 5, &crash_dir).unwrap();
+
+        assert_eq!(crashes, 0);
+        assert!(!crash_dir.exists());
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    fn unmutated_seed_does_not_diverge() {
+        let source = "add :: (int, int) -> int\nadd a b = { a + b }\n";
+        let scratch = ScratchDir::new("differential_seed");
+        let path   = scratch.write_source("main.az", source);
+        let dir    = scratch.0.join("divergences");
+
+        let diverged = run_differential_corpus(&path, 5, &dir).unwrap();
+
+        assert!(!diverged.contains(&0), "the seed itself diverged");
+        assert_eq!(dir.exists(), !diverged.is_empty());
+    }
+
+    #[test]
+    fn symbol_table_dump() {
+        use lexer::token::{Token, TokenKind};
+        use symbol_table::{Primitve, SymbolNode, Type};
+
+        let ident = |raw: &str| Token::dummy(raw, TokenKind::Ident);
+
+        let mut sym_table = SymbolTable::new();
+        sym_table.push(SymbolNode::new(ident("add_two"), Type::Func, 0, 0));
+        sym_table.push(SymbolNode::new(ident("x"), Type::Prim(Primitve::F32), 1, 0));
+
+        assert_eq!(
+            format_symbol_table(&sym_table),
+            "name     type      kind      depth\n\
+             add_two  function  function  0\n\
+             x        float     variable  1\n"
+        );
+    }
 }
@@ -0,0 +1,22 @@
+// The `fuzz` feature mutates the source before compiling it
+#![cfg(not(feature = "fuzz"))]
+
+use std::path::Path;
+
+use mac::{compile_from_path, CompileError, CompileOptions};
+
+#[test]
+fn compiles_fixture_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/two_functions.az");
+
+    let program = compile_from_path(&path, &CompileOptions::new()).unwrap();
+
+    assert_eq!(program.declarations.map_or(0, |decls| decls.len()), 3);
+}
+
+#[test]
+fn missing_file_is_a_read_error() {
+    let result = compile_from_path(Path::new("tests/fixtures/missing.az"), &CompileOptions::new());
+
+    assert!(matches!(result, Err(CompileError::Read { .. })));
+}
@@ -0,0 +1,71 @@
+//! Golden AST snapshots
+//!
+//! Every `.az` file in `tests/corpus/` is parsed and its AST, serialized as JSON, is compared
+//! with the `.json` file of the same name next to it. Run with `AZALEA_UPDATE_SNAPSHOTS=1` to
+//! write the snapshots instead, then review the diff before committing it.
+
+// The `fuzz` feature mutates the source before compiling it
+#![cfg(not(feature = "fuzz"))]
+
+use std::path::{Path, PathBuf};
+
+use mac::CompileOptions;
+
+// Parse the source file at `path` and serialize its AST
+fn serialized_ast(path: &Path) -> String {
+    let opts          = CompileOptions::new().with_path(path);
+    let error_counter = opts.error_counter();
+
+    let source       = mac::read_source(path).unwrap();
+    let preprocessed = mac::preprocess(&source, &opts).unwrap();
+    let tokens       = mac::lex(&preprocessed, &opts, &error_counter).unwrap();
+    let (ast, _)     = mac::parse(tokens, &preprocessed, &opts, &error_counter).unwrap();
+
+    serde_json::to_string_pretty(&ast).unwrap() + "\n"
+}
+
+// Every `.az` file in the corpus, in a stable order
+fn corpus_files() -> Vec<PathBuf> {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+
+    let mut files = std::fs::read_dir(corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "az"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    files
+}
+
+#[test]
+fn ast_matches_snapshot() {
+    let update = std::env::var_os("AZALEA_UPDATE_SNAPSHOTS").is_some_and(|val| val == "1");
+
+    let files = corpus_files();
+    assert!(!files.is_empty(), "the corpus has no `.az` files");
+
+    let mut mismatched = Vec::new();
+    for file in files
+    {
+        let actual   = serialized_ast(&file);
+        let snapshot = file.with_extension("json");
+
+        if update
+        {
+            std::fs::write(&snapshot, actual).unwrap();
+            continue;
+        }
+
+        if std::fs::read_to_string(&snapshot).ok().as_deref() != Some(actual.as_str())
+        {
+            mismatched.push(file.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "AST differs from its snapshot for {mismatched:?} (rerun with \
+         `AZALEA_UPDATE_SNAPSHOTS=1` to update them)"
+    );
+}